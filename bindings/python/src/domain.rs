@@ -28,6 +28,11 @@ impl AccountDomain {
         self.0.alias.clone()
     }
 
+    #[getter]
+    pub fn commodities(&self) -> Vec<String> {
+        self.0.commodities.clone()
+    }
+
     pub fn __repr__(&self) -> String {
         format!("<AccountDomain: {}>", &self.0.name)
     }
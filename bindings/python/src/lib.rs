@@ -1,8 +1,17 @@
+// pyo3 0.19 expands `#[new]` into impl blocks inside functions
+#![allow(non_local_definitions)]
+
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
 use zhang_ast::{Account, Spanned};
+use zhang_core::cache::LedgerCache;
+use zhang_core::data_source::LocalFileSystemDataSource;
+use zhang_core::data_type::text::ZhangDataType;
 
 use crate::domain::CommodityDomain;
 
@@ -17,14 +26,20 @@ pub struct Ledger(zhang_core::ledger::Ledger);
 
 #[pymethods]
 impl Ledger {
-    // #[new]
-    // pub fn new(path: &str, endpoint: &str) -> PyResult<Self> {
-    //     let pathbuf = PathBuf::from_str(path)?;
-    //     Ok(Ledger(
-    //         zhang_core::ledger::Ledger::load::<TextTransformer>(pathbuf, endpoint.to_owned()).unwrap(),
-    //     ))
-    // }
-    //
+    #[new]
+    #[pyo3(signature = (path, endpoint, database = None))]
+    pub fn new(path: &str, endpoint: &str, database: Option<&str>) -> PyResult<Self> {
+        let data_source = Arc::new(LocalFileSystemDataSource::new(ZhangDataType {}));
+        let ledger = match database {
+            Some(database) => {
+                let cache = LedgerCache::new(database, env!("CARGO_PKG_VERSION"));
+                zhang_core::ledger::Ledger::load_with_cache(PathBuf::from(path), endpoint.to_owned(), data_source, &cache)
+            }
+            None => zhang_core::ledger::Ledger::load_with_data_source(PathBuf::from(path), endpoint.to_owned(), data_source),
+        };
+        ledger.map(Ledger).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
     // #[staticmethod]
     // pub fn from_string(content: &str) -> PyResult<Self> {
     //     let t_dir = temp_dir();
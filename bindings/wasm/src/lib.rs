@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use beancount::Beancount;
 use wasm_bindgen::prelude::*;
+use zhang_core::custom::CustomRegistry;
 use zhang_core::data_type::text::ZhangDataType;
 use zhang_core::data_type::DataType;
 use zhang_core::ledger::{Ledger, LedgerProcessContext};
@@ -84,6 +85,8 @@ pub fn parse(content: &str) -> PlayGroundParse {
                 entry: (PathBuf::from("/"), "".to_owned()),
                 visited_files: vec![],
                 data_source: source.clone(),
+                custom_registry: CustomRegistry::default(),
+                keep_checkpoints: false,
             })
             .unwrap();
             let result1 = result.store.read().unwrap();
@@ -100,6 +103,8 @@ pub fn parse(content: &str) -> PlayGroundParse {
                 entry: (PathBuf::from("/"), "".to_owned()),
                 visited_files: vec![],
                 data_source: source.clone(),
+                custom_registry: CustomRegistry::default(),
+                keep_checkpoints: false,
             })
             .unwrap();
             let result1 = result.store.read().unwrap();
@@ -1,3 +1,4 @@
+use bigdecimal::BigDecimal;
 use itertools::Either;
 use zhang_ast::amount::Amount;
 use zhang_ast::{Account, Date, Directive, Meta};
@@ -38,6 +39,7 @@ pub struct BalanceDirective {
     pub date: Date,
     pub account: Account,
     pub amount: Amount,
+    pub tolerance: Option<BigDecimal>,
 
     pub meta: Meta,
 }
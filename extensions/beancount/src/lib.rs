@@ -95,6 +95,7 @@ impl DataType for Beancount {
                                     date: balance.date,
                                     account: balance.account,
                                     amount: balance.amount,
+                                    tolerance: balance.tolerance,
                                     meta: balance.meta,
                                 }),
                             });
@@ -116,6 +117,7 @@ impl DataType for Beancount {
                 date: check.date,
                 account: check.account,
                 amount: check.amount,
+                tolerance: check.tolerance,
 
                 meta: check.meta,
             }
@@ -133,7 +135,7 @@ impl DataType for Beancount {
                     date: pad.date,
                     account: pad.account,
                     amount: pad.amount,
-
+                    tolerance: None,
                     meta: pad.meta,
                 };
                 [pad_directive.bc_to_string(), balance_directive.bc_to_string()].join("\n")
@@ -197,11 +199,15 @@ trait BeancountOnlyExportable {
 
 impl BeancountOnlyExportable for BalanceDirective {
     fn bc_to_string(self) -> String {
+        let amount = match self.tolerance {
+            Some(tolerance) => format!("{} ~ {} {}", self.amount.number, tolerance, self.amount.currency),
+            None => ZhangDataTypeExportable::export(self.amount),
+        };
         let line = [
             ZhangDataTypeExportable::export(self.date),
             "balance".to_string(),
             ZhangDataTypeExportable::export(self.account),
-            ZhangDataTypeExportable::export(self.amount),
+            amount,
         ]
         .join(" ");
         append_meta(self.meta, line)
@@ -448,6 +454,36 @@ mod test {
                 date: Date::Date(NaiveDate::from_ymd_opt(1970, 1, 2).unwrap()),
                 account: Account::from_str("Assets:BankAccount").unwrap(),
                 amount: Amount::new(BigDecimal::from(100i32), "CNY"),
+                tolerance: None,
+                meta: Default::default(),
+            })
+        );
+    }
+
+    #[test]
+    fn should_transform_to_balance_check_directive_with_tolerance() {
+        let beancount_data_type = Beancount::default();
+        let mut directives = beancount_data_type
+            .transform(
+                indoc! {r#"
+                1970-01-02 balance Assets:BankAccount 100.00 ~ 0.01 CNY
+            "#}
+                .to_string(),
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(directives.len(), 1);
+
+        let balance_check_directive = directives.pop().unwrap().data;
+
+        assert_eq!(
+            balance_check_directive,
+            Directive::BalanceCheck(BalanceCheck {
+                date: Date::Date(NaiveDate::from_ymd_opt(1970, 1, 2).unwrap()),
+                account: Account::from_str("Assets:BankAccount").unwrap(),
+                amount: Amount::new(BigDecimal::from_str("100.00").unwrap(), "CNY"),
+                tolerance: Some(BigDecimal::from_str("0.01").unwrap()),
                 meta: Default::default(),
             })
         );
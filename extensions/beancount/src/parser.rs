@@ -460,14 +460,22 @@ impl BeancountParser {
         }))
     }
 
+    fn balance_tolerance(input: Node) -> Result<BigDecimal> {
+        let ret: BigDecimal = match_nodes!(input.into_children();
+            [number_expr(tolerance)] => tolerance,
+        );
+        Ok(ret)
+    }
     fn balance(input: Node) -> Result<BeancountOnlyDirective> {
-        let (date, account, amount, commodity): (Date, Account, BigDecimal, String) = match_nodes!(input.into_children();
-            [date(date), account_name(name), number_expr(amount), commodity_name(commodity)] => (date, name, amount, commodity),
+        let (date, account, amount, tolerance, commodity): (Date, Account, BigDecimal, Option<BigDecimal>, String) = match_nodes!(input.into_children();
+            [date(date), account_name(name), number_expr(amount), commodity_name(commodity)] => (date, name, amount, None, commodity),
+            [date(date), account_name(name), number_expr(amount), balance_tolerance(tolerance), commodity_name(commodity)] => (date, name, amount, Some(tolerance), commodity),
         );
         Ok(BeancountOnlyDirective::Balance(BalanceDirective {
             date,
             account,
             amount: Amount::new(amount, commodity),
+            tolerance,
             meta: Default::default(),
         }))
     }
@@ -728,6 +736,21 @@ mod test {
                     date: Date::Date(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
                     account: Account::from_str("Assets:BankAccount").unwrap(),
                     amount: Amount::new(BigDecimal::from(2i32), "CNY"),
+                    tolerance: None,
+                    meta: Default::default(),
+                }),
+                directive
+            );
+        }
+        #[test]
+        fn should_parse_balance_with_tolerance() {
+            let directive = get_right_directive("1970-01-01 balance Assets:BankAccount 2.00 ~ 0.01 CNY");
+            assert_eq!(
+                BeancountOnlyDirective::Balance(BalanceDirective {
+                    date: Date::Date(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
+                    account: Account::from_str("Assets:BankAccount").unwrap(),
+                    amount: Amount::new(BigDecimal::from_str("2.00").unwrap(), "CNY"),
+                    tolerance: Some(BigDecimal::from_str("0.01").unwrap()),
                     meta: Default::default(),
                 }),
                 directive
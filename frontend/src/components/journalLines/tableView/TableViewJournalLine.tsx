@@ -2,6 +2,7 @@ import { JournalItem } from '../../../rest-model';
 import TableViewBalancePadLine from './TableViewBalancePadLine';
 import TableViewTransactionLine from './TableViewTransactionLine';
 import TableViewBalanceCheckLine from './TableViewBalanceCheckLine';
+import TableViewNoteLine from './TableViewNoteLine';
 
 interface Props {
   data: JournalItem;
@@ -18,6 +19,9 @@ export default function TableViewJournalLine({ data }: Props) {
     case 'Transaction':
       line = <TableViewTransactionLine data={data} />;
       break;
+    case 'Note':
+      line = <TableViewNoteLine data={data} />;
+      break;
   }
   return line;
 }
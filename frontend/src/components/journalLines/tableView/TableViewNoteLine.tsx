@@ -0,0 +1,30 @@
+import { format } from 'date-fns';
+import { JournalNoteItem } from '../../../rest-model';
+import { TableRow, TableCell } from '@/components/ui/table';
+import { cn } from '@/lib/utils';
+import { Badge } from '@/components/ui/badge';
+
+interface Props {
+  data: JournalNoteItem;
+}
+
+export default function TableViewNoteLine({ data }: Props) {
+  const time = format(new Date(data.datetime), 'HH:mm:ss');
+
+  return (
+    <TableRow className={cn('p-1')}>
+      <TableCell>{time}</TableCell>
+      <TableCell>
+        <Badge variant="outline">Note</Badge>
+      </TableCell>
+      <TableCell>
+        <div className="flex flex-col">
+          <span className="text-sm">{data.comment}</span>
+          <span className="text-xs text-gray-500">{data.account}</span>
+        </div>
+      </TableCell>
+      <TableCell />
+      <TableCell />
+    </TableRow>
+  );
+}
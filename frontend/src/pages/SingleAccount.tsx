@@ -7,7 +7,7 @@ import AccountDocumentUpload from '../components/AccountDocumentUpload';
 import Amount from '../components/Amount';
 import LoadingComponent from '../components/basic/LoadingComponent';
 import PayeeNarration from '../components/basic/PayeeNarration';
-import { AccountBalanceHistory, AccountInfo, AccountJournalItem, Document } from '../rest-model';
+import { AccountBalanceHistory, AccountHoldings, AccountInfo, AccountJournalEntry, Document } from '../rest-model';
 import DocumentPreview from '../components/journalPreview/DocumentPreview';
 import { useDocumentTitle } from '@mantine/hooks';
 import { AccountBalanceHistoryGraph } from '../components/AccountBalanceHistoryGraph';
@@ -21,7 +21,7 @@ import { CardContent, CardHeader, CardTitle } from '@/components/ui/card.tsx';
 import { Card } from '@/components/ui/card.tsx';
 import { Badge } from '@/components/ui/badge.tsx';
 import { ACCOUNTS_LINK } from '@/layout/Sidebar.tsx';
-import { Cog, FileStack, Notebook } from 'lucide-react';
+import { Cog, FileStack, Notebook, Wallet } from 'lucide-react';
 
 function SingleAccount() {
   const setBreadcrumb = useSetAtom(breadcrumbAtom);
@@ -91,6 +91,9 @@ function SingleAccount() {
           <TabsTrigger value="journals">
             <Notebook className="w-4 h-4 mr-2" /> Journals
           </TabsTrigger>
+          <TabsTrigger value="holdings">
+            <Wallet className="w-4 h-4 mr-2" /> Holdings
+          </TabsTrigger>
           <TabsTrigger value="documents">
             <FileStack className="w-4 h-4 mr-2" /> Documents
           </TabsTrigger>
@@ -118,19 +121,89 @@ function SingleAccount() {
                   <LoadingComponent
                     url={`/api/accounts/${accountName}/journals`}
                     skeleton={<div>loading</div>}
-                    render={(data: AccountJournalItem[]) => (
+                    render={(data: AccountJournalEntry[]) => (
                       <>
-                        {(data ?? []).map((item) => (
-                          <TableRow>
-                            <TableCell>{format(new Date(item.datetime), 'yyyy-MM-dd HH:mm:ss')}</TableCell>
-                            <TableCell>
-                              <PayeeNarration payee={item.payee} narration={item.narration} />
+                        {(data ?? []).map((item) =>
+                          item.type === 'Note' ? (
+                            <TableRow>
+                              <TableCell>{format(new Date(item.datetime), 'yyyy-MM-dd HH:mm:ss')}</TableCell>
+                              <TableCell colSpan={3}>{item.comment}</TableCell>
+                            </TableRow>
+                          ) : (
+                            <TableRow>
+                              <TableCell>{format(new Date(item.datetime), 'yyyy-MM-dd HH:mm:ss')}</TableCell>
+                              <TableCell>
+                                <PayeeNarration payee={item.payee} narration={item.narration} />
+                              </TableCell>
+                              <TableCell className="text-right ">
+                                <Amount amount={item.inferred_unit_number} currency={item.inferred_unit_commodity} />
+                              </TableCell>
+                              <TableCell className="text-right ">
+                                <Amount amount={item.account_after_number} currency={item.account_after_commodity} />
+                              </TableCell>
+                            </TableRow>
+                          ),
+                        )}
+                      </>
+                    )}
+                  />
+                </TableBody>
+              </Table>
+            </CardContent>
+          </Card>
+        </TabsContent>
+        <TabsContent value="holdings">
+          <Card className="mt-2 rounded-sm ">
+            <CardHeader className="flex flex-row items-center justify-between space-y-0 pb-2 ">
+              <CardTitle>Holdings</CardTitle>
+            </CardHeader>
+            <CardContent>
+              <Table>
+                <TableHeader>
+                  <TableRow>
+                    <TableHead>Account</TableHead>
+                    <TableHead>Acquisition Date</TableHead>
+                    <TableHead className="text-right ">Units</TableHead>
+                    <TableHead className="text-right ">Cost Basis</TableHead>
+                    <TableHead className="text-right ">Market Value</TableHead>
+                    <TableHead className="text-right ">Unrealized Gain</TableHead>
+                  </TableRow>
+                </TableHeader>
+                <TableBody>
+                  <LoadingComponent
+                    url={`/api/accounts/${accountName}/holdings`}
+                    skeleton={<div>loading</div>}
+                    render={(data: AccountHoldings) => (
+                      <>
+                        {data.holdings.map((item, idx) => (
+                          <TableRow key={idx}>
+                            <TableCell>{item.account}</TableCell>
+                            <TableCell>{item.acquisition_date}</TableCell>
+                            <TableCell className="text-right ">
+                              <Amount amount={item.amount} currency={item.commodity} />
+                            </TableCell>
+                            <TableCell className="text-right ">
+                              <Amount amount={item.cost_basis.number} currency={item.cost_basis.commodity} />
+                            </TableCell>
+                            <TableCell className="text-right ">
+                              {item.market_value && <Amount amount={item.market_value.number} currency={item.market_value.commodity} />}
+                            </TableCell>
+                            <TableCell className="text-right ">
+                              {item.unrealized_gain && <Amount amount={item.unrealized_gain.number} currency={item.unrealized_gain.commodity} />}
+                            </TableCell>
+                          </TableRow>
+                        ))}
+                        {Object.entries(data.total).map(([commodity, total]) => (
+                          <TableRow key={commodity} className="font-semibold">
+                            <TableCell colSpan={3}>Total</TableCell>
+                            <TableCell className="text-right ">
+                              <Amount amount={total.cost_basis} currency={commodity} />
                             </TableCell>
                             <TableCell className="text-right ">
-                              <Amount amount={item.inferred_unit_number} currency={item.inferred_unit_commodity} />
+                              <Amount amount={total.market_value} currency={commodity} />
                             </TableCell>
                             <TableCell className="text-right ">
-                              <Amount amount={item.account_after_number} currency={item.account_after_commodity} />
+                              <Amount amount={total.unrealized_gain} currency={commodity} />
                             </TableCell>
                           </TableRow>
                         ))}
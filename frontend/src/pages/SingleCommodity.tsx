@@ -10,6 +10,7 @@ import { breadcrumbAtom, titleAtom } from '../states/basic';
 import { Tabs, TabsList, TabsTrigger, TabsContent } from '@/components/ui/tabs.tsx';
 import { Table, TableHeader, TableRow, TableCell, TableHead, TableBody } from '@/components/ui/table.tsx';
 import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card.tsx';
+import { Badge } from '@/components/ui/badge';
 import { COMMODITIES_LINK } from '@/layout/Sidebar.tsx';
 import { useEffect } from 'react';
 
@@ -90,6 +91,7 @@ export default function SingleCommodity() {
                   <TableRow>
                     <TableHead>Date</TableHead>
                     <TableHead>Price</TableHead>
+                    <TableHead>Source</TableHead>
                   </TableRow>
                 </TableHeader>
                 <TableBody>
@@ -99,6 +101,7 @@ export default function SingleCommodity() {
                       <TableCell>
                         <Amount amount={it.amount} currency={it.target_commodity} />
                       </TableCell>
+                      <TableCell>{it.source === 'implicit' ? <Badge variant="outline">implicit</Badge> : <Badge variant="secondary">explicit</Badge>}</TableCell>
                     </TableRow>
                   ))}
                 </TableBody>
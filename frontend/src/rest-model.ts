@@ -63,6 +63,8 @@ export interface InfoForNewTransaction {
   account_name: string[];
 }
 
+export type AccountJournalEntry = ({ type: 'Posting' } & AccountJournalItem) | AccountJournalNoteItem;
+
 export interface AccountJournalItem {
   datetime: string;
   trx_id: string;
@@ -75,6 +77,17 @@ export interface AccountJournalItem {
   account_after_commodity: string;
 }
 
+export interface AccountJournalNoteItem {
+  type: 'Note';
+  id: string;
+  sequence: number;
+  datetime: string;
+  account: string;
+  comment: string;
+  tags: string[];
+  links: string[];
+}
+
 export interface CommodityListItem {
   name: string;
   precision: number;
@@ -104,13 +117,51 @@ export interface CommodityLot {
   acquisition_date?: string;
 }
 
+export interface HoldingItem {
+  account: string;
+  commodity: string;
+  amount: string;
+  cost: AmountResponse;
+  acquisition_date?: string;
+  cost_basis: AmountResponse;
+  price?: AmountResponse;
+  price_datetime?: string;
+  market_value?: AmountResponse;
+  unrealized_gain?: AmountResponse;
+}
+
+export interface HoldingTotal {
+  cost_basis: string;
+  market_value: string;
+  unrealized_gain: string;
+}
+
+export interface AccountHoldings {
+  datetime: string;
+  holdings: HoldingItem[];
+  accounts: Record<string, Record<string, HoldingTotal>>;
+  total: Record<string, HoldingTotal>;
+}
+
 export interface CommodityPrice {
   datetime: string;
   amount: string;
   target_commodity: string;
+  source: 'explicit' | 'implicit';
 }
 
-export type JournalItem = JournalTransactionItem | JournalBalancePadItem | JournalBalanceCheckItem;
+export type JournalItem = JournalTransactionItem | JournalBalancePadItem | JournalBalanceCheckItem | JournalNoteItem;
+
+export interface JournalNoteItem {
+  type: 'Note';
+  id: string;
+  sequence: number;
+  datetime: string;
+  account: string;
+  comment: string;
+  tags: string[];
+  links: string[];
+}
 
 export interface JournalBalancePadItem {
   type: 'BalancePad';
@@ -155,6 +206,7 @@ export interface JournalTransactionItem {
   is_balanced: boolean;
   postings: Posting[];
   metas: Meta[];
+  events: Record<string, string>;
 }
 
 export interface Posting {
@@ -192,6 +244,7 @@ export interface StatisticGraphResponse {
 export interface CalculatedAmountResponse {
   calculated: AmountResponse;
   detail: { [commodity: string]: string };
+  conversions: { [commodity: string]: string[] };
 }
 
 export interface AmountResponse {
@@ -229,11 +282,17 @@ export interface Option {
   value: string;
 }
 
+export type BudgetPeriod = 'Weekly' | 'Monthly' | 'Quarterly' | 'Yearly';
+
 export interface BudgetListItem {
   name: string;
   alias?: string;
   category?: string;
   closed: boolean;
+  rollover: boolean;
+  period: BudgetPeriod;
+  interval_start: string;
+  interval_end: string;
   assigned_amount: AmountResponse;
   activity_amount: AmountResponse;
   available_amount: AmountResponse;
@@ -244,13 +303,30 @@ export interface BudgetInfoResponse {
   alias?: string;
   category?: string;
   closed: boolean;
+  rollover: boolean;
+  period: BudgetPeriod;
+  interval_start: string;
+  interval_end: string;
 
   related_accounts: string[];
+  targets: BudgetTargetProgress[];
   assigned_amount: AmountResponse;
   activity_amount: AmountResponse;
   available_amount: AmountResponse;
 }
 
+export type BudgetTarget =
+  | { type: 'Interval'; amount: AmountResponse }
+  | { type: 'SaveBy'; amount: AmountResponse; date: string }
+  | { type: 'SpendingCap'; amount: AmountResponse };
+
+export interface BudgetTargetProgress {
+  target: BudgetTarget;
+  current: AmountResponse;
+  remaining: AmountResponse;
+  achieved: boolean;
+}
+
 export type BudgetIntervalEventResponse = BudgetIntervalEventBudgetResponse | BudgetIntervalEventPostingResponse;
 
 export interface BudgetIntervalEventBudgetResponse {
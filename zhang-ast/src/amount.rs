@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 pub struct CalculatedAmount {
     pub calculated: Amount,
     pub detail: HashMap<String, BigDecimal>,
+    /// commodities converted into the calculated currency, with the path of commodities the conversion goes through
+    pub conversions: HashMap<String, Vec<String>>,
 }
 
 impl CalculatedAmount {
@@ -17,6 +19,7 @@ impl CalculatedAmount {
         CalculatedAmount {
             calculated: Amount::new(BigDecimal::zero(), currency.to_owned()),
             detail,
+            conversions: HashMap::new(),
         }
     }
     pub fn persist_commodity(mut self, commodity: &str) -> Self {
@@ -25,7 +28,7 @@ impl CalculatedAmount {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Hash, Debug, Clone, Serialize, Deserialize)]
 pub struct Amount {
     pub number: BigDecimal,
     pub currency: String,
@@ -2,7 +2,7 @@ use std::collections::HashSet;
 use std::ops::{Div, Mul, Neg};
 
 use bigdecimal::{BigDecimal, One, Signed};
-use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
 use chrono_tz::Tz;
 use indexmap::IndexSet;
 use itertools::Itertools;
@@ -45,11 +45,8 @@ impl Date {
             Date::Datetime(datetime) => datetime.date(),
         }
     }
-    pub fn as_budget_interval(&self) -> u32 {
-        let date = self.naive_date();
-        let year = date.year();
-        let month = date.month();
-        month + (year * 100) as u32
+    pub fn as_budget_interval(&self, period: BudgetPeriod) -> u32 {
+        period.interval(self.naive_date())
     }
 }
 
@@ -80,6 +77,8 @@ pub struct BalanceCheck {
     pub date: Date,
     pub account: Account,
     pub amount: Amount,
+    /// explicit tolerance given by `~ 0.01` syntax
+    pub tolerance: Option<BigDecimal>,
     pub meta: Meta,
 }
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -279,6 +278,37 @@ pub struct Event {
     pub meta: Meta,
 }
 
+/// the template of transactions repeated at a fixed frequency, e.g.
+/// ```zhang
+/// 2024-01-01 recurring "monthly" "Landlord" "Rent"
+///   until: 2024-12-31
+///   Expenses:Rent 1000 CNY
+///   Assets:Bank
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Recurring {
+    pub date: Date,
+    pub frequency: RecurringFrequency,
+    pub payee: Option<ZhangString>,
+    pub narration: Option<ZhangString>,
+    pub tags: IndexSet<String>,
+    pub links: IndexSet<String>,
+    pub postings: Vec<Posting>,
+    /// the last date of occurrences, inclusive
+    pub until: Option<NaiveDate>,
+    pub meta: Meta,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum RecurringFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Query {
     pub date: Date,
@@ -351,6 +381,50 @@ pub struct Budget {
     pub meta: Meta,
 }
 
+/// the period of budget intervals, which is configured by the `period` meta of budget directive.
+///
+/// intervals are identified as `year*100+index`, where index is the ISO week, the month or the quarter in the year,
+/// and always `1` for yearly budgets, E.G. `202312` is the December of 2023 for monthly budgets.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum BudgetPeriod {
+    Weekly,
+    #[default]
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl BudgetPeriod {
+    /// the interval which the date falls in
+    pub fn interval(&self, date: NaiveDate) -> u32 {
+        let (year, index) = match self {
+            BudgetPeriod::Weekly => (date.iso_week().year(), date.iso_week().week()),
+            BudgetPeriod::Monthly => (date.year(), date.month()),
+            BudgetPeriod::Quarterly => (date.year(), date.month0() / 3 + 1),
+            BudgetPeriod::Yearly => (date.year(), 1),
+        };
+        year as u32 * 100 + index
+    }
+
+    /// the first and last date of the interval, or `None` if the interval is invalid
+    pub fn interval_range(&self, interval: u32) -> Option<(NaiveDate, NaiveDate)> {
+        let year = (interval / 100) as i32;
+        let index = interval % 100;
+        let (start, months) = match self {
+            BudgetPeriod::Weekly => {
+                let start = NaiveDate::from_isoywd_opt(year, index, Weekday::Mon)?;
+                return Some((start, start.checked_add_days(Days::new(6))?));
+            }
+            BudgetPeriod::Monthly => (NaiveDate::from_ymd_opt(year, index, 1)?, 1),
+            BudgetPeriod::Quarterly if (1..=4).contains(&index) => (NaiveDate::from_ymd_opt(year, (index - 1) * 3 + 1, 1)?, 3),
+            BudgetPeriod::Yearly if index == 1 => (NaiveDate::from_ymd_opt(year, 1, 1)?, 12),
+            _ => return None,
+        };
+        Some((start, start.checked_add_months(Months::new(months))?.pred_opt()?))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BudgetAdd {
     pub date: Date,
@@ -376,3 +450,33 @@ pub struct BudgetClose {
 
     pub meta: Meta,
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use crate::BudgetPeriod;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn should_locate_budget_interval() {
+        assert_eq!(BudgetPeriod::Weekly.interval(date(2024, 1, 1)), 202401);
+        assert_eq!(BudgetPeriod::Weekly.interval(date(2023, 1, 1)), 202252);
+        assert_eq!(BudgetPeriod::Monthly.interval(date(2023, 12, 31)), 202312);
+        assert_eq!(BudgetPeriod::Quarterly.interval(date(2023, 12, 31)), 202304);
+        assert_eq!(BudgetPeriod::Yearly.interval(date(2023, 12, 31)), 202301);
+    }
+
+    #[test]
+    fn should_get_range_of_budget_interval() {
+        assert_eq!(BudgetPeriod::Weekly.interval_range(202401), Some((date(2024, 1, 1), date(2024, 1, 7))));
+        assert_eq!(BudgetPeriod::Monthly.interval_range(202402), Some((date(2024, 2, 1), date(2024, 2, 29))));
+        assert_eq!(BudgetPeriod::Quarterly.interval_range(202404), Some((date(2024, 10, 1), date(2024, 12, 31))));
+        assert_eq!(BudgetPeriod::Yearly.interval_range(202401), Some((date(2024, 1, 1), date(2024, 12, 31))));
+        assert_eq!(BudgetPeriod::Quarterly.interval_range(202405), None);
+        assert_eq!(BudgetPeriod::Yearly.interval_range(202402), None);
+    }
+}
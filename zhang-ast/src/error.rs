@@ -1,7 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Debug, Display, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Display, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ErrorKind {
     UnbalancedTransaction,
     TransactionCannotInferTradeAmount,
@@ -11,15 +11,23 @@ pub enum ErrorKind {
     AccountBalanceCheckError,
     AccountDoesNotExist,
     AccountClosed,
+    AccountNotOpenAtDate,
 
     CommodityDoesNotDefine,
+    CommodityNotAllowedInAccount,
     NoEnoughCommodityLot,
+    AmbiguousCommodityLot,
     CloseNonZeroAccount,
 
     BudgetDoesNotExist,
     DefineDuplicatedBudget,
+    BudgetOverspent,
+    BudgetSpendingCapExceeded,
+    BudgetCloseNonEmpty,
+    BudgetActivityPriceNotFound,
 
     MultipleOperatingCurrencyDetect,
 
     ParseInvalidMeta,
+    InvalidCustomArgument,
 }
@@ -7,7 +7,7 @@ use strum::{Display, EnumString};
 
 use crate::account::Account;
 use crate::amount::Amount;
-use crate::data::{Close, Comment, Commodity, Custom, Document, Event, Include, Note, Open, Options, Plugin, Price, Transaction};
+use crate::data::{Close, Comment, Commodity, Custom, Document, Event, Include, Note, Open, Options, Plugin, Price, Recurring, Transaction};
 use crate::error::ErrorKind;
 use crate::{BalanceCheck, BalancePad, Budget, BudgetAdd, BudgetClose, BudgetTransfer, Meta};
 
@@ -17,6 +17,7 @@ pub enum DirectiveType {
     Close,
     Commodity,
     Transaction,
+    Recurring,
     BalancePad,
     BalanceCheck,
     Note,
@@ -41,6 +42,7 @@ pub enum Directive {
     Close(Close),
     Commodity(Commodity),
     Transaction(Transaction),
+    Recurring(Recurring),
     BalancePad(BalancePad),
     BalanceCheck(BalanceCheck),
     Note(Note),
@@ -66,6 +68,7 @@ impl Directive {
             Directive::Close(close) => Some(close.date.naive_datetime()),
             Directive::Commodity(commodity) => Some(commodity.date.naive_datetime()),
             Directive::Transaction(txn) => Some(txn.date.naive_datetime()),
+            Directive::Recurring(recurring) => Some(recurring.date.naive_datetime()),
             Directive::BalanceCheck(check) => Some(check.date.naive_datetime()),
             Directive::BalancePad(pad) => Some(pad.date.naive_datetime()),
             Directive::Note(note) => Some(note.date.naive_datetime()),
@@ -90,6 +93,7 @@ impl Directive {
             Directive::Close(_) => DirectiveType::Close,
             Directive::Commodity(_) => DirectiveType::Commodity,
             Directive::Transaction(_) => DirectiveType::Transaction,
+            Directive::Recurring(_) => DirectiveType::Recurring,
             Directive::Note(_) => DirectiveType::Note,
             Directive::Document(_) => DirectiveType::Document,
             Directive::Price(_) => DirectiveType::Price,
@@ -114,6 +118,7 @@ impl Directive {
             Directive::Close(ref mut directive) => directive.meta = meta,
             Directive::Commodity(ref mut directive) => directive.meta = meta,
             Directive::Transaction(ref mut directive) => directive.meta = meta,
+            Directive::Recurring(ref mut directive) => directive.meta = meta,
             Directive::BalancePad(ref mut directive) => directive.meta = meta,
             Directive::BalanceCheck(ref mut directive) => directive.meta = meta,
             Directive::Note(ref mut directive) => directive.meta = meta,
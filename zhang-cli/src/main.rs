@@ -2,11 +2,19 @@ use std::fmt::Debug;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use beancount::Beancount;
+use chrono::NaiveDate;
 use clap::{Args, Parser};
 use env_logger::Env;
 use log::{error, info};
 use self_update::Status;
 use tokio::task::spawn_blocking;
+use zhang_core::cache::LedgerCache;
+use zhang_core::data_source::{DataSource, LocalFileSystemDataSource};
+use zhang_core::data_type::text::ZhangDataType;
+use zhang_core::ledger::Ledger;
+use zhang_core::query::QueryResult;
+use zhang_core::ZhangResult;
 use zhang_server::ServeConfig;
 
 use crate::opendal::OpendalDataSource;
@@ -19,6 +27,12 @@ pub enum Opts {
     /// zhang parser
     Parse(ParseOpts),
 
+    /// execute the SQL-like query over the ledger
+    Query(QueryOpts),
+
+    /// print the transactions closing the period and opening the next period
+    Close(CloseOpts),
+
     /// export to target file
     Export(ExportOpts),
 
@@ -41,10 +55,47 @@ pub struct ParseOpts {
     #[clap(short, long, default_value = "main.zhang")]
     pub endpoint: String,
 
-    /// indicate cache database file path, using tempfile if not present
+    /// indicate cache database file path, the ledger is not cached if not present
+    #[clap(long)]
+    pub database: Option<PathBuf>,
+}
+#[derive(Args, Debug)]
+pub struct QueryOpts {
+    /// base path of zhang project
+    pub path: PathBuf,
+
+    /// the query to execute, e.g. "SELECT account, sum(inferred_amount) GROUP BY account"
+    pub query: String,
+
+    /// the endpoint of main zhang file.
+    #[clap(short, long, default_value = "main.zhang")]
+    pub endpoint: String,
+
+    /// indicate cache database file path, the ledger is not cached if not present
+    #[clap(long)]
+    pub database: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct CloseOpts {
+    /// base path of zhang project
+    pub path: PathBuf,
+
+    /// the first day of period, e.g. 2023-01-01
+    pub from: NaiveDate,
+
+    /// the last day of period, e.g. 2023-12-31
+    pub to: NaiveDate,
+
+    /// the endpoint of main zhang file.
+    #[clap(short, long, default_value = "main.zhang")]
+    pub endpoint: String,
+
+    /// indicate cache database file path, the ledger is not cached if not present
     #[clap(long)]
     pub database: Option<PathBuf>,
 }
+
 #[derive(Args, Debug)]
 pub struct ExportOpts {
     /// base path of zhang project
@@ -111,15 +162,54 @@ pub struct ServerOpts {
     /// whether the server report version info for anonymous statistics
     #[clap(long)]
     pub no_report: bool,
+
+    /// indicate cache database file path, the ledger is not cached if not present
+    #[clap(long)]
+    pub database: Option<PathBuf>,
 }
 
 impl Opts {
     pub async fn run(self) {
         match self {
-            Opts::Parse(_parse_opts) => {
-                // let format = SupportedFormat::from_path(&parse_opts.endpoint).expect("unsupported file type");
-                // todo: fix parse
-                // Ledger::load_with_database(parse_opts.path, parse_opts.endpoint, format.transformer()).expect("Cannot load ledger");
+            Opts::Parse(parse_opts) => {
+                let result = load_local_ledger(parse_opts.path, parse_opts.endpoint, parse_opts.database);
+                match result.and_then(|ledger| ledger.operations().errors()) {
+                    Ok(errors) => info!("ledger is parsed with {} errors", errors.len()),
+                    Err(e) => error!("fail to parse ledger: {}", e),
+                }
+            }
+            Opts::Query(QueryOpts {
+                path,
+                query,
+                endpoint,
+                database,
+            }) => {
+                let result = load_local_ledger(path, endpoint, database);
+                match result.and_then(|ledger| ledger.operations().query(&query)) {
+                    Ok(result) => print!("{}", format_query_result(&result)),
+                    Err(e) => error!("fail to execute query: {}", e),
+                }
+            }
+            Opts::Close(CloseOpts {
+                path,
+                from,
+                to,
+                endpoint,
+                database,
+            }) => {
+                let result = load_local_ledger(path, endpoint, database).and_then(|ledger| {
+                    let formatter = ledger.operations().amount_formatter();
+                    ledger
+                        .close_period(from, to)?
+                        .directives()
+                        .into_iter()
+                        .map(|directive| ledger.data_source.export(formatter.pad_directive(directive.data)))
+                        .collect::<ZhangResult<Vec<_>>>()
+                });
+                match result {
+                    Ok(contents) => contents.iter().for_each(|content| println!("{}\n", String::from_utf8_lossy(content))),
+                    Err(e) => error!("fail to close period: {}", e),
+                }
             }
             Opts::Export(_) => todo!(),
             Opts::Serve(mut opts) => {
@@ -136,6 +226,7 @@ impl Opts {
                     is_local_fs: file_system == FileSystem::Fs,
                     no_report: opts.no_report,
                     data_source: Arc::new(data_source),
+                    database: opts.database,
                 })
                 .await;
                 match result {
@@ -172,6 +263,47 @@ impl Opts {
     }
 }
 
+/// load ledger from local file system, beancount files are detected by the extension of endpoint
+fn load_local_ledger(path: PathBuf, endpoint: String, database: Option<PathBuf>) -> ZhangResult<Ledger> {
+    let data_source: Arc<dyn DataSource> = match PathBuf::from(&endpoint).extension().and_then(|it| it.to_str()) {
+        Some("bc" | "bean" | "beancount") => Arc::new(LocalFileSystemDataSource::new(Beancount {})),
+        _ => Arc::new(LocalFileSystemDataSource::new(ZhangDataType {})),
+    };
+    match database {
+        Some(database) => {
+            let cache = LedgerCache::new(database, env!("ZHANG_BUILD_VERSION"));
+            Ledger::load_with_cache(path, endpoint, data_source, &cache)
+        }
+        None => Ledger::load_with_data_source(path, endpoint, data_source),
+    }
+}
+
+/// format the query result as a plain text table
+fn format_query_result(result: &QueryResult) -> String {
+    let rows: Vec<Vec<String>> = result.rows.iter().map(|row| row.iter().map(|value| value.to_string()).collect()).collect();
+    let widths: Vec<usize> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| rows.iter().map(|row| row[idx].chars().count()).fold(column.chars().count(), usize::max))
+        .collect();
+    let format_line = |cells: &[String]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        format!("{}\n", line.join("  ").trim_end())
+    };
+
+    let mut output = format_line(&result.columns);
+    output.push_str(&format_line(&widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>()));
+    for row in rows.iter() {
+        output.push_str(&format_line(row));
+    }
+    output
+}
+
 #[tokio::main]
 async fn main() {
     // console_subscriber::init();
@@ -282,6 +414,7 @@ mod test {
                             auth: None,
                             source: None,
                             no_report: false,
+                            database: None,
                         },
                     )
                     .await;
@@ -1,5 +1,5 @@
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use async_recursion::async_recursion;
@@ -8,7 +8,7 @@ use log::{debug, info};
 use opendal::services::{Fs, Github, Webdav};
 use opendal::{ErrorKind, Operator};
 use zhang_ast::{Directive, Include, SpanInfo, Spanned, ZhangString};
-use zhang_core::data_source::{DataSource, LoadResult};
+use zhang_core::data_source::{DataSource, LoadResult, ParsedFileCache};
 use zhang_core::data_type::text::parser::parse as zhang_parse;
 use zhang_core::data_type::text::ZhangDataType;
 use zhang_core::data_type::DataType;
@@ -22,6 +22,7 @@ pub struct OpendalDataSource {
     operator: Operator,
     data_type: Box<dyn DataType<Carrier = String> + 'static + Send + Sync>,
     is_beancount: bool,
+    parsed_files: ParsedFileCache,
 }
 
 #[async_trait::async_trait]
@@ -55,7 +56,9 @@ impl DataSource for OpendalDataSource {
                 continue;
             }
             let file_content = self.get_file_content(striped_pathbuf.clone()).await?;
-            let entity_directives = self.parse(&file_content, striped_pathbuf.clone())?;
+            let entity_directives = self
+                .parsed_files
+                .get_or_parse(&pathbuf, file_content.as_bytes(), || self.parse(&file_content, striped_pathbuf.clone()))?;
 
             entity_directives.iter().filter_map(|directive| self.go_next(directive)).for_each(|buf| {
                 let fullpath = if buf.starts_with('/') {
@@ -68,6 +71,7 @@ impl DataSource for OpendalDataSource {
             directives.extend(entity_directives);
             visited.push(pathbuf);
         }
+        self.parsed_files.retain(&visited);
         Ok(LoadResult {
             directives: self.transform(directives)?,
             visited_files: visited,
@@ -89,6 +93,11 @@ impl DataSource for OpendalDataSource {
         }
     }
 
+    async fn async_get_visited(&self, entry: &Path, path: &Path) -> ZhangResult<Vec<u8>> {
+        let striped_path = path.strip_prefix(entry).unwrap_or(path);
+        self.async_get(striped_path.to_string_lossy().to_string()).await
+    }
+
     async fn async_append(&self, ledger: &Ledger, directives: Vec<Directive>) -> ZhangResult<()> {
         for directive in directives {
             self.append_directive(ledger, directive, None, true).await?;
@@ -143,6 +152,7 @@ impl OpendalDataSource {
         let content_buf = ledger.data_source.async_get(striped_endpoint.to_string_lossy().to_string()).await?;
         let content = String::from_utf8(content_buf)?;
 
+        let directive = ledger.operations().amount_formatter().pad_directive(directive);
         let appended_content = format!("{}\n{}\n", content, self.data_type.export(Spanned::new(directive, SpanInfo::default())));
 
         ledger
@@ -201,6 +211,7 @@ impl OpendalDataSource {
             operator,
             data_type: new_data_type,
             is_beancount,
+            parsed_files: ParsedFileCache::default(),
         }
     }
 
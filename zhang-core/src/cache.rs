@@ -0,0 +1,357 @@
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use log::{info, warn};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha256::digest;
+use zhang_ast::{Directive, Spanned};
+
+use crate::custom::CustomRegistry;
+use crate::data_source::DataSource;
+use crate::error::IoErrorIntoZhangError;
+use crate::ledger::Ledger;
+use crate::options::InMemoryOptions;
+use crate::store::checkpoint::Checkpoints;
+use crate::store::Store;
+use crate::{ZhangError, ZhangResult};
+
+/// [LedgerCache] persists the processed ledger into a local file, so that an unchanged ledger can be loaded without parsing and processing.
+/// the cache is invalidated once the version of zhang or the content of any visited file is changed.
+/// includes are plain file paths, so a newly included file always comes with a change of a visited file.
+pub struct LedgerCache {
+    path: PathBuf,
+    version: String,
+}
+
+/// the first line of cache file, which is checked before deserializing the ledger
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    version: String,
+    /// visited files with the sha256 of their content
+    files: Vec<(PathBuf, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedLedger<'a> {
+    options: Cow<'a, InMemoryOptions>,
+    metas: Cow<'a, [Spanned<Directive>]>,
+    directives: Cow<'a, [Spanned<Directive>]>,
+    trx_counter: i32,
+    store: Cow<'a, Store>,
+    checkpoints: Cow<'a, Checkpoints>,
+}
+
+impl LedgerCache {
+    pub fn new(path: impl Into<PathBuf>, version: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            version: version.into(),
+        }
+    }
+
+    /// load the ledger from cache, return `None` if the cache is missing or outdated
+    pub fn load(&self, entry: PathBuf, endpoint: String, data_source: Arc<dyn DataSource>) -> Option<Ledger> {
+        let (header, body) = self.read()?;
+        let unchanged = header.files.iter().all(|(path, hash)| {
+            data_source
+                .get_visited(&entry, path)
+                .map(|content| digest(content.as_slice()).eq(hash))
+                .unwrap_or(false)
+        });
+        if !unchanged {
+            info!("ledger files are changed since cached");
+            return None;
+        }
+        self.restore(header, &body, (entry, endpoint), data_source)
+    }
+
+    pub async fn async_load(&self, entry: PathBuf, endpoint: String, data_source: Arc<dyn DataSource>) -> Option<Ledger> {
+        let (header, body) = self.read()?;
+        for (path, hash) in header.files.iter() {
+            let content = data_source.async_get_visited(&entry, path).await;
+            if !content.map(|content| digest(content.as_slice()).eq(hash)).unwrap_or(false) {
+                info!("ledger files are changed since cached");
+                return None;
+            }
+        }
+        self.restore(header, &body, (entry, endpoint), data_source)
+    }
+
+    /// write the ledger into cache, ledgers with plugins are not cached since plugins cannot be restored from cache
+    pub fn save(&self, ledger: &Ledger) -> ZhangResult<()> {
+        if Self::has_plugins(ledger) {
+            return Ok(());
+        }
+        let files = ledger
+            .visited_files
+            .iter()
+            .map(|path| {
+                let content = ledger.data_source.get_visited(&ledger.entry.0, path)?;
+                Ok((path.clone(), digest(content.as_slice())))
+            })
+            .collect::<ZhangResult<Vec<_>>>()?;
+        self.write(ledger, files)
+    }
+
+    pub async fn async_save(&self, ledger: &Ledger) -> ZhangResult<()> {
+        if Self::has_plugins(ledger) {
+            return Ok(());
+        }
+        let mut files = Vec::with_capacity(ledger.visited_files.len());
+        for path in ledger.visited_files.iter() {
+            let content = ledger.data_source.async_get_visited(&ledger.entry.0, path).await?;
+            files.push((path.clone(), digest(content.as_slice())));
+        }
+        self.write(ledger, files)
+    }
+
+    fn has_plugins(ledger: &Ledger) -> bool {
+        let has_plugins = ledger.metas.iter().any(|it| matches!(it.data, Directive::Plugin(_)));
+        if has_plugins {
+            info!("ledger with plugins is not cached");
+        }
+        has_plugins
+    }
+
+    fn write(&self, ledger: &Ledger, files: Vec<(PathBuf, String)>) -> ZhangResult<()> {
+        let header = CacheHeader {
+            version: self.version.clone(),
+            files,
+        };
+        let store = ledger.store.read().expect("poison lock detect");
+        let cached_ledger = CachedLedger {
+            options: Cow::Borrowed(&ledger.options),
+            metas: Cow::Borrowed(&ledger.metas),
+            directives: Cow::Borrowed(&ledger.directives),
+            trx_counter: ledger.trx_counter.load(Ordering::Relaxed),
+            store: Cow::Borrowed(&store),
+            checkpoints: Cow::Borrowed(&ledger.checkpoints),
+        };
+
+        let mut content = serde_json::to_vec(&header).map_err(|e| ZhangError::CustomError(format!("fail to serialize cache header: {}", e)))?;
+        content.push(b'\n');
+        serde_json::to_writer(&mut content, &cached_ledger).map_err(|e| ZhangError::CustomError(format!("fail to serialize ledger: {}", e)))?;
+        std::fs::write(&self.path, content).with_path(&self.path)?;
+        info!("ledger is cached into {}", self.path.display());
+        Ok(())
+    }
+
+    /// read the header and the remaining content of cache file
+    fn read(&self) -> Option<(CacheHeader, Vec<u8>)> {
+        let mut content = match std::fs::read(&self.path) {
+            Ok(content) => content,
+            Err(e) => {
+                info!("cache {} is not available: {}", self.path.display(), e);
+                return None;
+            }
+        };
+        let header_end = content.iter().position(|it| *it == b'\n')?;
+        let body = content.split_off(header_end + 1);
+        let header: CacheHeader = match serde_json::from_slice(&content) {
+            Ok(header) => header,
+            Err(e) => {
+                warn!("cache {} is invalid: {}", self.path.display(), e);
+                return None;
+            }
+        };
+        if header.version != self.version {
+            info!("cache is created by zhang {}, current version is {}", header.version, self.version);
+            return None;
+        }
+        Some((header, body))
+    }
+
+    fn restore(&self, header: CacheHeader, body: &[u8], entry: (PathBuf, String), data_source: Arc<dyn DataSource>) -> Option<Ledger> {
+        let cached_ledger = match serde_json::from_slice::<CachedLedger>(body) {
+            Ok(cached_ledger) => cached_ledger,
+            Err(e) => {
+                warn!("cache {} is invalid: {}", self.path.display(), e);
+                return None;
+            }
+        };
+
+        let options = cached_ledger.options.into_owned();
+        let mut store = cached_ledger.store.into_owned();
+        store.convert_timezone(&options.timezone);
+        store.rebuild_indexes();
+        let mut checkpoints = cached_ledger.checkpoints.into_owned();
+        checkpoints.convert_timezone(&options.timezone);
+        checkpoints.rebuild_latest();
+
+        let visited_files = header.files.into_iter().map(|(path, _)| path).collect();
+        let mut ledger = Ledger::new(entry, visited_files, data_source, CustomRegistry::default());
+        ledger.options = options;
+        ledger.metas = cached_ledger.metas.into_owned();
+        ledger.directives = cached_ledger.directives.into_owned();
+        ledger.custom_registry.declare(&ledger.directives);
+        ledger.restore_processed_directives();
+        ledger.store = Arc::new(RwLock::new(store));
+        ledger.trx_counter = AtomicI32::new(cached_ledger.trx_counter);
+        ledger.checkpoints = checkpoints;
+        info!("Ledger loaded from cache {}", self.path.display());
+        Some(ledger)
+    }
+}
+
+/// deserialize the datetime of store in UTC, which is converted into the timezone of ledger by [Store::convert_timezone] after loading
+pub(crate) fn deserialize_datetime<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Tz>, D::Error> {
+    let datetime = DateTime::<Utc>::deserialize(deserializer)?;
+    Ok(datetime.with_timezone(&Tz::UTC))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use bigdecimal::BigDecimal;
+    use indoc::indoc;
+    use tempfile::tempdir;
+
+    use crate::cache::LedgerCache;
+    use crate::data_source::{DataSource, LocalFileSystemDataSource};
+    use crate::data_type::text::ZhangDataType;
+    use crate::ledger::Ledger;
+
+    fn data_source() -> Arc<dyn DataSource> {
+        Arc::new(LocalFileSystemDataSource::new(ZhangDataType {}))
+    }
+
+    fn store_json(ledger: &Ledger) -> serde_json::Value {
+        serde_json::to_value(&*ledger.store.read().unwrap()).unwrap()
+    }
+
+    fn setup() -> PathBuf {
+        let temp_dir = tempdir().unwrap().into_path().canonicalize().unwrap();
+        std::fs::write(
+            temp_dir.join("main.zhang"),
+            indoc! {r#"
+                option "operating_currency" "CNY"
+                option "timezone" "America/New_York"
+                1970-01-01 commodity CNY
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Assets:Stock
+                1970-01-01 open Expenses:Food
+                include "2024.zhang"
+            "#},
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("2024.zhang"),
+            indoc! {r#"
+                2024-01-05 "Lunch"
+                  Expenses:Food 10 CNY
+                  Assets:Bank
+                2024-01-06 "Buy"
+                  Assets:Stock 2 AAPL {100 CNY}
+                  Assets:Bank
+                2024-01-07 balance Assets:Bank 0 CNY
+                2024-01-08 "Dinner"
+                  Expenses:Food 10 CNY
+                  Assets:Bank
+            "#},
+        )
+        .unwrap();
+        temp_dir
+    }
+
+    fn load_with_cache(path: &Path, cache: &LedgerCache) -> Ledger {
+        Ledger::load_with_cache(path.to_path_buf(), "main.zhang".to_string(), data_source(), cache).unwrap()
+    }
+
+    #[test]
+    fn should_restore_same_ledger_from_cache() {
+        let temp_dir = setup();
+        let cache = LedgerCache::new(temp_dir.join("zhang.cache"), "0.1.0");
+        let ledger = load_with_cache(&temp_dir, &cache);
+
+        let cached_ledger = cache
+            .load(temp_dir.clone(), "main.zhang".to_string(), data_source())
+            .expect("cache should be valid");
+        assert_eq!(store_json(&ledger), store_json(&cached_ledger));
+        assert_eq!(ledger.visited_files, cached_ledger.visited_files);
+        assert_eq!(ledger.options.timezone, cached_ledger.options.timezone);
+        assert_eq!(ledger.directives, cached_ledger.directives);
+
+        let balances = cached_ledger.operations().single_account_latest_balances("Assets:Bank").unwrap();
+        assert_eq!(balances.len(), 1);
+        assert_eq!(balances[0].balance_number, BigDecimal::from(-10));
+        assert_eq!(cached_ledger.operations().errors().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn should_invalidate_cache_given_file_changed() {
+        let temp_dir = setup();
+        let cache = LedgerCache::new(temp_dir.join("zhang.cache"), "0.1.0");
+        load_with_cache(&temp_dir, &cache);
+
+        std::fs::write(
+            temp_dir.join("2024.zhang"),
+            indoc! {r#"
+                2024-01-05 "Lunch"
+                  Expenses:Food 20 CNY
+                  Assets:Bank
+            "#},
+        )
+        .unwrap();
+        assert!(cache.load(temp_dir.clone(), "main.zhang".to_string(), data_source()).is_none());
+
+        let ledger = load_with_cache(&temp_dir, &cache);
+        let balances = ledger.operations().single_account_latest_balances("Assets:Bank").unwrap();
+        assert_eq!(balances[0].balance_number, BigDecimal::from(-20));
+        assert!(cache.load(temp_dir.clone(), "main.zhang".to_string(), data_source()).is_some());
+    }
+
+    #[test]
+    fn should_reload_appended_directives_incrementally_after_restore() {
+        let temp_dir = setup();
+        let cache = LedgerCache::new(temp_dir.join("zhang.cache"), "0.1.0");
+        load_with_cache(&temp_dir, &cache);
+        let mut cached_ledger = cache
+            .load(temp_dir.clone(), "main.zhang".to_string(), data_source())
+            .expect("cache should be valid");
+
+        let content = std::fs::read_to_string(temp_dir.join("2024.zhang")).unwrap();
+        std::fs::write(
+            temp_dir.join("2024.zhang"),
+            format!("{}2024-01-09 \"Lunch\"\n  Expenses:Food 10 CNY\n  Assets:Bank\n", content),
+        )
+        .unwrap();
+        let load_result = data_source().load(temp_dir.to_string_lossy().to_string(), "main.zhang".to_string()).unwrap();
+        assert!(cached_ledger.incremental_process(&load_result.directives).unwrap());
+
+        let ledger = Ledger::load_with_data_source(temp_dir.clone(), "main.zhang".to_string(), data_source()).unwrap();
+        assert_eq!(store_json(&ledger), store_json(&cached_ledger));
+    }
+
+    #[test]
+    fn should_reload_early_change_from_restored_checkpoints() {
+        let temp_dir = setup();
+        let cache = LedgerCache::new(temp_dir.join("zhang.cache"), "0.1.0");
+        load_with_cache(&temp_dir, &cache);
+        let mut cached_ledger = cache
+            .load(temp_dir.clone(), "main.zhang".to_string(), data_source())
+            .expect("cache should be valid");
+
+        let content = std::fs::read_to_string(temp_dir.join("2024.zhang")).unwrap();
+        std::fs::write(temp_dir.join("2024.zhang"), content.replacen("10 CNY", "15 CNY", 1)).unwrap();
+        let load_result = data_source().load(temp_dir.to_string_lossy().to_string(), "main.zhang".to_string()).unwrap();
+        assert!(cached_ledger.incremental_process(&load_result.directives).unwrap());
+
+        let ledger = Ledger::load_with_data_source(temp_dir.clone(), "main.zhang".to_string(), data_source()).unwrap();
+        assert_eq!(store_json(&ledger), store_json(&cached_ledger));
+    }
+
+    #[test]
+    fn should_invalidate_cache_given_version_changed() {
+        let temp_dir = setup();
+        load_with_cache(&temp_dir, &LedgerCache::new(temp_dir.join("zhang.cache"), "0.1.0"));
+
+        let cache = LedgerCache::new(temp_dir.join("zhang.cache"), "0.2.0");
+        assert!(cache.load(temp_dir.clone(), "main.zhang".to_string(), data_source()).is_none());
+    }
+}
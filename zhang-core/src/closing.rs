@@ -0,0 +1,468 @@
+//! closing clears the income and expenses of a period into equity, and carries the balances into the next period:
+//! - the income and expenses booked in the period go into the current earnings account, which is `Equity:Earnings:Current` by default
+//! - the income and expenses booked before the period go into the previous earnings account, which is `Equity:Earnings:Previous` by default
+//! - the balances of assets, liabilities and equity at the end of period are opened in the next period, the current earnings become previous earnings.
+//!   lots with cost are opened with their cost and acquisition date, so the cost basis is kept in the next period
+//!
+//! the closing is a view of the ledger, nothing is written into the store.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use indexmap::IndexSet;
+use itertools::Itertools;
+use zhang_ast::amount::Amount;
+use zhang_ast::{Account, Date, Directive, Flag, Meta, Open, Posting, PostingCost, SpanInfo, Spanned, Transaction, ZhangString};
+
+use crate::constants::{
+    DEFAULT_CURRENT_EARNINGS_ACCOUNT, DEFAULT_OPENING_BALANCES_ACCOUNT, DEFAULT_PREVIOUS_EARNINGS_ACCOUNT, KEY_CURRENT_EARNINGS_ACCOUNT,
+    KEY_OPENING_BALANCES_ACCOUNT, KEY_PREVIOUS_EARNINGS_ACCOUNT,
+};
+use crate::ledger::Ledger;
+use crate::store::CommodityLotRecord;
+use crate::utils::date_range;
+use crate::{ZhangError, ZhangResult};
+
+type Inventory = BTreeMap<String, BigDecimal>;
+/// commodity lots by account
+pub struct PeriodClosingDomain {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// the open directives of earnings and opening balances accounts which are used but not opened in the ledger, dated at the end of period
+    pub opens: Vec<Open>,
+    /// the transaction clearing income and expenses into earnings, dated at the end of period
+    pub closing: Option<Transaction>,
+    /// the transaction opening the balances in the next period, dated at the day after the period
+    pub opening: Option<Transaction>,
+    /// balances of assets, liabilities and equity at the end of period after closing
+    pub balance_sheet: BTreeMap<String, Vec<Amount>>,
+}
+
+impl PeriodClosingDomain {
+    /// the open directives, the closing and opening transactions, which can be exported by [crate::data_type::DataType::export]
+    pub fn directives(&self) -> Vec<Spanned<Directive>> {
+        let opens = self.opens.iter().map(|open| Directive::Open(open.clone()));
+        let transactions = self.closing.iter().chain(self.opening.iter()).map(|trx| Directive::Transaction(trx.clone()));
+        opens
+            .chain(transactions)
+            .map(|directive| Spanned::new(directive, SpanInfo::default()))
+            .collect_vec()
+    }
+}
+
+fn end_of_date(ledger: &Ledger, date: NaiveDate) -> ZhangResult<DateTime<Utc>> {
+    date_range::end_of_date(&ledger.options.timezone, date)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok_or_else(|| ZhangError::CustomError(format!("cannot get the end of {}", date)))
+}
+
+fn postings(account: &str, inventory: &Inventory) -> ZhangResult<Vec<Posting>> {
+    let account = Account::from_str(account).map_err(|_| ZhangError::InvalidAccount)?;
+    Ok(inventory
+        .iter()
+        .filter(|(_, number)| !number.is_zero())
+        .map(|(currency, number)| Posting {
+            flag: None,
+            account: account.clone(),
+            units: Some(Amount::new(number.clone(), currency.clone())),
+            cost: None,
+            price: None,
+            comment: None,
+        })
+        .collect_vec())
+}
+
+/// the postings opening the inventory of account, lots with cost are opened with their cost and acquisition date.
+/// the weights of postings are subtracted from `residual`
+fn lot_postings(account: &str, inventory: &Inventory, lots: &[CommodityLotRecord], residual: &mut Inventory) -> ZhangResult<Vec<Posting>> {
+    let account = Account::from_str(account).map_err(|_| ZhangError::InvalidAccount)?;
+    let posting = |units: Amount, cost: Option<PostingCost>| Posting {
+        flag: None,
+        account: account.clone(),
+        units: Some(units),
+        cost,
+        price: None,
+        comment: None,
+    };
+    let mut postings = vec![];
+    for (currency, number) in inventory.iter() {
+        let mut remaining = number.clone();
+        for lot in lots.iter().filter(|lot| lot.commodity.eq(currency) && !lot.amount.is_zero()) {
+            let Some(cost) = &lot.cost else {
+                continue;
+            };
+            remaining -= &lot.amount;
+            *residual.entry(cost.currency.clone()).or_default() -= &lot.amount * &cost.number;
+            let lot_cost = PostingCost {
+                base: Some(cost.clone()),
+                date: lot.acquisition_date.map(Date::Date),
+            };
+            postings.push(posting(Amount::new(lot.amount.clone(), currency.clone()), Some(lot_cost)));
+        }
+        if !remaining.is_zero() {
+            *residual.entry(currency.clone()).or_default() -= &remaining;
+            postings.push(posting(Amount::new(remaining, currency.clone()), None));
+        }
+    }
+    Ok(postings)
+}
+
+fn open(date: NaiveDate, account: &str) -> ZhangResult<Open> {
+    Ok(Open {
+        date: Date::Date(date),
+        account: Account::from_str(account).map_err(|_| ZhangError::InvalidAccount)?,
+        commodities: vec![],
+        meta: Meta::default(),
+    })
+}
+
+fn transaction(date: Date, narration: String, postings: Vec<Posting>) -> Option<Transaction> {
+    if postings.is_empty() {
+        return None;
+    }
+    Some(Transaction {
+        date,
+        flag: Some(Flag::Okay),
+        payee: None,
+        narration: Some(ZhangString::quote(narration)),
+        tags: IndexSet::new(),
+        links: IndexSet::new(),
+        postings,
+        meta: Meta::default(),
+    })
+}
+
+/// close the period from `from` to `to`, both inclusive
+pub fn close_period(ledger: &Ledger, from: NaiveDate, to: NaiveDate) -> ZhangResult<PeriodClosingDomain> {
+    if from > to {
+        return Err(ZhangError::CustomError(format!("the start {} of period is after the end {}", from, to)));
+    }
+    let next_period = to
+        .checked_add_days(Days::new(1))
+        .ok_or_else(|| ZhangError::CustomError(format!("cannot open the period after {}", to)))?;
+    let before_period = from.pred_opt().map(|date| end_of_date(ledger, date)).transpose()?;
+    let end_of_period = end_of_date(ledger, to)?;
+
+    let operations = ledger.operations();
+    let current_earnings_account = operations
+        .option::<String>(KEY_CURRENT_EARNINGS_ACCOUNT)?
+        .unwrap_or_else(|| DEFAULT_CURRENT_EARNINGS_ACCOUNT.to_owned());
+    let previous_earnings_account = operations
+        .option::<String>(KEY_PREVIOUS_EARNINGS_ACCOUNT)?
+        .unwrap_or_else(|| DEFAULT_PREVIOUS_EARNINGS_ACCOUNT.to_owned());
+    let opening_balances_account = operations
+        .option::<String>(KEY_OPENING_BALANCES_ACCOUNT)?
+        .unwrap_or_else(|| DEFAULT_OPENING_BALANCES_ACCOUNT.to_owned());
+
+    let accounts: BTreeSet<String> = {
+        let store = operations.read();
+        store.accounts.keys().chain(store.posting_index.keys()).cloned().collect()
+    };
+    let balance = |account: &str, datetime: DateTime<Utc>| -> ZhangResult<Inventory> {
+        Ok(operations
+            .account_target_date_balance(account, datetime)?
+            .into_iter()
+            .map(|balance| (balance.balance_commodity, balance.balance_number))
+            .collect())
+    };
+
+    let mut closing_postings = vec![];
+    let mut current_earnings = Inventory::new();
+    let mut previous_earnings = Inventory::new();
+    let mut balance_sheet: BTreeMap<String, Inventory> = BTreeMap::new();
+    for account_name in accounts.iter() {
+        let account = Account::from_str(account_name).map_err(|_| ZhangError::InvalidAccount)?;
+        let end_balance = balance(account_name, end_of_period)?;
+        if account.is_income_statement_account() {
+            let start_balance = match before_period {
+                Some(datetime) => balance(account_name, datetime)?,
+                None => Inventory::new(),
+            };
+            for (currency, number) in end_balance.iter() {
+                let previous = start_balance.get(currency).cloned().unwrap_or_default();
+                *current_earnings.entry(currency.clone()).or_default() += number - &previous;
+                *previous_earnings.entry(currency.clone()).or_default() += previous;
+            }
+            let cleared = end_balance.into_iter().map(|(currency, number)| (currency, -number)).collect();
+            closing_postings.extend(postings(account_name, &cleared)?);
+        } else {
+            balance_sheet.insert(account_name.clone(), end_balance);
+        }
+    }
+    closing_postings.extend(postings(&current_earnings_account, &current_earnings)?);
+    closing_postings.extend(postings(&previous_earnings_account, &previous_earnings)?);
+    for (account, earnings) in [(&current_earnings_account, current_earnings), (&previous_earnings_account, previous_earnings)] {
+        let inventory = balance_sheet.entry(account.clone()).or_default();
+        for (currency, number) in earnings {
+            *inventory.entry(currency).or_default() += number;
+        }
+    }
+    balance_sheet.retain(|_, inventory| {
+        inventory.retain(|_, number| !number.is_zero());
+        !inventory.is_empty()
+    });
+
+    let mut opening_balances: BTreeMap<String, Inventory> = BTreeMap::new();
+    for (account, inventory) in balance_sheet.iter() {
+        let account = if account.eq(&current_earnings_account) {
+            &previous_earnings_account
+        } else {
+            account
+        };
+        let opening_inventory = opening_balances.entry(account.clone()).or_default();
+        for (currency, number) in inventory {
+            *opening_inventory.entry(currency.clone()).or_default() += number;
+        }
+    }
+    let lots = ledger.commodity_lots_at(&end_of_period, |_| true)?;
+    let mut residual = Inventory::new();
+    let mut opening_postings = vec![];
+    for (account, inventory) in opening_balances.iter() {
+        let account_lots = lots.get(account).map(Vec::as_slice).unwrap_or_default();
+        opening_postings.extend(lot_postings(account, inventory, account_lots, &mut residual)?);
+    }
+    if !opening_postings.is_empty() {
+        opening_postings.extend(postings(&opening_balances_account, &residual)?);
+    }
+
+    let posted_accounts: BTreeSet<&str> = closing_postings
+        .iter()
+        .chain(opening_postings.iter())
+        .map(|posting| posting.account.name())
+        .collect();
+    let opens = [&current_earnings_account, &previous_earnings_account, &opening_balances_account]
+        .into_iter()
+        .unique()
+        .filter(|account| posted_accounts.contains(account.as_str()) && !accounts.contains(*account))
+        .map(|account| open(to, account))
+        .collect::<ZhangResult<Vec<_>>>()?;
+
+    Ok(PeriodClosingDomain {
+        from,
+        to,
+        opens,
+        // the closing is dated at the end of period where the balances are measured, so it goes after every transaction of the last day
+        closing: transaction(
+            Date::Datetime(end_of_period.with_timezone(&ledger.options.timezone).naive_local()),
+            format!("Close income and expenses from {} to {}", from, to),
+            closing_postings,
+        ),
+        opening: transaction(Date::Date(next_period), format!("Opening balances after {}", to), opening_postings),
+        balance_sheet: balance_sheet
+            .into_iter()
+            .map(|(account, inventory)| {
+                let amounts = inventory.into_iter().map(|(currency, number)| Amount::new(number, currency)).collect_vec();
+                (account, amounts)
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use bigdecimal::{BigDecimal, Zero};
+    use chrono::NaiveDate;
+    use indoc::indoc;
+    use itertools::Itertools;
+    use zhang_ast::amount::Amount;
+    use zhang_ast::Transaction;
+
+    use crate::closing::PeriodClosingDomain;
+    use crate::data_type::text::ZhangDataType;
+    use crate::data_type::DataType;
+    use crate::store::CommodityLotRecord;
+    use crate::test::load_from_text;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn postings(trx: &Transaction) -> Vec<(String, Amount)> {
+        trx.postings
+            .iter()
+            .map(|posting| (posting.account.name().to_owned(), posting.units.clone().unwrap()))
+            .collect_vec()
+    }
+
+    fn export(closing: &PeriodClosingDomain) -> String {
+        closing
+            .directives()
+            .into_iter()
+            .map(|directive| ZhangDataType {}.export(directive))
+            .join("\n\n")
+    }
+
+    fn cny(account: &str, number: i32) -> (String, Amount) {
+        (account.to_owned(), Amount::new(BigDecimal::from(number), "CNY"))
+    }
+
+    const LEDGER: &str = indoc! {r#"
+        option "operating_currency" "CNY"
+        1970-01-01 open Assets:Bank
+        1970-01-01 open Income:Salary
+        1970-01-01 open Expenses:Food
+        1970-01-01 open Equity:Opening
+
+        2022-12-01 "Opening"
+          Assets:Bank 1000 CNY
+          Equity:Opening
+
+        2022-12-15 "Salary"
+          Assets:Bank 500 CNY
+          Income:Salary
+
+        2023-01-10 "Salary"
+          Assets:Bank 3000 CNY
+          Income:Salary
+
+        2023-02-01 "Lunch"
+          Expenses:Food 200 CNY
+          Assets:Bank
+
+        2024-01-05 "Lunch"
+          Expenses:Food 50 CNY
+          Assets:Bank
+    "#};
+
+    #[test]
+    fn should_clear_income_and_expenses_into_earnings() {
+        let ledger = load_from_text(LEDGER);
+        let closing = ledger.close_period(date(2023, 1, 1), date(2023, 12, 31)).unwrap();
+
+        let closing_trx = closing.closing.unwrap();
+        assert_eq!(closing_trx.date.naive_date(), date(2023, 12, 31));
+        assert_eq!(
+            postings(&closing_trx),
+            vec![
+                cny("Expenses:Food", -200),
+                cny("Income:Salary", 3500),
+                cny("Equity:Earnings:Current", -2800),
+                cny("Equity:Earnings:Previous", -500),
+            ]
+        );
+
+        assert_eq!(
+            closing.balance_sheet.keys().collect_vec(),
+            vec!["Assets:Bank", "Equity:Earnings:Current", "Equity:Earnings:Previous", "Equity:Opening"]
+        );
+        assert_eq!(closing.balance_sheet["Assets:Bank"], vec![Amount::new(BigDecimal::from(4300), "CNY")]);
+    }
+
+    #[test]
+    fn should_clear_timed_transactions_of_last_day_after_appending_closing() {
+        let text = indoc! {r#"
+            option "operating_currency" "CNY"
+            1970-01-01 open Assets:Bank
+            1970-01-01 open Expenses:Food
+
+            2023-12-31 18:30:00 "Dinner"
+              Expenses:Food 80 CNY
+              Assets:Bank
+        "#};
+        let ledger = load_from_text(text);
+        let closing = ledger.close_period(date(2023, 1, 1), date(2023, 12, 31)).unwrap();
+        assert_eq!(
+            closing.closing.as_ref().unwrap().date,
+            zhang_ast::Date::Datetime(date(2023, 12, 31).and_hms_opt(23, 59, 59).unwrap())
+        );
+
+        let closed = load_from_text(&format!("{}\n{}", text, export(&closing)));
+        let mut operations = closed.operations();
+        assert!(operations.errors().unwrap().is_empty());
+        let food = operations.single_account_latest_balances("Expenses:Food").unwrap().pop().unwrap();
+        assert!(food.balance_number.is_zero());
+    }
+
+    #[test]
+    fn should_open_balances_in_next_period() {
+        let ledger = load_from_text(LEDGER);
+        let closing = ledger.close_period(date(2023, 1, 1), date(2023, 12, 31)).unwrap();
+
+        let opening_trx = closing.opening.clone().unwrap();
+        assert_eq!(opening_trx.date.naive_date(), date(2024, 1, 1));
+        assert_eq!(
+            postings(&opening_trx),
+            vec![cny("Assets:Bank", 4300), cny("Equity:Earnings:Previous", -3300), cny("Equity:Opening", -1000)]
+        );
+
+        assert_eq!(
+            closing.opens.iter().map(|open| open.account.name()).collect_vec(),
+            vec!["Equity:Earnings:Current", "Equity:Earnings:Previous"]
+        );
+        let next_period = load_from_text(&format!(
+            indoc! {r#"
+                option "operating_currency" "CNY"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Income:Salary
+                1970-01-01 open Expenses:Food
+                1970-01-01 open Equity:Opening
+
+                {}
+            "#},
+            export(&closing)
+        ));
+        let mut operations = next_period.operations();
+        assert!(operations.errors().unwrap().is_empty());
+        let bank = operations.single_account_latest_balances("Assets:Bank").unwrap().pop().unwrap();
+        assert_eq!(bank.balance_number, BigDecimal::from(4300));
+    }
+
+    #[test]
+    fn should_open_lots_with_cost_in_next_period() {
+        let ledger = load_from_text(indoc! {r#"
+            option "operating_currency" "CNY"
+            1970-01-01 open Assets:Bank
+            1970-01-01 open Assets:Stock
+            1970-01-01 open Income:Gains
+
+            2023-01-10 "Buy"
+              Assets:Stock 10 AAPL {100 CNY}
+              Assets:Bank
+
+            2023-06-10 "Buy"
+              Assets:Stock 5 AAPL {120 CNY}
+              Assets:Bank
+
+            2024-02-01 "Sell"
+              Assets:Stock -10 AAPL {100 CNY} @ 150 CNY
+              Assets:Bank 1500 CNY
+              Income:Gains -500 CNY
+        "#});
+        let closing = ledger.close_period(date(2023, 1, 1), date(2023, 12, 31)).unwrap();
+
+        let next_period = load_from_text(&format!(
+            indoc! {r#"
+                option "operating_currency" "CNY"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Assets:Stock
+
+                {}
+            "#},
+            export(&closing)
+        ));
+        let mut operations = next_period.operations();
+        assert!(operations.errors().unwrap().is_empty());
+        let lots = next_period.store.read().unwrap().commodity_lots["Assets:Stock"]
+            .iter()
+            .filter(|lot| !lot.amount.is_zero())
+            .cloned()
+            .collect_vec();
+        let lot = |amount: i32, cost: i32, acquisition_date: NaiveDate| CommodityLotRecord {
+            commodity: "AAPL".to_owned(),
+            amount: BigDecimal::from(amount),
+            cost: Some(Amount::new(BigDecimal::from(cost), "CNY")),
+            acquisition_date: Some(acquisition_date),
+        };
+        assert_eq!(lots, vec![lot(10, 100, date(2023, 1, 10)), lot(5, 120, date(2023, 6, 10))]);
+        let bank = operations.single_account_latest_balances("Assets:Bank").unwrap().pop().unwrap();
+        assert_eq!(bank.balance_number, BigDecimal::from(-1600));
+    }
+
+    #[test]
+    fn should_reject_period_ending_before_start() {
+        let ledger = load_from_text(LEDGER);
+        assert!(ledger.close_period(date(2023, 12, 31), date(2023, 1, 1)).is_err());
+    }
+}
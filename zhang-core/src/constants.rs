@@ -5,6 +5,12 @@ pub const KEY_DEFAULT_ROUNDING: &str = "default_rounding";
 pub const KEY_DEFAULT_BALANCE_TOLERANCE_PRECISION: &str = "default_balance_tolerance_precision";
 pub const KEY_DEFAULT_COMMODITY_PRECISION: &str = "default_commodity_precision";
 pub const KEY_TIMEZONE: &str = "timezone";
+pub const KEY_CAPITAL_GAINS_ACCOUNT: &str = "capital_gains_account";
+pub const KEY_CURRENT_EARNINGS_ACCOUNT: &str = "current_earnings_account";
+pub const KEY_PREVIOUS_EARNINGS_ACCOUNT: &str = "previous_earnings_account";
+pub const KEY_OPENING_BALANCES_ACCOUNT: &str = "opening_balances_account";
+pub const KEY_BALANCE_INCLUDE_SUB_ACCOUNTS: &str = "balance_include_sub_accounts";
+pub const KEY_IMPLICIT_PRICES: &str = "implicit_prices";
 
 pub const KEY_FEATURES_PLUGIN: &str = "features.plugin";
 
@@ -13,15 +19,26 @@ pub const DEFAULT_OPERATING_CURRENCY: &str = "CNY";
 pub const DEFAULT_ROUNDING: Rounding = Rounding::RoundDown;
 pub const DEFAULT_BALANCE_TOLERANCE_PRECISION: i32 = 2;
 pub const DEFAULT_TIMEZONE: &str = "Asia/Hong_Kong";
+pub const DEFAULT_CURRENT_EARNINGS_ACCOUNT: &str = "Equity:Earnings:Current";
+pub const DEFAULT_PREVIOUS_EARNINGS_ACCOUNT: &str = "Equity:Earnings:Previous";
+pub const DEFAULT_OPENING_BALANCES_ACCOUNT: &str = "Equity:Opening-Balances";
 
 pub const DEFAULT_ROUNDING_PLAIN: &str = "RoundDown";
 pub const DEFAULT_COMMODITY_PRECISION_PLAIN: &str = "2";
 pub const DEFAULT_BALANCE_TOLERANCE_PRECISION_PLAIN: &str = "2";
 
 pub const DEFAULT_BOOKING_METHOD: &str = "FIFO";
+/// the decimal places of the average cost merged by average booking
+pub const AVERAGE_COST_SCALE: i64 = 8;
 
 pub const TRUE: &str = "true";
 
 pub const TXN_ID: &str = "txn_id";
+pub const RECURRING_ID: &str = "recurring_id";
+pub const OCCURRENCE_DATE: &str = "occurrence_date";
+pub const FORECAST_FLAG: &str = "#";
 
 pub const COMMODITY_GROUP: &str = "group";
+pub const COMMODITY_BALANCE_TOLERANCE: &str = "balance_tolerance";
+
+pub const BALANCE_INCLUDE_SUB_ACCOUNTS: &str = "include_sub_accounts";
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use strum::{AsRefStr, EnumString};
+use zhang_ast::{Directive, Spanned, StringOrAccount};
+
+/// custom type to declare the schema of another custom type in ledger, e.g.
+/// `1970-01-01 custom "custom-schema" "autopay" "account:account" "amount:amount" "enabled:boolean?"`
+pub const CUSTOM_SCHEMA: &str = "custom-schema";
+
+/// the type of the argument in custom directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum CustomArgumentType {
+    String,
+    Account,
+    /// number with commodity, like `"100 CNY"`
+    Amount,
+    Number,
+    /// `"true"` or `"false"`, case insensitive
+    Boolean,
+}
+
+impl CustomArgumentType {
+    pub fn accept(&self, value: &StringOrAccount) -> bool {
+        match (self, value) {
+            (CustomArgumentType::Account, StringOrAccount::Account(_)) => true,
+            (CustomArgumentType::Account, StringOrAccount::String(_)) => false,
+            (_, StringOrAccount::Account(_)) => false,
+            (CustomArgumentType::String, StringOrAccount::String(_)) => true,
+            (CustomArgumentType::Amount, StringOrAccount::String(value)) => {
+                let mut parts = value.as_str().split_whitespace();
+                matches!(
+                    (parts.next().map(BigDecimal::from_str), parts.next(), parts.next()),
+                    (Some(Ok(_)), Some(_), None)
+                )
+            }
+            (CustomArgumentType::Number, StringOrAccount::String(value)) => BigDecimal::from_str(value.as_str().trim()).is_ok(),
+            (CustomArgumentType::Boolean, StringOrAccount::String(value)) => {
+                value.as_str().eq_ignore_ascii_case("true") || value.as_str().eq_ignore_ascii_case("false")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomArgument {
+    pub name: String,
+    pub argument_type: CustomArgumentType,
+    pub optional: bool,
+}
+
+impl CustomArgument {
+    pub fn required(name: impl Into<String>, argument_type: CustomArgumentType) -> Self {
+        Self {
+            name: name.into(),
+            argument_type,
+            optional: false,
+        }
+    }
+    pub fn optional(name: impl Into<String>, argument_type: CustomArgumentType) -> Self {
+        Self {
+            name: name.into(),
+            argument_type,
+            optional: true,
+        }
+    }
+}
+
+/// the argument schema of a custom type, optional arguments must be placed after the required ones
+#[derive(Debug, Clone, Default)]
+pub struct CustomSchema {
+    pub arguments: Vec<CustomArgument>,
+}
+
+/// the reason why the values of custom directive do not match the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomArgumentViolation {
+    pub argument: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl CustomSchema {
+    pub fn new(arguments: Vec<CustomArgument>) -> Self {
+        Self { arguments }
+    }
+
+    pub fn validate(&self, values: &[StringOrAccount]) -> Vec<CustomArgumentViolation> {
+        let mut violations = vec![];
+        for (idx, argument) in self.arguments.iter().enumerate() {
+            match values.get(idx) {
+                Some(value) if !argument.argument_type.accept(value) => violations.push(CustomArgumentViolation {
+                    argument: argument.name.clone(),
+                    expected: argument.argument_type.as_ref().to_owned(),
+                    actual: value_as_str(value).to_owned(),
+                }),
+                None if !argument.optional => violations.push(CustomArgumentViolation {
+                    argument: argument.name.clone(),
+                    expected: argument.argument_type.as_ref().to_owned(),
+                    actual: "missing".to_owned(),
+                }),
+                _ => {}
+            }
+        }
+        if values.len() > self.arguments.len() {
+            violations.push(CustomArgumentViolation {
+                argument: format!("#{}", self.arguments.len() + 1),
+                expected: format!("at most {} arguments", self.arguments.len()),
+                actual: format!("{} arguments", values.len()),
+            });
+        }
+        violations
+    }
+
+    /// parse the values of `custom-schema` directive into the declared custom type and its schema.
+    /// each argument is declared as `name:type`, and optional arguments end with `?`
+    pub fn from_declaration(values: &[StringOrAccount]) -> Result<(String, CustomSchema), CustomArgumentViolation> {
+        let custom_type = match values.first() {
+            Some(StringOrAccount::String(custom_type)) => custom_type.as_str().to_owned(),
+            other => {
+                return Err(CustomArgumentViolation {
+                    argument: "custom_type".to_owned(),
+                    expected: CustomArgumentType::String.as_ref().to_owned(),
+                    actual: other.map(value_as_str).unwrap_or("missing").to_owned(),
+                })
+            }
+        };
+        let mut arguments: Vec<CustomArgument> = vec![];
+        for (idx, value) in values.iter().enumerate().skip(1) {
+            let declaration = value_as_str(value);
+            let (name, argument_type) = declaration.split_once(':').unwrap_or((declaration, ""));
+            let (argument_type, optional) = match argument_type.strip_suffix('?') {
+                Some(argument_type) => (argument_type, true),
+                None => (argument_type, false),
+            };
+            let argument_type = CustomArgumentType::from_str(argument_type.trim()).ok();
+            let follows_optional = !optional && arguments.last().map(|it| it.optional).unwrap_or(false);
+            match argument_type {
+                Some(argument_type) if !name.trim().is_empty() && !follows_optional => arguments.push(CustomArgument {
+                    name: name.trim().to_owned(),
+                    argument_type,
+                    optional,
+                }),
+                _ => {
+                    return Err(CustomArgumentViolation {
+                        argument: format!("#{}", idx + 1),
+                        expected: if follows_optional { "optional argument" } else { "name:type" }.to_owned(),
+                        actual: declaration.to_owned(),
+                    })
+                }
+            }
+        }
+        Ok((custom_type, CustomSchema::new(arguments)))
+    }
+
+    /// name the values with the argument names of schema
+    pub fn named_arguments(&self, values: &[StringOrAccount]) -> Vec<(String, String)> {
+        self.arguments
+            .iter()
+            .zip(values.iter())
+            .map(|(argument, value)| (argument.name.clone(), value_as_str(value).to_owned()))
+            .collect()
+    }
+}
+
+pub(crate) fn value_as_str(value: &StringOrAccount) -> &str {
+    match value {
+        StringOrAccount::String(value) => value.as_str(),
+        StringOrAccount::Account(account) => account.name(),
+    }
+}
+
+/// registry of custom types and their argument schemas.
+/// schemas come from [CustomRegistry::register] and the `custom-schema` directives of ledger, and the declared ones take precedence.
+/// custom directives whose type is not registered are stored without validation.
+#[derive(Debug, Clone)]
+pub struct CustomRegistry {
+    schemas: HashMap<String, CustomSchema>,
+    declared: HashMap<String, CustomSchema>,
+}
+
+impl CustomRegistry {
+    pub fn empty() -> Self {
+        Self {
+            schemas: HashMap::new(),
+            declared: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, custom_type: impl Into<String>, schema: CustomSchema) {
+        self.schemas.insert(custom_type.into(), schema);
+    }
+
+    pub fn schema(&self, custom_type: &str) -> Option<&CustomSchema> {
+        self.declared.get(custom_type).or_else(|| self.schemas.get(custom_type))
+    }
+
+    /// replace the declared schemas with the valid `custom-schema` directives, which apply to the whole ledger regardless of their dates
+    pub fn declare(&mut self, directives: &[Spanned<Directive>]) {
+        self.declared = directives
+            .iter()
+            .filter_map(|directive| match &directive.data {
+                Directive::Custom(custom) if custom.custom_type.as_str() == CUSTOM_SCHEMA => CustomSchema::from_declaration(&custom.values).ok(),
+                _ => None,
+            })
+            .collect();
+    }
+}
+
+impl Default for CustomRegistry {
+    fn default() -> Self {
+        let mut registry = CustomRegistry::empty();
+        registry.register(
+            "fava-option",
+            CustomSchema::new(vec![
+                CustomArgument::required("key", CustomArgumentType::String),
+                CustomArgument::optional("value", CustomArgumentType::String),
+            ]),
+        );
+        registry.register(
+            "fava-sidebar-link",
+            CustomSchema::new(vec![
+                CustomArgument::required("name", CustomArgumentType::String),
+                CustomArgument::required("url", CustomArgumentType::String),
+            ]),
+        );
+        registry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use zhang_ast::{Account, StringOrAccount, ZhangString};
+
+    use crate::custom::{CustomArgument, CustomArgumentType, CustomSchema};
+
+    fn declaration(values: &[&str]) -> Vec<StringOrAccount> {
+        values.iter().map(|value| string(value)).collect()
+    }
+
+    fn string(value: &str) -> StringOrAccount {
+        StringOrAccount::String(ZhangString::QuoteString(value.to_owned()))
+    }
+    fn account(value: &str) -> StringOrAccount {
+        StringOrAccount::Account(Account::from_str(value).unwrap())
+    }
+
+    fn schema() -> CustomSchema {
+        CustomSchema::new(vec![
+            CustomArgument::required("account", CustomArgumentType::Account),
+            CustomArgument::required("amount", CustomArgumentType::Amount),
+            CustomArgument::optional("enabled", CustomArgumentType::Boolean),
+        ])
+    }
+
+    #[test]
+    fn should_pass_given_valid_values() {
+        assert!(schema().validate(&[account("Assets:Card"), string("100 CNY")]).is_empty());
+        assert!(schema().validate(&[account("Assets:Card"), string("100 CNY"), string("TRUE")]).is_empty());
+    }
+
+    #[test]
+    fn should_reject_invalid_values() {
+        let violations = schema().validate(&[string("Assets:Card"), string("100")]);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].argument, "account");
+        assert_eq!(violations[1].argument, "amount");
+        assert_eq!(violations[1].actual, "100");
+    }
+
+    #[test]
+    fn should_reject_missing_and_extra_values() {
+        let violations = schema().validate(&[account("Assets:Card")]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].actual, "missing");
+
+        let violations = schema().validate(&[account("Assets:Card"), string("1 CNY"), string("true"), string("extra")]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].argument, "#4");
+    }
+
+    #[test]
+    fn should_parse_schema_declaration() {
+        let (custom_type, schema) = CustomSchema::from_declaration(&declaration(&["autopay", "account:account", "amount:amount", "enabled:boolean?"])).unwrap();
+        assert_eq!(custom_type, "autopay");
+        assert!(schema.validate(&[account("Assets:Card"), string("100 CNY")]).is_empty());
+        assert_eq!(schema.validate(&[string("Assets:Card"), string("100 CNY")]).len(), 1);
+    }
+
+    #[test]
+    fn should_reject_invalid_schema_declaration() {
+        let violation = CustomSchema::from_declaration(&declaration(&["autopay", "account:ledger"])).unwrap_err();
+        assert_eq!(violation.argument, "#2");
+        assert_eq!(violation.actual, "account:ledger");
+
+        let violation = CustomSchema::from_declaration(&declaration(&["autopay", "note:string?", "amount:amount"])).unwrap_err();
+        assert_eq!(violation.argument, "#3");
+        assert_eq!(violation.expected, "optional argument");
+    }
+}
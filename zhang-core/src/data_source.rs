@@ -1,5 +1,7 @@
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 use chrono::Datelike;
 use log::debug;
@@ -28,6 +30,11 @@ where
         unimplemented!()
     }
 
+    /// get the content of file in `visited_files` of load result, which is the path joined with entry
+    fn get_visited(&self, _entry: &Path, path: &Path) -> ZhangResult<Vec<u8>> {
+        self.get(path.to_string_lossy().to_string())
+    }
+
     fn load(&self, _entry: String, _endpoint: String) -> ZhangResult<LoadResult> {
         unimplemented!()
     }
@@ -47,6 +54,10 @@ where
     async fn async_get(&self, path: String) -> ZhangResult<Vec<u8>> {
         self.get(path)
     }
+
+    async fn async_get_visited(&self, entry: &Path, path: &Path) -> ZhangResult<Vec<u8>> {
+        self.get_visited(entry, path)
+    }
     async fn async_append(&self, ledger: &Ledger, directives: Vec<Directive>) -> ZhangResult<()> {
         self.append(ledger, directives)
     }
@@ -58,12 +69,14 @@ where
 
 pub struct LocalFileSystemDataSource {
     data_type: Box<dyn DataType<Carrier = String> + 'static + Send + Sync>,
+    parsed_files: ParsedFileCache,
 }
 
 impl LocalFileSystemDataSource {
     pub fn new<DT: DataType<Carrier = String> + Send + Sync + 'static>(data_type: DT) -> Self {
         LocalFileSystemDataSource {
             data_type: Box::new(data_type),
+            parsed_files: ParsedFileCache::default(),
         }
     }
     fn go_next(&self, directive: &Spanned<Directive>) -> Option<String> {
@@ -108,6 +121,7 @@ impl LocalFileSystemDataSource {
         let content_buf = ledger.data_source.get(endpoint.to_string_lossy().to_string())?;
         let content = String::from_utf8(content_buf)?;
 
+        let directive = ledger.operations().amount_formatter().pad_directive(directive);
         let appended_content = format!("{}\n{}\n", content, self.data_type.export(Spanned::new(directive, SpanInfo::default())));
 
         ledger
@@ -145,9 +159,10 @@ impl DataSource for LocalFileSystemDataSource {
                 continue;
             }
             let file_content = self.get(pathbuf.to_string_lossy().to_string())?;
-            let entity_directives = self
-                .data_type
-                .transform(String::from_utf8_lossy(&file_content).to_string(), Some(pathbuf.to_string_lossy().to_string()))?;
+            let entity_directives = self.parsed_files.get_or_parse(&pathbuf, &file_content, || {
+                self.data_type
+                    .transform(String::from_utf8_lossy(&file_content).to_string(), Some(pathbuf.to_string_lossy().to_string()))
+            })?;
 
             entity_directives.iter().filter_map(|directive| self.go_next(directive)).for_each(|buf| {
                 let fullpath = if buf.starts_with('/') {
@@ -160,6 +175,7 @@ impl DataSource for LocalFileSystemDataSource {
             directives.extend(entity_directives);
             visited.push(pathbuf);
         }
+        self.parsed_files.retain(&visited);
         Ok(LoadResult {
             directives,
             visited_files: visited,
@@ -178,6 +194,53 @@ impl DataSource for LocalFileSystemDataSource {
     }
 }
 
+/// parsed directives of each file, keyed by the hash of file content.
+/// data sources use it to skip parsing the unchanged files when reloading.
+#[derive(Default)]
+pub struct ParsedFileCache {
+    files: RwLock<HashMap<PathBuf, ParsedFile>>,
+}
+
+struct ParsedFile {
+    content_hash: u64,
+    directives: Vec<Spanned<Directive>>,
+}
+
+impl ParsedFileCache {
+    /// return the cached directives if the content is not changed, otherwise parse and cache them
+    pub fn get_or_parse(
+        &self, path: &Path, content: &[u8], parse: impl FnOnce() -> ZhangResult<Vec<Spanned<Directive>>>,
+    ) -> ZhangResult<Vec<Spanned<Directive>>> {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let content_hash = hasher.finish();
+
+        if let Some(parsed) = self.files.read().expect("poison lock detect").get(path) {
+            if parsed.content_hash == content_hash {
+                debug!("reuse parsed directives of file: {:?}", path.display());
+                return Ok(parsed.directives.clone());
+            }
+        }
+        let directives = parse()?;
+        self.files.write().expect("poison lock detect").insert(
+            path.to_path_buf(),
+            ParsedFile {
+                content_hash,
+                directives: directives.clone(),
+            },
+        );
+        Ok(directives)
+    }
+
+    /// drop the files which are no longer visited
+    pub fn retain(&self, visited_files: &[PathBuf]) {
+        self.files
+            .write()
+            .expect("poison lock detect")
+            .retain(|path, _| has_path_visited(visited_files, path));
+    }
+}
+
 pub struct LoadResult {
     pub directives: Vec<Spanned<Directive>>,
     pub visited_files: Vec<PathBuf>,
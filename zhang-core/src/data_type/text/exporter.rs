@@ -106,6 +106,33 @@ impl ZhangDataTypeExportable for Transaction {
     }
 }
 
+impl ZhangDataTypeExportable for Recurring {
+    type Output = String;
+    fn export(self) -> String {
+        let mut header = vec![
+            Some(self.date.export()),
+            Some("recurring".to_owned()),
+            Some(ZhangString::quote(self.frequency.to_string()).export()),
+            self.payee.map(|it| it.export()),
+            self.narration.map(|it| it.export()),
+        ];
+        let mut tags = self.tags.into_iter().map(|it| Some(format!("#{}", it))).collect_vec();
+        let mut links = self.links.into_iter().map(|it| Some(format!("^{}", it))).collect_vec();
+        header.append(&mut tags);
+        header.append(&mut links);
+
+        let mut recurring = self.postings.into_iter().map(|posting| format!("  {}", posting.export())).collect_vec();
+        recurring.insert(0, header.into_iter().flatten().join(" "));
+        if let Some(until) = self.until {
+            recurring.push(format!("  until: {}", until.format("%Y-%m-%d")));
+        }
+        let mut meta = self.meta.export().into_iter().map(|it| format!("  {}", it)).collect_vec();
+        recurring.append(&mut meta);
+
+        recurring.into_iter().join("\n")
+    }
+}
+
 impl ZhangDataTypeExportable for Posting {
     type Output = String;
     fn export(self) -> String {
@@ -199,7 +226,11 @@ impl ZhangDataTypeExportable for BalancePad {
 impl ZhangDataTypeExportable for BalanceCheck {
     type Output = String;
     fn export(self) -> String {
-        let line = [self.date.export(), "balance".to_string(), self.account.export(), self.amount.export()];
+        let amount = match self.tolerance {
+            Some(tolerance) => format!("{} ~ {} {}", self.amount.number, tolerance, self.amount.currency),
+            None => self.amount.export(),
+        };
+        let line = [self.date.export(), "balance".to_string(), self.account.export(), amount];
         append_meta(self.meta, line.join(" "))
     }
 }
@@ -321,6 +352,7 @@ impl ZhangDataTypeExportable for Directive {
             Directive::Close(close) => close.export(),
             Directive::Commodity(commodity) => commodity.export(),
             Directive::Transaction(txn) => txn.export(),
+            Directive::Recurring(recurring) => recurring.export(),
             Directive::BalancePad(pad) => pad.export(),
             Directive::BalanceCheck(check) => check.export(),
             Directive::Note(note) => note.export(),
@@ -399,6 +431,13 @@ mod test {
         "#}
         );
 
+        assert_parse!(
+            "balance check with tolerance",
+            indoc! {r#"
+            1970-01-01 balance Equity:hello 10.00 ~ 0.01 CNY
+        "#}
+        );
+
         assert_parse!(
             "balance pad",
             indoc! {r#"
@@ -659,4 +698,17 @@ mod test {
             "#}
         );
     }
+
+    #[test]
+    fn recurring() {
+        assert_parse!(
+            "recurring directive",
+            indoc! {r#"
+                2024-01-31 recurring "monthly" "Landlord" "Rent" #home ^rent
+                  Expenses:Rent 1000 CNY
+                  Assets:Bank
+                  until: 2024-12-31
+            "#}
+        );
+    }
 }
@@ -404,6 +404,47 @@ impl ZhangParser {
         Ok(Directive::Transaction(transaction))
     }
 
+    fn recurring(input: Node) -> Result<Directive> {
+        let ret: (
+            Date,
+            ZhangString,
+            Option<ZhangString>,
+            Option<ZhangString>,
+            (Vec<String>, Vec<String>),
+            Vec<(Option<Posting>, Option<(String, ZhangString)>)>,
+        ) = match_nodes!(input.children();
+            [date(date), quote_string(frequency), tags_or_links(tags_or_links), transaction_lines(lines)] => (date, frequency, None, None, tags_or_links, lines),
+            [date(date), quote_string(frequency), quote_string(payee), tags_or_links(tags_or_links), transaction_lines(lines)] => (date, frequency, Some(payee), None, tags_or_links, lines),
+            [date(date), quote_string(frequency), quote_string(payee), quote_string(narration), tags_or_links(tags_or_links), transaction_lines(lines)] => (date, frequency, Some(payee), Some(narration), tags_or_links, lines),
+        );
+        let frequency = RecurringFrequency::from_str(ret.1.as_str()).map_err(|_| input.error(format!("unknown recurring frequency {}", ret.1.as_str())))?;
+        let mut recurring = Recurring {
+            date: ret.0,
+            frequency,
+            payee: ret.2,
+            narration: ret.3,
+            tags: ret.4 .0.into_iter().collect(),
+            links: ret.4 .1.into_iter().collect(),
+            postings: vec![],
+            until: None,
+            meta: MultiValueMap::default(),
+        };
+
+        for line in ret.5 {
+            match line {
+                (Some(posting), None) => recurring.postings.push(posting),
+                (None, Some((key, value))) if key == "until" => {
+                    let until = NaiveDate::parse_from_str(value.as_str(), "%Y-%m-%d").map_err(|e| input.error(format!("invalid until date: {}", e)))?;
+                    recurring.until = Some(until);
+                }
+                (None, Some((key, value))) => recurring.meta.insert(key, value),
+                _ => {}
+            }
+        }
+
+        Ok(Directive::Recurring(recurring))
+    }
+
     fn commodity(input: Node) -> Result<Directive> {
         let ret = match_nodes!(input.into_children();
             [date(date), commodity_name(name)] => (date, name, Meta::default()),
@@ -471,10 +512,18 @@ impl ZhangParser {
         }))
     }
 
+    fn balance_tolerance(input: Node) -> Result<BigDecimal> {
+        let ret: BigDecimal = match_nodes!(input.into_children();
+            [number_expr(tolerance)] => tolerance,
+        );
+        Ok(ret)
+    }
+
     fn balance(input: Node) -> Result<Directive> {
-        let ret: (Date, Account, BigDecimal, String, Option<Account>) = match_nodes!(input.into_children();
-            [date(date), account_name(name), number_expr(amount), commodity_name(commodity)] => (date, name, amount, commodity, None),
-            [date(date), account_name(name), number_expr(amount), commodity_name(commodity), account_name(pad)] => (date, name, amount, commodity, Some(pad)),
+        let ret: (Date, Account, BigDecimal, String, Option<Account>, Option<BigDecimal>) = match_nodes!(input.into_children();
+            [date(date), account_name(name), number_expr(amount), commodity_name(commodity)] => (date, name, amount, commodity, None, None),
+            [date(date), account_name(name), number_expr(amount), balance_tolerance(tolerance), commodity_name(commodity)] => (date, name, amount, commodity, None, Some(tolerance)),
+            [date(date), account_name(name), number_expr(amount), commodity_name(commodity), account_name(pad)] => (date, name, amount, commodity, Some(pad), None),
         );
         if let Some(pad) = ret.4 {
             Ok(Directive::BalancePad(BalancePad {
@@ -489,6 +538,7 @@ impl ZhangParser {
                 date: ret.0,
                 account: ret.1,
                 amount: Amount::new(ret.2, ret.3),
+                tolerance: ret.5,
                 meta: Default::default(),
             }))
         }
@@ -608,6 +658,7 @@ impl ZhangParser {
             [valuable_comment(item)] => Some(Directive::Comment(Comment { content:item })),
 
             [transaction(item)] => Some(item),
+            [recurring(item)] => Some(item),
             [empty_space_line(_)] => None,
             [metable_head(head)] => Some(head),
             [metable_head(head), metas(meta)] => {
@@ -725,6 +776,19 @@ mod test {
                     date: Date::DateHour(NaiveDate::from_ymd_opt(2101, 10, 10).unwrap().and_hms_opt(10, 10, 0).unwrap()),
                     account: Account::from_str("Assets:Hello").unwrap(),
                     amount: Amount::new(BigDecimal::from(123i32), "CNY"),
+                    tolerance: None,
+                    meta: Default::default()
+                }),
+                balance.data
+            );
+
+            let balance = parse("2101-10-10 10:10 balance Assets:Hello 123.45 ~ 0.01 CNY", None).unwrap().remove(0);
+            assert_eq!(
+                Directive::BalanceCheck(BalanceCheck {
+                    date: Date::DateHour(NaiveDate::from_ymd_opt(2101, 10, 10).unwrap().and_hms_opt(10, 10, 0).unwrap()),
+                    account: Account::from_str("Assets:Hello").unwrap(),
+                    amount: Amount::new(BigDecimal::from_str("123.45").unwrap(), "CNY"),
+                    tolerance: Some(BigDecimal::from_str("0.01").unwrap()),
                     meta: Default::default()
                 }),
                 balance.data
@@ -1,29 +1,36 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::ops::{Add, AddAssign, Sub};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::{Add, AddAssign, Mul, Sub};
 use std::str::FromStr;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use bigdecimal::{BigDecimal, Zero};
-use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use bigdecimal::{BigDecimal, RoundingMode, Signed, Zero};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use chrono_tz::Tz;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use log::debug;
 use serde::Deserialize;
 use uuid::Uuid;
-use zhang_ast::amount::Amount;
+use zhang_ast::amount::{Amount, CalculatedAmount};
 use zhang_ast::error::ErrorKind;
 use zhang_ast::utils::inventory::BookingMethod;
-use zhang_ast::{Account, AccountType, Currency, Date, Flag, Meta, PostingCost, Rounding, SpanInfo, Transaction};
+use zhang_ast::{Account, AccountType, BudgetPeriod, Currency, Date, Flag, Meta, PostingCost, Rounding, SpanInfo, Transaction};
 
+use crate::constants::{AVERAGE_COST_SCALE, DEFAULT_COMMODITY_PRECISION, DEFAULT_ROUNDING, KEY_DEFAULT_COMMODITY_PRECISION, KEY_DEFAULT_ROUNDING};
 use crate::domains::schemas::{
-    AccountBalanceDomain, AccountDailyBalanceDomain, AccountDomain, AccountJournalDomain, AccountStatus, CommodityDomain, ErrorDomain, MetaDomain, MetaType,
-    OptionDomain, PriceDomain, TransactionInfoDomain,
+    AccountBalanceDomain, AccountDailyBalanceDomain, AccountDomain, AccountJournalDomain, AccountJournalItemDomain, AccountStatus, CommodityDomain,
+    ConvertedPriceDomain, ErrorDomain, MetaDomain, MetaType, OptionDomain, PriceDomain, PriceSource, TransactionInfoDomain,
 };
+use crate::query::QueryResult;
 use crate::store::{
-    BudgetDomain, BudgetEvent, BudgetEventType, BudgetIntervalDetail, CommodityLotRecord, DocumentDomain, DocumentType, PostingDomain, Store, TransactionDomain,
+    BudgetDomain, BudgetEvent, BudgetEventType, BudgetIntervalDetail, BudgetTarget, CommodityLotRecord, CustomDomain, DocumentDomain, DocumentType,
+    EventDomain, NoteDomain, PostingDomain, RealizedGainDomain, Store, TransactionDomain,
 };
+use crate::utils::calculable::Calculable;
+use crate::utils::date_range::beginning_of_date;
+use crate::utils::formatter::AmountFormatter;
 use crate::utils::id::FromSpan;
+use crate::utils::price_grip::PriceGrip;
 use crate::{ZhangError, ZhangResult};
 
 pub mod schemas;
@@ -56,6 +63,48 @@ pub struct AccountCommodityLot {
     pub acquisition_date: Option<NaiveDate>,
 }
 
+/// lot with cost, valued by the market price at a given date
+#[derive(Debug, Clone)]
+pub struct HoldingDomain {
+    pub account: Account,
+    pub commodity: Currency,
+    pub amount: BigDecimal,
+    /// the cost per unit of the lot
+    pub cost: Amount,
+    pub acquisition_date: Option<NaiveDate>,
+    /// `amount * cost`
+    pub cost_basis: Amount,
+    /// the price per unit in cost commodity, none if no price is found
+    pub price: Option<ConvertedPriceDomain>,
+    pub market_value: Option<Amount>,
+    /// `market_value - cost_basis`
+    pub unrealized_gain: Option<Amount>,
+}
+
+/// node of account tree, the top nodes are account types like `Assets`
+#[derive(Debug, Clone)]
+pub struct AccountTreeDomain {
+    pub name: String,
+    /// none for the intermediate accounts which are not opened, like `Expenses:Food` with only `Expenses:Food:Lunch` opened
+    pub status: Option<AccountStatus>,
+    /// balance of the account itself
+    pub balance: CalculatedAmount,
+    /// balance of the account and all its sub-accounts
+    pub total: CalculatedAmount,
+    pub children: Vec<AccountTreeDomain>,
+}
+
+/// progress of a budget target in an interval
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BudgetTargetProgressDomain {
+    pub target: BudgetTarget,
+    /// the assigned amount for interval targets, the available amount for save-by goals, or the activity amount for spending caps
+    pub current: Amount,
+    /// the amount to assign or save to reach the target, or the amount can still be spent under the spending cap
+    pub remaining: Amount,
+    pub achieved: bool,
+}
+
 pub struct Operations {
     pub timezone: Tz,
     pub store: Arc<RwLock<Store>>,
@@ -92,6 +141,94 @@ impl Operations {
         }
         Ok(ret)
     }
+
+    /// holdings of the lots with cost, valued by the latest prices at the datetime.
+    /// lots without cost are skipped since they have no cost basis.
+    pub(crate) fn lot_holdings(
+        &mut self, commodity_lots: HashMap<String, Vec<CommodityLotRecord>>, datetime: NaiveDateTime,
+    ) -> ZhangResult<Vec<HoldingDomain>> {
+        let lots = commodity_lots
+            .into_iter()
+            .flat_map(|(account, lots)| lots.into_iter().map(move |lot| (account.clone(), lot)))
+            .filter(|(_, lot)| !lot.amount.is_zero())
+            .filter_map(|(account, lot)| lot.cost.clone().map(|cost| (account, lot, cost)))
+            .sorted_by(|(a, a_lot, _), (b, b_lot, _)| {
+                a.cmp(b)
+                    .then_with(|| a_lot.commodity.cmp(&b_lot.commodity))
+                    .then_with(|| a_lot.acquisition_date.cmp(&b_lot.acquisition_date))
+            })
+            .collect_vec();
+
+        let mut ret = vec![];
+        for (account, lot, cost) in lots {
+            let cost_basis = Amount::new((&lot.amount).mul(&cost.number), cost.currency.clone());
+            let price = self.get_price(datetime, &lot.commodity, &cost.currency)?;
+            let market_value = price.as_ref().map(|price| Amount::new((&lot.amount).mul(&price.amount), cost.currency.clone()));
+            let unrealized_gain = market_value
+                .as_ref()
+                .map(|market_value| Amount::new((&market_value.number).sub(&cost_basis.number), cost.currency.clone()));
+            ret.push(HoldingDomain {
+                account: Account::from_str(&account).map_err(|_| ZhangError::InvalidAccount)?,
+                commodity: lot.commodity,
+                amount: lot.amount,
+                cost,
+                acquisition_date: lot.acquisition_date,
+                cost_basis,
+                price,
+                market_value,
+                unrealized_gain,
+            });
+        }
+        Ok(ret)
+    }
+
+    /// all event types, sorted by name
+    pub fn event_types(&self) -> ZhangResult<Vec<String>> {
+        let store = self.read();
+        Ok(store.events.keys().cloned().sorted().collect_vec())
+    }
+
+    /// timeline of the given event type, ordered by datetime
+    pub fn events(&self, event_type: impl AsRef<str>) -> ZhangResult<Vec<EventDomain>> {
+        let store = self.read();
+        Ok(store.events.get(event_type.as_ref()).cloned().unwrap_or_default())
+    }
+
+    /// the value of the given event type at the datetime, which is the latest event happened at or before it
+    pub fn event_value(&self, event_type: impl AsRef<str>, datetime: DateTime<Tz>) -> ZhangResult<Option<EventDomain>> {
+        let store = self.read();
+        Ok(store.events.get(event_type.as_ref()).and_then(|timeline| latest_event(timeline, datetime)))
+    }
+
+    /// the values of all event types active at the datetime, sorted by event type
+    pub fn active_events(&self, datetime: DateTime<Tz>) -> ZhangResult<Vec<EventDomain>> {
+        let store = self.read();
+        Ok(store
+            .events
+            .values()
+            .filter_map(|timeline| latest_event(timeline, datetime))
+            .sorted_by(|a, b| a.event_type.cmp(&b.event_type))
+            .collect_vec())
+    }
+
+    /// custom directives of the given custom type, ordered by datetime
+    pub fn customs(&self, custom_type: impl AsRef<str>) -> ZhangResult<Vec<CustomDomain>> {
+        let store = self.read();
+        Ok(store.customs.get(custom_type.as_ref()).cloned().unwrap_or_default())
+    }
+
+    /// realized gains of reduced lots, filtered by the datetime range if given
+    pub fn realized_gains(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> ZhangResult<Vec<RealizedGainDomain>> {
+        let store = self.read();
+        Ok(store
+            .realized_gains
+            .iter()
+            .filter(|gain| from.map(|from| gain.datetime >= from).unwrap_or(true))
+            .filter(|gain| to.map(|to| gain.datetime <= to).unwrap_or(true))
+            .sorted_by_key(|gain| gain.datetime)
+            .cloned()
+            .collect_vec())
+    }
 }
 
 impl Operations {
@@ -106,7 +243,9 @@ impl Operations {
 impl Operations {
     /// insert or update account
     /// if account exists, then update its status only
-    pub(crate) fn insert_or_update_account(&mut self, datetime: DateTime<Tz>, account: Account, status: AccountStatus, alias: Option<&str>) -> ZhangResult<()> {
+    pub(crate) fn insert_or_update_account(
+        &mut self, datetime: DateTime<Tz>, account: Account, status: AccountStatus, alias: Option<&str>, commodities: &[String],
+    ) -> ZhangResult<()> {
         let mut store = self.write();
         let account_domain = store.accounts.entry(account.name().to_owned()).or_insert_with(|| AccountDomain {
             date: datetime.naive_local(),
@@ -114,6 +253,7 @@ impl Operations {
             name: account.name().to_owned(),
             status,
             alias: alias.map(|it| it.to_owned()),
+            commodities: commodities.to_vec(),
         });
 
         // if account exists, the property only can be changed is status;
@@ -198,8 +338,9 @@ impl Operations {
             inferred_amount,
             previous_amount,
             after_amount,
+            realized_gain: None,
         };
-        store.postings.push(posting.clone());
+        store.insert_posting(posting.clone());
         let txn_header = store
             .transactions
             .get_mut(trx_id)
@@ -208,6 +349,66 @@ impl Operations {
         Ok(())
     }
 
+    /// insert note of account
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn insert_note(
+        &mut self, id: &Uuid, datetime: DateTime<Tz>, account: Account, comment: &str, tags: Vec<String>, links: Vec<String>, span: &SpanInfo,
+    ) -> ZhangResult<()> {
+        let mut store = self.write();
+
+        let sequence = store.notes.len() as i32 + 1;
+        store.notes.push(NoteDomain {
+            id: *id,
+            sequence,
+            datetime,
+            account,
+            comment: comment.to_owned(),
+            tags,
+            links,
+            span: span.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// insert event into the timeline of its event type
+    pub(crate) fn insert_event(&mut self, id: &Uuid, datetime: DateTime<Tz>, event_type: &str, description: &str, span: &SpanInfo) -> ZhangResult<()> {
+        let mut store = self.write();
+
+        let timeline = store.events.entry(event_type.to_owned()).or_default();
+        let idx = timeline.partition_point(|event| event.datetime <= datetime);
+        timeline.insert(
+            idx,
+            EventDomain {
+                id: *id,
+                datetime,
+                event_type: event_type.to_owned(),
+                description: description.to_owned(),
+                span: span.clone(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// insert custom directive under its custom type
+    pub(crate) fn insert_custom(
+        &mut self, id: &Uuid, datetime: DateTime<Tz>, custom_type: &str, values: Vec<String>, arguments: IndexMap<String, String>, span: &SpanInfo,
+    ) -> ZhangResult<()> {
+        let mut store = self.write();
+
+        store.customs.entry(custom_type.to_owned()).or_default().push(CustomDomain {
+            id: *id,
+            datetime,
+            custom_type: custom_type.to_owned(),
+            values,
+            arguments,
+            span: span.clone(),
+        });
+
+        Ok(())
+    }
+
     /// insert document
     /// datetime means:
     ///  - for transaction document: transaction datetime
@@ -226,13 +427,16 @@ impl Operations {
     }
 
     /// insert single price
-    pub(crate) fn insert_price(&mut self, datetime: DateTime<Tz>, commodity: &str, amount: &BigDecimal, target_commodity: &str) -> ZhangResult<()> {
+    pub(crate) fn insert_price(
+        &mut self, datetime: DateTime<Tz>, commodity: &str, amount: &BigDecimal, target_commodity: &str, source: PriceSource,
+    ) -> ZhangResult<()> {
         let mut store = self.write();
-        store.prices.push(PriceDomain {
+        store.insert_price(PriceDomain {
             datetime: datetime.naive_local(),
             commodity: commodity.to_owned(),
             amount: amount.clone(),
             target_commodity: target_commodity.to_owned(),
+            source,
         });
         Ok(())
     }
@@ -240,16 +444,9 @@ impl Operations {
     pub(crate) fn account_target_day_balance(&mut self, account_name: &str, datetime: DateTime<Tz>, currency: &str) -> ZhangResult<Option<AccountAmount>> {
         let store = self.read();
 
-        let account = Account::from_str(account_name).map_err(|_| ZhangError::InvalidAccount)?;
+        Account::from_str(account_name).map_err(|_| ZhangError::InvalidAccount)?;
 
-        let posting: Option<&PostingDomain> = store
-            .postings
-            .iter()
-            .filter(|posting| posting.account.eq(&account))
-            .filter(|posting| posting.after_amount.currency.eq(&currency))
-            .filter(|posting| posting.trx_datetime.le(&datetime))
-            .sorted_by_key(|posting| posting.trx_datetime)
-            .next_back();
+        let posting = store.latest_account_commodity_posting(account_name, currency, &datetime);
 
         Ok(posting.map(|it| AccountAmount {
             number: it.after_amount.number.clone(),
@@ -257,6 +454,20 @@ impl Operations {
         }))
     }
 
+    /// latest balances of the account and all of its sub-accounts at the datetime, keyed by account name
+    pub(crate) fn account_tree_target_day_balances(
+        &mut self, account_name: &str, datetime: DateTime<Tz>, currency: &str,
+    ) -> ZhangResult<BTreeMap<String, BigDecimal>> {
+        let store = self.read();
+
+        let ret = store
+            .account_tree_names(account_name)
+            .filter_map(|name| store.latest_account_commodity_posting(name, currency, &datetime))
+            .map(|posting| (posting.account.name().to_owned(), posting.after_amount.number.clone()))
+            .collect();
+        Ok(ret)
+    }
+
     pub(crate) fn default_account_lot(&mut self, account_name: &str, currency: &str) -> ZhangResult<CommodityLotRecord> {
         let mut store = self.write();
         let entry = store.commodity_lots.entry(account_name.to_owned()).or_default();
@@ -292,43 +503,21 @@ impl Operations {
         let mut store = self.write();
         let entry = store.commodity_lots.entry(account_name.to_owned()).or_default();
 
-        let mut option = entry
-            .iter()
-            // match commodity
-            .filter(|lot| lot.commodity.eq(currency))
-            // match cost, works with empty cost
-            .filter(|it| {
-                if lot_meta.base.is_some() {
-                    it.cost.eq(&lot_meta.base)
-                } else {
-                    it.cost.is_some()
-                }
-            })
-            // match cost date
-            .filter(|it| {
-                if lot_meta.base.is_some() {
-                    // if cost date in lot meta is defined, use txn date
-                    it.acquisition_date.eq(&lot_meta.date.as_ref().map(|it| it.naive_date()).or(Some(txn_date)))
-                } else {
-                    // if cost  in meta is null, return all lots
-                    true
-                }
-            });
+        let mut option = entry.iter().filter(|lot| lot_match_meta(lot, currency, lot_meta, txn_date));
 
         let lot_record = match booking_method {
-            BookingMethod::Fifo => option.next().cloned(),
+            // ambiguity of strict booking is checked before reduction, so the first matched lot is picked here
+            BookingMethod::Fifo | BookingMethod::Strict => option.next().cloned(),
             BookingMethod::Lifo => option.next_back().cloned(),
-            BookingMethod::Average => {
-                unimplemented!()
-            }
-            BookingMethod::AverageOnly => {
-                unimplemented!()
-            }
-            BookingMethod::Strict => {
-                unimplemented!()
-            }
+            // lots of average booking account are merged after each posting, so there is at most one lot with cost
+            BookingMethod::Average | BookingMethod::AverageOnly => option.next().cloned(),
+            // none booking does not do lot matching, reduction is recorded as a new lot
             BookingMethod::None => {
-                unimplemented!()
+                if lot_meta.base.is_some() {
+                    option.next().cloned()
+                } else {
+                    None
+                }
             }
         };
         if let Some(record) = lot_record {
@@ -353,6 +542,104 @@ impl Operations {
         }
     }
 
+    /// get all lots matching the lot meta, used to detect the ambiguous reduction of strict booking
+    pub(crate) fn account_lots_by_meta(
+        &self, account_name: &str, currency: &str, lot_meta: &PostingCost, txn_date: NaiveDate,
+    ) -> ZhangResult<Vec<CommodityLotRecord>> {
+        let store = self.read();
+        Ok(store
+            .commodity_lots
+            .get(account_name)
+            .map(|lots| {
+                lots.iter()
+                    .filter(|lot| lot_match_meta(lot, currency, lot_meta, txn_date))
+                    .cloned()
+                    .collect_vec()
+            })
+            .unwrap_or_default())
+    }
+
+    /// merge all held lots with cost into one lot with the weighted average cost, which is used by average booking.
+    /// negative lots left by failed reductions are kept apart, so they never change the average cost
+    pub(crate) fn average_account_lots(&mut self, account_name: &str, currency: &str) -> ZhangResult<()> {
+        let mut store = self.write();
+        let entry = store.commodity_lots.entry(account_name.to_owned()).or_default();
+
+        let (cost_lots, mut rest_lots): (Vec<CommodityLotRecord>, Vec<CommodityLotRecord>) = entry
+            .drain(..)
+            .partition(|lot| lot.commodity.eq(currency) && lot.cost.is_some() && lot.amount.is_positive());
+
+        let grouped_lots = cost_lots.into_iter().into_group_map_by(|lot| lot.cost.as_ref().map(|it| it.currency.clone()));
+        for (cost_currency, lots) in grouped_lots.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+            if lots.len() == 1 {
+                rest_lots.extend(lots);
+                continue;
+            }
+            let total_amount: BigDecimal = lots.iter().map(|lot| &lot.amount).sum();
+            if total_amount.is_zero() {
+                continue;
+            }
+            let total_cost: BigDecimal = lots
+                .iter()
+                .map(|lot| (&lot.amount).mul(lot.cost.as_ref().map(|it| it.number.clone()).unwrap_or_default()))
+                .sum();
+            rest_lots.push(CommodityLotRecord {
+                commodity: currency.to_owned(),
+                amount: total_amount.clone(),
+                acquisition_date: lots.iter().filter_map(|lot| lot.acquisition_date).min(),
+                cost: cost_currency.map(|cost_currency| Amount::new(average_cost(&lots, total_cost / total_amount), cost_currency)),
+            });
+        }
+        *entry = rest_lots;
+        Ok(())
+    }
+
+    /// record the realized gain of reducing the lot, which is calculated as `amount * (price - cost)`
+    /// return none if the lot has no cost, or the cost and price are not in the same commodity
+    pub(crate) fn insert_realized_gain(
+        &mut self, trx_id: &Uuid, posting_idx: usize, account_name: &str, lot_record: &CommodityLotRecord, amount: &BigDecimal, price: &Amount,
+    ) -> ZhangResult<Option<Amount>> {
+        let Some(cost) = lot_record.cost.as_ref().filter(|cost| cost.currency.eq(&price.currency)) else {
+            return Ok(None);
+        };
+        let gain = Amount::new(amount.mul((&price.number).sub(&cost.number)), price.currency.clone());
+
+        let mut store = self.write();
+        let posting_id = Uuid::from_txn_posting(trx_id, posting_idx);
+        let datetime = store
+            .transactions
+            .get(trx_id)
+            .map(|trx| trx.datetime)
+            .expect("invalid context: cannot find txn header when inserting realized gains");
+
+        store.realized_gains.push(RealizedGainDomain {
+            trx_id: *trx_id,
+            posting_id,
+            datetime,
+            account: Account::from_str(account_name).map_err(|_| ZhangError::InvalidAccount)?,
+            commodity: lot_record.commodity.clone(),
+            amount: amount.clone(),
+            cost: cost.clone(),
+            price: price.clone(),
+            acquisition_date: lot_record.acquisition_date,
+            gain: gain.clone(),
+        });
+
+        let store = &mut *store;
+        let postings = store
+            .postings
+            .iter_mut()
+            .chain(store.transactions.get_mut(trx_id).into_iter().flat_map(|trx| trx.postings.iter_mut()))
+            .filter(|posting| posting.id.eq(&posting_id));
+        for posting in postings {
+            posting.realized_gain = Some(match posting.realized_gain.take() {
+                Some(realized_gain) => Amount::new((&realized_gain.number).add(&gain.number), realized_gain.currency),
+                None => gain.clone(),
+            });
+        }
+        Ok(Some(gain))
+    }
+
     pub(crate) fn update_account_lot(&mut self, account_name: &str, lot_record: &CommodityLotRecord, amount: &BigDecimal) -> ZhangResult<()> {
         let mut store = self.write();
         let entry = store.commodity_lots.entry(account_name.to_owned()).or_default();
@@ -425,48 +712,60 @@ impl Operations {
     pub fn accounts_latest_balance(&mut self) -> ZhangResult<Vec<AccountDailyBalanceDomain>> {
         let store = self.read();
 
-        let mut ret: HashMap<Account, IndexMap<Currency, BTreeMap<NaiveDate, Amount>>> = HashMap::new();
-
-        for posting in store.postings.iter().cloned().sorted_by_key(|posting| posting.trx_datetime) {
-            let posting: PostingDomain = posting;
-            let date = posting.trx_datetime.naive_local().date();
-
-            let account_inventory = ret.entry(posting.account).or_default();
-            let dated_amount = account_inventory.entry(posting.after_amount.currency.clone()).or_default();
-            dated_amount.insert(date, posting.after_amount);
-        }
-
-        Ok(ret
-            .into_iter()
-            .flat_map(|(account, account_inventory)| {
-                account_inventory
+        Ok(store
+            .posting_index
+            .keys()
+            .flat_map(|account_name| {
+                store
+                    .account_currencies(account_name)
                     .into_iter()
-                    .map(|(_, mut dated)| {
-                        let (date, amount) = dated.pop_last().expect("");
-                        AccountDailyBalanceDomain {
-                            date,
-                            account: account.name().to_owned(),
-                            balance_number: amount.number,
-                            balance_commodity: amount.currency,
-                        }
+                    .filter_map(|currency| store.account_commodity_postings(account_name, currency).next_back())
+                    .map(|posting| AccountDailyBalanceDomain {
+                        date: posting.trx_datetime.naive_local().date(),
+                        account: posting.account.name().to_owned(),
+                        balance_number: posting.after_amount.number.clone(),
+                        balance_commodity: posting.after_amount.currency.clone(),
                     })
                     .collect_vec()
             })
             .collect_vec())
     }
 
-    pub fn get_price(&mut self, date: NaiveDateTime, from: impl AsRef<str>, to: impl AsRef<str>) -> ZhangResult<Option<PriceDomain>> {
+    /// the price of `from` in `to` at the date, using the latest price of each commodity pair at or before the date.
+    /// inverse prices and intermediate commodities are used if there is no direct price.
+    pub fn get_price(&mut self, date: NaiveDateTime, from: impl AsRef<str>, to: impl AsRef<str>) -> ZhangResult<Option<ConvertedPriceDomain>> {
         let store = self.read();
-        let x = store
-            .prices
+        let latest_prices: HashMap<(&str, &str), &PriceDomain> = store
+            .latest_prices(date)
+            .map(|price| ((price.commodity.as_str(), price.target_commodity.as_str()), price))
+            .collect();
+
+        let mut grip = PriceGrip::default();
+        for price in latest_prices.values() {
+            grip.insert(price.commodity.clone(), price.target_commodity.clone(), price.amount.clone());
+        }
+
+        let Some((amount, path)) = grip.convert(&from.as_ref().to_owned(), &to.as_ref().to_owned()) else {
+            return Ok(None);
+        };
+        let datetime = path
             .iter()
-            .filter(|price| price.commodity.eq(from.as_ref()))
-            .filter(|price| price.target_commodity.eq(to.as_ref()))
-            .filter(|price| price.datetime.le(&date))
-            .sorted_by(|a, b| a.datetime.cmp(&b.datetime))
-            .next()
-            .cloned();
-        Ok(x)
+            .tuple_windows()
+            .filter_map(|(a, b)| {
+                latest_prices
+                    .get(&(a.as_str(), b.as_str()))
+                    .or_else(|| latest_prices.get(&(b.as_str(), a.as_str())))
+            })
+            .map(|price| price.datetime)
+            .min()
+            .unwrap_or(date);
+        Ok(Some(ConvertedPriceDomain {
+            datetime,
+            commodity: from.as_ref().to_owned(),
+            amount,
+            target_commodity: to.as_ref().to_owned(),
+            path,
+        }))
     }
 
     pub fn metas(&self, type_: MetaType, type_identifier: impl AsRef<str>) -> ZhangResult<Vec<MetaDomain>> {
@@ -525,6 +824,19 @@ impl Operations {
         Ok(store.commodities.get(name).cloned())
     }
 
+    /// the formatter applying the precision, rounding, prefix and suffix of all commodities,
+    /// and the default precision and rounding of ledger to undefined commodities
+    pub fn amount_formatter(&self) -> AmountFormatter {
+        let default_precision = self
+            .option::<i32>(KEY_DEFAULT_COMMODITY_PRECISION)
+            .ok()
+            .flatten()
+            .unwrap_or(DEFAULT_COMMODITY_PRECISION);
+        let default_rounding = self.option::<Rounding>(KEY_DEFAULT_ROUNDING).ok().flatten().unwrap_or(DEFAULT_ROUNDING);
+        let store = self.read();
+        AmountFormatter::new(store.commodities.values().cloned(), default_precision, default_rounding)
+    }
+
     pub fn exist_commodity(&mut self, name: &str) -> ZhangResult<bool> {
         Ok(self.commodity(name)?.is_some())
     }
@@ -560,33 +872,16 @@ impl Operations {
 
         let account = Account::from_str(account_name).map_err(|_| ZhangError::InvalidAccount)?;
 
-        let mut ret: IndexMap<Currency, BTreeMap<NaiveDate, Amount>> = IndexMap::new();
-
-        for posting in store
-            .postings
-            .iter()
-            .filter(|posting| posting.account.eq(&account))
-            .cloned()
-            .sorted_by_key(|posting| posting.trx_datetime)
-        {
-            let posting: PostingDomain = posting;
-            let date = posting.trx_datetime.naive_local().date();
-
-            let dated_amount = ret.entry(posting.after_amount.currency.clone()).or_default();
-            dated_amount.insert(date, posting.after_amount);
-        }
-
-        Ok(ret
+        Ok(store
+            .account_currencies(account.name())
             .into_iter()
-            .map(|(_, mut balance)| {
-                let (date, amount) = balance.pop_last().expect("");
-                AccountBalanceDomain {
-                    datetime: date.and_time(NaiveTime::default()),
-                    account: account.name().to_owned(),
-                    account_status: AccountStatus::Open,
-                    balance_number: amount.number,
-                    balance_commodity: amount.currency,
-                }
+            .filter_map(|currency| store.account_commodity_postings(account.name(), currency).next_back())
+            .map(|posting| AccountBalanceDomain {
+                datetime: posting.trx_datetime.naive_local().date().and_time(NaiveTime::default()),
+                account: account.name().to_owned(),
+                account_status: AccountStatus::Open,
+                balance_number: posting.after_amount.number.clone(),
+                balance_commodity: posting.after_amount.currency.clone(),
             })
             .collect_vec())
     }
@@ -600,50 +895,50 @@ impl Operations {
 
         let mut ret: HashMap<Currency, HashMap<NaiveDate, Amount>> = HashMap::new();
 
-        for posting in store
-            .postings
-            .iter()
-            .filter(|posting| posting.account.eq(&account))
-            .cloned()
-            .sorted_by_key(|posting| posting.trx_datetime)
-        {
-            let posting: PostingDomain = posting;
-            let date = posting.trx_datetime.naive_local().date();
-
-            let dated_amount = ret.entry(posting.after_amount.currency.clone()).or_default();
-            dated_amount.insert(date, posting.after_amount);
+        for currency in store.account_currencies(account.name()) {
+            let dated_amount = ret.entry(currency.clone()).or_default();
+            for posting in store.account_commodity_postings(account.name(), currency) {
+                dated_amount.insert(posting.trx_datetime.naive_local().date(), posting.after_amount.clone());
+            }
         }
 
         Ok(ret)
     }
 
-    pub fn account_journals(&mut self, account: &str) -> ZhangResult<Vec<AccountJournalDomain>> {
+    /// get target account's journals, including postings and notes, ordered by datetime descending.
+    /// notes are numbered apart from transactions, so they go after the transactions of the same datetime
+    pub fn account_journals(&mut self, account: &str) -> ZhangResult<Vec<AccountJournalItemDomain>> {
         let store = self.read();
         let account = Account::from_str(account).map_err(|_| ZhangError::InvalidAccount)?;
 
         let mut ret = vec![];
-        for posting in store.postings.iter().filter(|posting| posting.account.eq(&account)).cloned().sorted_by(|a, b| {
-            a.trx_datetime
-                .cmp(&b.trx_datetime)
-                .reverse()
-                .then(a.trx_sequence.cmp(&b.trx_sequence).reverse())
-        }) {
+        for posting in store.postings.iter().filter(|posting| posting.account.eq(&account)).cloned() {
             let posting: PostingDomain = posting;
             let trx_header = store.transactions.get(&posting.trx_id);
-            ret.push(AccountJournalDomain {
-                datetime: posting.trx_datetime.naive_local(),
-                timestamp: posting.trx_datetime.timestamp(),
-                account: posting.account.name().to_owned(),
-                trx_id: posting.id.to_string(),
-                payee: trx_header.and_then(|it| it.payee.clone()),
-                narration: trx_header.and_then(|it| it.narration.clone()),
-                inferred_unit_number: posting.inferred_amount.number,
-                inferred_unit_commodity: posting.inferred_amount.currency,
-                account_after_number: posting.after_amount.number,
-                account_after_commodity: posting.after_amount.currency,
-            })
+            ret.push((
+                (posting.trx_datetime, false, posting.trx_sequence),
+                AccountJournalItemDomain::Posting(AccountJournalDomain {
+                    datetime: posting.trx_datetime.naive_local(),
+                    timestamp: posting.trx_datetime.timestamp(),
+                    account: posting.account.name().to_owned(),
+                    trx_id: posting.id.to_string(),
+                    payee: trx_header.and_then(|it| it.payee.clone()),
+                    narration: trx_header.and_then(|it| it.narration.clone()),
+                    inferred_unit_number: posting.inferred_amount.number,
+                    inferred_unit_commodity: posting.inferred_amount.currency,
+                    account_after_number: posting.after_amount.number,
+                    account_after_commodity: posting.after_amount.currency,
+                }),
+            ))
         }
-        Ok(ret)
+        for note in store.notes.iter().filter(|note| note.account.eq(&account)).cloned() {
+            ret.push(((note.datetime, true, note.sequence), AccountJournalItemDomain::Note(note)));
+        }
+        Ok(ret
+            .into_iter()
+            .sorted_by(|(a_key, _), (b_key, _)| a_key.cmp(b_key).reverse())
+            .map(|(_, item)| item)
+            .collect_vec())
     }
 
     pub fn dated_journals(&mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> ZhangResult<Vec<PostingDomain>> {
@@ -715,6 +1010,12 @@ impl Operations {
         Ok(ret)
     }
 
+    /// execute the query written in the SQL-like query language, see [crate::query]
+    pub fn query(&self, query: &str) -> ZhangResult<QueryResult> {
+        let query = crate::query::parse(query)?;
+        crate::query::execute(self, &query)
+    }
+
     pub fn errors(&mut self) -> ZhangResult<Vec<ErrorDomain>> {
         let store = self.read();
         Ok(store.errors.iter().cloned().collect_vec())
@@ -795,39 +1096,80 @@ impl Operations {
         Ok(ret)
     }
 
-    pub fn account_target_date_balance(&self, account_name: impl AsRef<str>, date: DateTime<Utc>) -> ZhangResult<Vec<AccountBalanceDomain>> {
-        let store = self.read();
-
-        let account = Account::from_str(account_name.as_ref()).map_err(|_| ZhangError::InvalidAccount)?;
+    /// the tree of all accounts with balances at the given datetime, converted into operating currency
+    pub fn account_tree(&mut self, datetime: DateTime<Tz>) -> ZhangResult<Vec<AccountTreeDomain>> {
+        let (names, statuses): (BTreeSet<String>, HashMap<String, AccountStatus>) = {
+            let store = self.read();
+            (
+                store.accounts.keys().chain(store.posting_index.keys()).cloned().collect(),
+                store.accounts.iter().map(|(name, account)| (name.clone(), account.status)).collect(),
+            )
+        };
 
-        let mut ret: IndexMap<Currency, BTreeMap<NaiveDate, Amount>> = IndexMap::new();
+        let mut balances: BTreeMap<String, Vec<Amount>> = BTreeMap::new();
+        for name in names {
+            let account = Account::from_str(&name).map_err(|_| ZhangError::InvalidAccount)?;
+            let mut ancestor = account.account_type.to_string();
+            for component in account.components() {
+                balances.entry(ancestor.clone()).or_default();
+                ancestor = format!("{}:{}", ancestor, component);
+            }
+            let amounts = self
+                .account_target_date_balance(&name, datetime.with_timezone(&Utc))?
+                .into_iter()
+                .map(|balance| Amount::new(balance.balance_number, balance.balance_commodity))
+                .collect_vec();
+            balances.insert(name, amounts);
+        }
+        self.account_tree_nodes(None, &balances, &statuses, datetime)
+    }
 
-        for posting in store
-            .postings
-            .iter()
-            .filter(|posting| posting.account.eq(&account))
-            .filter(|positing| positing.trx_datetime.le(&date))
-            .cloned()
-            .sorted_by_key(|posting| posting.trx_datetime)
-        {
-            let posting: PostingDomain = posting;
-            let date = posting.trx_datetime.naive_local().date();
+    fn account_tree_nodes(
+        &mut self, parent: Option<&str>, balances: &BTreeMap<String, Vec<Amount>>, statuses: &HashMap<String, AccountStatus>, datetime: DateTime<Tz>,
+    ) -> ZhangResult<Vec<AccountTreeDomain>> {
+        let is_child = |name: &str| match parent {
+            None => !name.contains(':'),
+            Some(parent) => name
+                .strip_prefix(parent)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(|leaf| !leaf.contains(':'))
+                .unwrap_or(false),
+        };
 
-            let dated_amount = ret.entry(posting.after_amount.currency.clone()).or_default();
-            dated_amount.insert(date, posting.after_amount);
+        let mut nodes = vec![];
+        for (name, amounts) in balances.iter().filter(|(name, _)| is_child(name)) {
+            let sub_account_prefix = format!("{}:", name);
+            let total_amounts = balances
+                .iter()
+                .filter(|(it, _)| *it == name || it.starts_with(&sub_account_prefix))
+                .flat_map(|(_, amounts)| amounts.iter().cloned())
+                .collect_vec();
+            nodes.push(AccountTreeDomain {
+                name: name.clone(),
+                status: statuses.get(name).copied(),
+                balance: amounts.calculate(datetime, self)?,
+                total: total_amounts.calculate(datetime, self)?,
+                children: self.account_tree_nodes(Some(name), balances, statuses, datetime)?,
+            });
         }
+        Ok(nodes)
+    }
 
-        Ok(ret
+    pub fn account_target_date_balance(&self, account_name: impl AsRef<str>, date: DateTime<Utc>) -> ZhangResult<Vec<AccountBalanceDomain>> {
+        let store = self.read();
+
+        let account = Account::from_str(account_name.as_ref()).map_err(|_| ZhangError::InvalidAccount)?;
+
+        Ok(store
+            .account_currencies(account.name())
             .into_iter()
-            .map(|(_, mut balance)| {
-                let (date, amount) = balance.pop_last().expect("");
-                AccountBalanceDomain {
-                    datetime: date.and_time(NaiveTime::default()),
-                    account: account.name().to_owned(),
-                    account_status: AccountStatus::Open,
-                    balance_number: amount.number,
-                    balance_commodity: amount.currency,
-                }
+            .filter_map(|currency| store.latest_account_commodity_posting(account.name(), currency, &date))
+            .map(|posting| AccountBalanceDomain {
+                datetime: posting.trx_datetime.naive_local().date().and_time(NaiveTime::default()),
+                account: account.name().to_owned(),
+                account_status: AccountStatus::Open,
+                balance_number: posting.after_amount.number.clone(),
+                balance_commodity: posting.after_amount.currency.clone(),
             })
             .collect_vec())
     }
@@ -914,70 +1256,119 @@ impl Operations {
         Ok(store.budgets.values().cloned().collect_vec())
     }
 
+    pub fn budget(&self, name: impl AsRef<str>) -> ZhangResult<Option<BudgetDomain>> {
+        let store = self.read();
+        Ok(store.budgets.get(name.as_ref()).cloned())
+    }
+
     /// check if budget exists
     pub fn contains_budget(&self, name: impl AsRef<str>) -> bool {
         let store = self.read();
         store.budgets.contains_key(name.as_ref())
     }
 
-    /// init or create a new budget
-    pub fn init_budget(
-        &mut self, name: impl Into<String>, commodity: impl Into<String>, date: DateTime<Tz>, alias: Option<String>, category: Option<String>,
-    ) -> ZhangResult<()> {
+    /// init or create a new budget, with the interval of date as its first interval
+    pub fn init_budget(&mut self, budget: BudgetDomain, date: DateTime<Tz>) -> ZhangResult<()> {
         let mut store = self.write();
-        let name = name.into();
-        let commodity = commodity.into();
-        let interval = (date.year() as u32) * 100 + date.month();
-
-        let budget_domain = store.budgets.entry(name.clone()).or_insert(BudgetDomain {
-            name,
-            commodity: commodity.clone(),
-            alias,
-            category,
-            closed: false,
-            detail: Default::default(),
-        });
+        let commodity = budget.commodity.clone();
+        let interval = budget.period.interval(date.date_naive());
+
+        let budget_domain = store.budgets.entry(budget.name.clone()).or_insert(budget);
         budget_domain.detail.entry(interval).or_insert(BudgetIntervalDetail {
             date: interval,
             events: vec![],
             assigned_amount: Amount::zero(&commodity),
             activity_amount: Amount::zero(&commodity),
+            available_amount: Amount::zero(&commodity),
         });
         Ok(())
     }
 
-    /// get target month's detail
-    pub fn budget_month_detail(&self, name: impl Into<String>, interval: u32) -> ZhangResult<Option<BudgetIntervalDetail>> {
+    /// the interval of budget which the datetime falls in
+    pub fn budget_interval(&self, name: impl AsRef<str>, datetime: DateTime<Tz>) -> u32 {
+        let store = self.read();
+        let target_budget = store.budgets.get(name.as_ref()).expect("budget does not exist");
+        target_budget.period.interval(datetime.date_naive())
+    }
+
+    /// get target interval's detail. the interval without any event takes the leftover or overspend of the latest interval
+    /// as its assigned amount, or carries it over as a rollover event if rollover is enabled
+    pub fn budget_interval_detail(&self, name: impl Into<String>, interval: u32) -> ZhangResult<Option<BudgetIntervalDetail>> {
         let store = self.read();
         let name = name.into();
         let target_budget = store.budgets.get(&name).expect("budget does not exist");
 
-        Ok(target_budget
-            .detail
+        let Some((_, fetched_detail)) = target_budget.detail.range(..=interval).next_back() else {
+            return Ok(None);
+        };
+        if fetched_detail.date == interval {
+            return Ok(Some(fetched_detail.clone()));
+        }
+
+        let carried_amount = fetched_detail.available_amount.clone();
+        if !target_budget.rollover {
+            return Ok(Some(BudgetIntervalDetail {
+                date: interval,
+                events: vec![],
+                assigned_amount: carried_amount.clone(),
+                activity_amount: Amount::zero(&target_budget.commodity),
+                available_amount: carried_amount,
+            }));
+        }
+        let mut events = vec![];
+        if let Some(datetime) = interval_beginning(target_budget.period, interval, &self.timezone).filter(|_| !carried_amount.is_zero()) {
+            events.push(BudgetEvent {
+                datetime,
+                timestamp: datetime.timestamp(),
+                amount: carried_amount.clone(),
+                event_type: BudgetEventType::Rollover,
+            });
+        }
+        Ok(Some(BudgetIntervalDetail {
+            date: interval,
+            events,
+            assigned_amount: Amount::zero(&target_budget.commodity),
+            activity_amount: Amount::zero(&target_budget.commodity),
+            available_amount: carried_amount,
+        }))
+    }
+
+    /// progress of the targets of budget in the interval
+    pub fn budget_target_progress(&self, name: impl AsRef<str>, interval: u32) -> ZhangResult<Vec<BudgetTargetProgressDomain>> {
+        let Some(budget) = self.budget(name)? else {
+            return Ok(vec![]);
+        };
+        let zero = Amount::zero(&budget.commodity);
+        let detail = self.budget_interval_detail(&budget.name, interval)?;
+        let (assigned, activity, available) = detail
+            .map(|detail| (detail.assigned_amount, detail.activity_amount, detail.available_amount))
+            .unwrap_or((zero.clone(), zero.clone(), zero));
+
+        Ok(budget
+            .targets
             .iter()
-            .filter(|item| item.0 <= &interval)
-            .max_by_key(|item| item.0)
-            .map(|item| item.1.clone())
-            .map(|fetched_detail| {
-                if fetched_detail.date == interval {
-                    fetched_detail
-                } else {
-                    BudgetIntervalDetail {
-                        date: interval,
-                        events: vec![],
-                        assigned_amount: fetched_detail.assigned_amount.sub(fetched_detail.activity_amount.number),
-                        activity_amount: Amount::zero(&target_budget.commodity),
-                    }
+            .map(|target| {
+                let (current, remaining, achieved) = match target {
+                    BudgetTarget::Interval { amount } => (&assigned, &amount.number - &assigned.number, assigned.number >= amount.number),
+                    BudgetTarget::SaveBy { amount, .. } => (&available, &amount.number - &available.number, available.number >= amount.number),
+                    BudgetTarget::SpendingCap { amount } => (&activity, &amount.number - &activity.number, activity.number <= amount.number),
+                };
+                BudgetTargetProgressDomain {
+                    target: target.clone(),
+                    current: current.clone(),
+                    remaining: Amount::new(remaining.max(BigDecimal::zero()), &budget.commodity),
+                    achieved,
                 }
-            }))
+            })
+            .collect_vec())
     }
 
     /// add amount to target month's budget
     pub fn budget_add_assigned_amount(&mut self, name: impl Into<String>, date: DateTime<Tz>, event_type: BudgetEventType, amount: Amount) -> ZhangResult<()> {
         let name = name.into();
-        let interval = (date.year() as u32) * 100 + date.month();
+        let interval = self.budget_interval(&name, date);
 
-        let previous_budget_detail = self.budget_month_detail(&name, interval)?;
+        let previous_budget_detail = self.budget_interval_detail(&name, interval)?;
 
         let mut store = self.write();
         let target_budget = store.budgets.get_mut(&name).expect("budget does not exist");
@@ -990,9 +1381,11 @@ impl Operations {
                 events: vec![],
                 assigned_amount: Amount::zero(&target_budget.commodity),
                 activity_amount: Amount::zero(&target_budget.commodity),
+                available_amount: Amount::zero(&target_budget.commodity),
             }));
 
         detail.assigned_amount = detail.assigned_amount.add(amount.number.clone());
+        detail.available_amount = detail.available_amount.add(amount.number.clone());
         detail.events.push(BudgetEvent {
             datetime: date,
             timestamp: date.timestamp(),
@@ -1019,12 +1412,21 @@ impl Operations {
         Ok(())
     }
 
-    /// close budget
-    pub fn budget_add_activity(&mut self, name: impl Into<String>, date: DateTime<Tz>, amount: Amount) -> ZhangResult<()> {
+    /// add activity into the interval of budget, the amount in other commodities is converted into the commodity of budget
+    /// with the price at the date. returns the converted amount, or `None` if no price is found,
+    /// in which case the original amount is kept as an event of the interval
+    pub fn budget_add_activity(&mut self, name: impl Into<String>, date: DateTime<Tz>, amount: Amount) -> ZhangResult<Option<Amount>> {
         let name = name.into();
-        let interval = (date.year() as u32) * 100 + date.month();
+        let interval = self.budget_interval(&name, date);
+        let commodity = self.budget(&name)?.expect("budget does not exist").commodity;
+        let converted = if amount.currency == commodity {
+            Some(amount.clone())
+        } else {
+            self.get_price(date.naive_local(), &amount.currency, &commodity)?
+                .map(|price| Amount::new(&amount.number * price.amount, &commodity))
+        };
 
-        let previous_budget_detail = self.budget_month_detail(&name, interval)?;
+        let previous_budget_detail = self.budget_interval_detail(&name, interval)?;
 
         let mut store = self.write();
         let target_budget = store.budgets.get_mut(&name).expect("budget does not exist");
@@ -1037,10 +1439,22 @@ impl Operations {
                 events: vec![],
                 assigned_amount: Amount::zero(&target_budget.commodity),
                 activity_amount: Amount::zero(&target_budget.commodity),
+                available_amount: Amount::zero(&target_budget.commodity),
             }));
 
-        detail.activity_amount = detail.activity_amount.add(amount.number);
-        Ok(())
+        match &converted {
+            Some(converted) => {
+                detail.available_amount = detail.available_amount.sub(converted.number.clone());
+                detail.activity_amount = detail.activity_amount.add(converted.number.clone());
+            }
+            None => detail.events.push(BudgetEvent {
+                datetime: date,
+                timestamp: date.timestamp(),
+                amount,
+                event_type: BudgetEventType::UnconvertedActivity,
+            }),
+        }
+        Ok(converted)
     }
 
     pub fn get_account_budget(&self, account_name: impl AsRef<str>) -> ZhangResult<Vec<String>> {
@@ -1048,3 +1462,44 @@ impl Operations {
         Ok(metas.into_iter().filter(|meta| meta.key.eq("budget")).map(|meta| meta.value).collect_vec())
     }
 }
+
+/// the beginning of the budget interval
+fn interval_beginning(period: BudgetPeriod, interval: u32, timezone: &Tz) -> Option<DateTime<Tz>> {
+    let (start, _) = period.interval_range(interval)?;
+    beginning_of_date(timezone, start)
+}
+
+/// the average cost rounded to [AVERAGE_COST_SCALE] decimal places without trailing zeros,
+/// but keeping the decimal places of the merged costs, e.g. `110` for costs `100` and `120`
+fn average_cost(lots: &[CommodityLotRecord], average: BigDecimal) -> BigDecimal {
+    let lots_scale = lots
+        .iter()
+        .filter_map(|lot| lot.cost.as_ref())
+        .map(|cost| cost.number.as_bigint_and_exponent().1)
+        .max()
+        .unwrap_or_default();
+    let average = average.with_scale_round(AVERAGE_COST_SCALE, RoundingMode::HalfEven).normalized();
+    let (_, average_scale) = average.as_bigint_and_exponent();
+    average.with_scale(average_scale.max(lots_scale).max(0))
+}
+
+/// find the latest event at or before the datetime in a timeline ordered by datetime
+fn latest_event(timeline: &[EventDomain], datetime: DateTime<Tz>) -> Option<EventDomain> {
+    let idx = timeline.partition_point(|event| event.datetime <= datetime);
+    idx.checked_sub(1).and_then(|idx| timeline.get(idx)).cloned()
+}
+
+/// check if the lot matches given commodity and lot meta
+fn lot_match_meta(lot: &CommodityLotRecord, currency: &str, lot_meta: &PostingCost, txn_date: NaiveDate) -> bool {
+    // match commodity
+    if lot.commodity.ne(currency) {
+        return false;
+    }
+    if lot_meta.base.is_some() {
+        // match cost, and if cost date in lot meta is not defined, use txn date
+        lot.cost.eq(&lot_meta.base) && lot.acquisition_date.eq(&lot_meta.date.as_ref().map(|it| it.naive_date()).or(Some(txn_date)))
+    } else {
+        // if cost in meta is null, return all lots with cost
+        lot.cost.is_some()
+    }
+}
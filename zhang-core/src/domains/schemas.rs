@@ -3,11 +3,13 @@ use std::path::PathBuf;
 
 use bigdecimal::BigDecimal;
 use chrono::{NaiveDate, NaiveDateTime};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, EnumString};
 use zhang_ast::error::ErrorKind;
 use zhang_ast::{Currency, Rounding, SpanInfo};
 
+use crate::store::NoteDomain;
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, AsRefStr, EnumString)]
 pub enum MetaType {
     AccountMeta,
@@ -21,16 +23,18 @@ pub struct OptionDomain {
     pub value: String,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct AccountDomain {
     pub date: NaiveDateTime,
     pub r#type: String,
     pub name: String,
     pub status: AccountStatus,
     pub alias: Option<String>,
+    /// commodities allowed to be used in the account, empty means any commodity is allowed
+    pub commodities: Vec<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy, Serialize, AsRefStr, EnumString)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, Serialize, Deserialize, AsRefStr, EnumString)]
 pub enum AccountStatus {
     Open,
     Close,
@@ -54,15 +58,38 @@ pub struct AccountDailyBalanceDomain {
     pub balance_commodity: String,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PriceDomain {
     pub datetime: NaiveDateTime,
     pub commodity: Currency,
     pub amount: BigDecimal,
     pub target_commodity: Currency,
+    pub source: PriceSource,
+}
+
+/// where the price comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    /// declared by `price` directive
+    Explicit,
+    /// inferred from the cost or price of posting, enabled by option `implicit_prices`
+    Implicit,
 }
 
+/// price resolved from one commodity to another, which may use inverse prices and intermediate commodities
 #[derive(Debug, Clone, serde::Serialize)]
+pub struct ConvertedPriceDomain {
+    /// datetime of the oldest price used in the conversion
+    pub datetime: NaiveDateTime,
+    pub commodity: Currency,
+    pub amount: BigDecimal,
+    pub target_commodity: Currency,
+    /// commodities the conversion goes through, starting with `commodity` and ending with `target_commodity`
+    pub path: Vec<Currency>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct MetaDomain {
     pub meta_type: String,
     pub type_identifier: String,
@@ -70,7 +97,7 @@ pub struct MetaDomain {
     pub value: String,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct CommodityDomain {
     pub name: String,
     pub precision: i32,
@@ -101,7 +128,14 @@ pub struct AccountJournalDomain {
     pub account_after_commodity: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// item of account journals, which can be a posting or a note of the account
+#[derive(Debug, Clone)]
+pub enum AccountJournalItemDomain {
+    Posting(AccountJournalDomain),
+    Note(NoteDomain),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorDomain {
     pub id: String,
     pub span: Option<SpanInfo>,
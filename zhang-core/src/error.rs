@@ -14,6 +14,9 @@ pub enum ZhangError {
     #[error("option value is invalid")]
     InvalidOptionValue,
 
+    #[error("query is invalid: {0}")]
+    InvalidQuery(String),
+
     #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
     #[error("fetch error")]
@@ -5,7 +5,7 @@ use crate::constants::{KEY_FEATURES_PLUGIN, TRUE};
 /// ```zhang
 /// option "features.{FEATURE_NAME}" "true"
 /// ```
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Features {
     pub plugins: bool,
 }
@@ -0,0 +1,249 @@
+//! forecast projects the future balances of accounts from the scheduled transactions, which are
+//! - the occurrences of recurring directives after the recorded date, see [recorded_until]
+//! - the transactions flagged as forecast (`#`) and dated after the recorded date
+//!
+//! projected transactions never go into the store, so the actual balances are not affected.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use itertools::Itertools;
+use zhang_ast::amount::Amount;
+use zhang_ast::{Account, Directive, Flag, SpanInfo, Spanned, Transaction};
+
+use crate::constants::FORECAST_FLAG;
+use crate::ledger::Ledger;
+use crate::process::recurring::{linked_dates, recurring_transactions};
+use crate::utils::date_range::end_of_date;
+use crate::{ZhangError, ZhangResult};
+
+pub struct ForecastDomain {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// projected balances of accounts at the end of each month
+    pub balances: BTreeMap<NaiveDate, Vec<AccountForecastDomain>>,
+    /// postings of projected transactions ordered by date
+    pub postings: Vec<ProjectedPostingDomain>,
+}
+
+pub struct AccountForecastDomain {
+    pub account: Account,
+    /// one amount per commodity ordered by commodity
+    pub balances: Vec<Amount>,
+}
+
+pub struct ProjectedPostingDomain {
+    pub date: NaiveDate,
+    pub account: Account,
+    pub amount: Amount,
+}
+
+fn is_forecast(trx: &Transaction) -> bool {
+    matches!(&trx.flag, Some(Flag::Custom(flag)) if flag == FORECAST_FLAG)
+}
+
+/// whether the transaction is a forecast which has not happened by the recorded date
+pub(crate) fn is_projected(trx: &Transaction, recorded_until: NaiveDate) -> bool {
+    is_forecast(trx) && trx.date.naive_date() > recorded_until
+}
+
+/// the date of the latest directive other than recurring directives and forecast transactions.
+/// the ledger is recorded until this date, so recurring directives are expanded up to it and the later occurrences are projected.
+/// it only depends on the ledger files, so the store stays the same no matter when the ledger is loaded.
+pub(crate) fn recorded_until<'a>(directives: impl IntoIterator<Item = &'a Spanned<Directive>>) -> NaiveDate {
+    directives
+        .into_iter()
+        .filter(|directive| match &directive.data {
+            Directive::Recurring(_) => false,
+            Directive::Transaction(trx) => !is_forecast(trx),
+            _ => true,
+        })
+        .filter_map(|directive| directive.datetime())
+        .map(|datetime| datetime.date())
+        .max()
+        .unwrap_or(NaiveDate::MIN)
+}
+
+/// the last day of the month which is `months` months after the month of `date`
+fn month_end(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    date.with_day0(0)?
+        .checked_add_months(Months::new(months.checked_add(1)?))?
+        .checked_sub_days(Days::new(1))
+}
+
+/// the projected transactions after `recorded_until` and no later than `until`
+fn projected_transactions(directives: &[Spanned<Directive>], recorded_until: NaiveDate, until: NaiveDate) -> Vec<(Transaction, SpanInfo)> {
+    let linked_dates = linked_dates(directives.iter().map(|it| &it.data));
+    let mut transactions = vec![];
+    for directive in directives {
+        match &directive.data {
+            Directive::Recurring(recurring) => transactions.extend(
+                recurring_transactions(recurring, &directive.span, &linked_dates, Some(recorded_until), until)
+                    .into_iter()
+                    .map(|trx| (trx, directive.span.clone())),
+            ),
+            Directive::Transaction(trx) if is_projected(trx, recorded_until) && trx.date.naive_date() <= until => {
+                transactions.push((trx.clone(), directive.span.clone()));
+            }
+            _ => {}
+        }
+    }
+    transactions
+}
+
+/// project the balances of accounts at the end of this month and the following `months` months
+pub fn forecast(ledger: &Ledger, today: NaiveDate, months: u32) -> ZhangResult<ForecastDomain> {
+    let invalid_date = || ZhangError::CustomError(format!("cannot forecast {} months after {}", months, today));
+    let to = month_end(today, months).ok_or_else(invalid_date)?;
+
+    let mut postings = vec![];
+    for (trx, span) in projected_transactions(&ledger.directives, recorded_until(&ledger.directives), to) {
+        for txn_posting in trx.txn_postings() {
+            let amount = match txn_posting.units() {
+                Some(amount) => amount,
+                None => txn_posting
+                    .infer_trade_amount()
+                    .map_err(|kind| ZhangError::ProcessError { span: span.clone(), kind })?,
+            };
+            postings.push(ProjectedPostingDomain {
+                date: trx.date.naive_date(),
+                account: txn_posting.posting.account.clone(),
+                amount,
+            });
+        }
+    }
+    postings.sort_by_key(|posting| posting.date);
+
+    let operations = ledger.operations();
+    let accounts: BTreeSet<String> = operations
+        .read()
+        .accounts
+        .keys()
+        .cloned()
+        .chain(postings.iter().map(|posting| posting.account.name().to_owned()))
+        .collect();
+
+    let mut balances = BTreeMap::new();
+    for nth in 0..=months {
+        let date = month_end(today, nth).ok_or_else(invalid_date)?;
+        let datetime = end_of_date(&ledger.options.timezone, date).ok_or_else(invalid_date)?.with_timezone(&Utc);
+
+        let mut account_balances = vec![];
+        for account in accounts.iter() {
+            let mut inventory: BTreeMap<String, BigDecimal> = operations
+                .account_target_date_balance(account, datetime)?
+                .into_iter()
+                .map(|balance| (balance.balance_commodity, balance.balance_number))
+                .collect();
+            for posting in postings.iter().filter(|posting| posting.date <= date && posting.account.name().eq(account)) {
+                *inventory.entry(posting.amount.currency.clone()).or_default() += &posting.amount.number;
+            }
+            if !inventory.is_empty() {
+                account_balances.push(AccountForecastDomain {
+                    account: Account::from_str(account).map_err(|_| ZhangError::InvalidAccount)?,
+                    balances: inventory.into_iter().map(|(currency, number)| Amount::new(number, currency)).collect_vec(),
+                });
+            }
+        }
+        balances.insert(date, account_balances);
+    }
+
+    Ok(ForecastDomain {
+        from: today,
+        to,
+        balances,
+        postings,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+    use indoc::indoc;
+    use zhang_ast::amount::Amount;
+
+    use crate::forecast::{forecast, ForecastDomain};
+    use crate::test::load_from_text;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn balance(forecast: &ForecastDomain, date: NaiveDate, account: &str) -> Option<Vec<Amount>> {
+        forecast.balances[&date]
+            .iter()
+            .find(|it| it.account.name() == account)
+            .map(|it| it.balances.clone())
+    }
+
+    const LEDGER: &str = indoc! {r#"
+        1970-01-01 open Assets:Bank
+        1970-01-01 open Expenses:Rent
+        1970-01-01 open Expenses:Insurance
+        1970-01-01 open Equity:Opening
+
+        1970-01-02 "Opening"
+          Assets:Bank 10000 CNY
+          Equity:Opening
+
+        2099-01-01 recurring "monthly" "Landlord" "Rent"
+          Expenses:Rent 1000 CNY
+          Assets:Bank
+          until: 2099-03-31
+
+        2099-02-10 # "Insurance"
+          Expenses:Insurance 500 CNY
+          Assets:Bank
+    "#};
+
+    #[test]
+    fn should_keep_projected_transactions_out_of_actual_balances() {
+        let ledger = load_from_text(LEDGER);
+        let operations = ledger.operations();
+        assert!(operations.single_account_latest_balances("Expenses:Rent").unwrap().is_empty());
+        assert!(operations.single_account_latest_balances("Expenses:Insurance").unwrap().is_empty());
+        assert_eq!(ledger.store.read().unwrap().transactions.len(), 1);
+    }
+
+    #[test]
+    fn should_project_balances_at_end_of_each_month() {
+        let ledger = load_from_text(LEDGER);
+        let forecast = forecast(&ledger, date(2098, 12, 15), 3).unwrap();
+
+        assert_eq!(forecast.to, date(2099, 3, 31));
+        assert_eq!(
+            forecast.balances.keys().cloned().collect::<Vec<_>>(),
+            vec![date(2098, 12, 31), date(2099, 1, 31), date(2099, 2, 28), date(2099, 3, 31)]
+        );
+        assert_eq!(forecast.postings.len(), 8);
+
+        let cny = |number: i32| vec![Amount::new(BigDecimal::from(number), "CNY")];
+        assert_eq!(balance(&forecast, date(2098, 12, 31), "Assets:Bank"), Some(cny(10000)));
+        assert_eq!(balance(&forecast, date(2098, 12, 31), "Expenses:Rent"), None);
+        assert_eq!(balance(&forecast, date(2099, 1, 31), "Expenses:Rent"), Some(cny(1000)));
+        assert_eq!(balance(&forecast, date(2099, 2, 28), "Expenses:Insurance"), Some(cny(500)));
+        assert_eq!(balance(&forecast, date(2099, 3, 31), "Assets:Bank"), Some(cny(6500)));
+    }
+
+    #[test]
+    fn should_reject_months_out_of_range() {
+        let ledger = load_from_text(LEDGER);
+        assert!(forecast(&ledger, date(2098, 12, 15), u32::MAX).is_err());
+    }
+
+    #[test]
+    fn should_only_project_transactions_after_recorded_date() {
+        let ledger = load_from_text(&format!("{}\n2099-02-15 balance Assets:Bank 7500 CNY\n", LEDGER));
+        let rent = ledger.operations().single_account_latest_balances("Expenses:Rent").unwrap().pop().unwrap();
+        assert_eq!(rent.balance_number, BigDecimal::from(2000));
+        assert!(ledger.operations().errors().unwrap().is_empty());
+
+        let forecast = forecast(&ledger, date(2099, 2, 15), 1).unwrap();
+
+        assert_eq!(forecast.postings.first().map(|it| it.date), Some(date(2099, 3, 1)));
+        assert_eq!(forecast.postings.len(), 2);
+    }
+}
@@ -1,21 +1,30 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::atomic::AtomicI32;
+use std::sync::atomic::{AtomicI32, Ordering as AtomicOrdering};
 use std::sync::{Arc, RwLock};
 
 use cfg_if::cfg_if;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
 use itertools::Itertools;
-use log::{error, info};
+use log::{error, info, warn};
 use zhang_ast::{Directive, DirectiveType, Options, Plugin, SpanInfo, Spanned};
 
+use crate::cache::LedgerCache;
+use crate::closing::{close_period, PeriodClosingDomain};
+use crate::custom::{CustomRegistry, CUSTOM_SCHEMA};
 use crate::data_source::DataSource;
-use crate::domains::Operations;
+use crate::domains::{HoldingDomain, Operations};
 use crate::error::IoErrorIntoZhangError;
+use crate::forecast::{forecast, is_projected, recorded_until, ForecastDomain};
 use crate::options::{BuiltinOption, InMemoryOptions};
+use crate::process::recurring::{expand_recurring_directives, mark_occurrence_errors};
+use crate::process::{account_lifetimes, AccountLifetime};
 use crate::process::{DirectivePreProcess, DirectiveProcess};
-use crate::store::Store;
+use crate::store::checkpoint::Checkpoints;
+use crate::store::{CommodityLotRecord, Store};
 use crate::{ZhangError, ZhangResult};
 
 pub struct Ledger {
@@ -34,6 +43,16 @@ pub struct Ledger {
 
     pub(crate) trx_counter: AtomicI32,
 
+    pub custom_registry: CustomRegistry,
+
+    /// directives passed into processors, in processing order
+    processed_directives: Vec<Spanned<Directive>>,
+    /// whether checkpoints are kept while processing, which is disabled for ledgers processed only for a moment
+    keep_checkpoints: bool,
+    pub(crate) checkpoints: Checkpoints,
+    /// the open and close dates of accounts in processed directives
+    pub(crate) account_lifetimes: HashMap<String, AccountLifetime>,
+
     #[cfg(feature = "plugin_runtime")]
     pub plugins: crate::plugin::store::PluginStore,
 }
@@ -43,6 +62,9 @@ pub struct LedgerProcessContext {
     pub entry: (PathBuf, String),
     pub visited_files: Vec<PathBuf>,
     pub data_source: Arc<dyn DataSource>,
+    pub custom_registry: CustomRegistry,
+    /// keep checkpoints while processing for later reloads, see [Checkpoints]
+    pub keep_checkpoints: bool,
 }
 
 struct SplitDirectives {
@@ -119,6 +141,8 @@ impl Ledger {
             entry: (entry, endpoint),
             visited_files: load_result.visited_files,
             data_source,
+            custom_registry: CustomRegistry::default(),
+            keep_checkpoints: true,
         })
     }
     pub async fn async_load(entry: PathBuf, endpoint: String, data_source: Arc<dyn DataSource>) -> ZhangResult<Ledger> {
@@ -129,23 +153,62 @@ impl Ledger {
             entry: (entry, endpoint),
             visited_files: load_result.visited_files,
             data_source,
+            custom_registry: CustomRegistry::default(),
+            keep_checkpoints: true,
         })
         .await
     }
 
-    pub fn process(context: LedgerProcessContext) -> ZhangResult<Ledger> {
-        let mut ret_ledger = Self {
+    /// create a ledger with empty store, which is not processed yet
+    pub(crate) fn new(entry: (PathBuf, String), visited_files: Vec<PathBuf>, data_source: Arc<dyn DataSource>, custom_registry: CustomRegistry) -> Ledger {
+        Self {
             options: InMemoryOptions::default(),
-            entry: context.entry,
-            visited_files: context.visited_files,
+            entry,
+            visited_files,
             directives: vec![],
             metas: vec![],
-            data_source: context.data_source,
+            data_source,
             store: Default::default(),
             trx_counter: AtomicI32::new(1),
+            custom_registry,
+            processed_directives: vec![],
+            keep_checkpoints: false,
+            checkpoints: Checkpoints::default(),
+            account_lifetimes: HashMap::new(),
             #[cfg(feature = "plugin_runtime")]
             plugins: crate::plugin::store::PluginStore::default(),
-        };
+        }
+    }
+
+    /// load the ledger from cache if none of the visited files is changed, otherwise process the ledger and refresh the cache
+    pub fn load_with_cache(entry: PathBuf, endpoint: String, data_source: Arc<dyn DataSource>, cache: &LedgerCache) -> ZhangResult<Ledger> {
+        let entry = entry.canonicalize().with_path(&entry)?;
+        if let Some(ledger) = cache.load(entry.clone(), endpoint.clone(), data_source.clone()) {
+            return Ok(ledger);
+        }
+        let ledger = Ledger::load_with_data_source(entry, endpoint, data_source)?;
+        if let Err(e) = cache.save(&ledger) {
+            warn!("fail to save ledger cache: {}", e);
+        }
+        Ok(ledger)
+    }
+
+    pub async fn async_load_with_cache(entry: PathBuf, endpoint: String, data_source: Arc<dyn DataSource>, cache: &LedgerCache) -> ZhangResult<Ledger> {
+        // remote entries of data sources like opendal cannot be canonicalized, they are kept as they are
+        let entry = entry.canonicalize().unwrap_or(entry);
+        if let Some(ledger) = cache.async_load(entry.clone(), endpoint.clone(), data_source.clone()).await {
+            return Ok(ledger);
+        }
+        let ledger = Ledger::async_load(entry, endpoint, data_source).await?;
+        if let Err(e) = cache.async_save(&ledger).await {
+            warn!("fail to save ledger cache: {}", e);
+        }
+        Ok(ledger)
+    }
+
+    pub fn process(context: LedgerProcessContext) -> ZhangResult<Ledger> {
+        let mut ret_ledger = Ledger::new(context.entry, context.visited_files, context.data_source, context.custom_registry);
+        ret_ledger.keep_checkpoints = context.keep_checkpoints;
         let SplitDirectives {
             meta_directives,
             dated_directives,
@@ -159,8 +222,10 @@ impl Ledger {
         ret_ledger.handle_plugins_pre_process(&mut plugin_directives)?;
         ret_ledger.handle_plugins(&mut plugin_directives)?;
 
+        let other_directives = ret_ledger.expand_recurring_directives(other_directives);
         let other_directives = ret_ledger.handle_plugin_execution(other_directives)?;
 
+        ret_ledger.custom_registry.declare(&other_directives);
         ret_ledger.handle_other_directives(other_directives)?;
 
         ret_ledger.metas = meta_directives;
@@ -176,18 +241,8 @@ impl Ledger {
     }
 
     async fn async_process(context: LedgerProcessContext) -> ZhangResult<Ledger> {
-        let mut ret_ledger = Self {
-            options: InMemoryOptions::default(),
-            entry: context.entry,
-            visited_files: context.visited_files,
-            directives: vec![],
-            metas: vec![],
-            data_source: context.data_source,
-            store: Default::default(),
-            trx_counter: AtomicI32::new(1),
-            #[cfg(feature = "plugin_runtime")]
-            plugins: crate::plugin::store::PluginStore::default(),
-        };
+        let mut ret_ledger = Ledger::new(context.entry, context.visited_files, context.data_source, context.custom_registry);
+        ret_ledger.keep_checkpoints = context.keep_checkpoints;
         let SplitDirectives {
             meta_directives,
             dated_directives,
@@ -198,7 +253,9 @@ impl Ledger {
         ret_ledger.handle_options(&mut options_directives)?;
         ret_ledger.async_handle_plugins_pre_process(&mut plugin_directives).await?;
         ret_ledger.handle_plugins(&mut plugin_directives)?;
+        let other_directives = ret_ledger.expand_recurring_directives(other_directives);
         let other_directives = ret_ledger.handle_plugin_execution(other_directives)?;
+        ret_ledger.custom_registry.declare(&other_directives);
         ret_ledger.handle_other_directives(other_directives)?;
 
         ret_ledger.metas = meta_directives;
@@ -213,33 +270,113 @@ impl Ledger {
         Ok(ret_ledger)
     }
 
+    /// the directives which the ledger is processed from, including the default options
+    pub(crate) fn source_directives(&self) -> Vec<Spanned<Directive>> {
+        // metas are kept in reversed order after splitting
+        self.metas.iter().rev().chain(self.directives.iter()).cloned().collect_vec()
+    }
+
+    /// rebuild the processed directives of a ledger restored from cache, so that appended directives can be reloaded incrementally
+    pub(crate) fn restore_processed_directives(&mut self) {
+        let SplitDirectives { other_directives, .. } = SplitDirectives::new(self.source_directives());
+        self.processed_directives = self.expand_recurring_directives(other_directives);
+    }
+
+    /// reload the ledger incrementally if possible, otherwise process the whole ledger again,
+    /// see [Ledger::incremental_process] for when it falls back.
     pub fn reload(&mut self) -> ZhangResult<()> {
-        let (entry, endpoint) = &mut self.entry;
+        let (entry, endpoint) = &self.entry;
         let transform_result = self.data_source.load(entry.to_string_lossy().to_string(), endpoint.clone())?;
+        if self.incremental_process(&transform_result.directives)? {
+            self.visited_files = transform_result.visited_files;
+            return Ok(());
+        }
+        let (entry, endpoint) = &self.entry;
         let reload_ledger = Ledger::process(LedgerProcessContext {
             directives: transform_result.directives,
             entry: (entry.clone(), endpoint.clone()),
             visited_files: transform_result.visited_files,
             data_source: self.data_source.clone(),
+            custom_registry: self.custom_registry.clone(),
+            keep_checkpoints: true,
         })?;
         *self = reload_ledger;
         Ok(())
     }
 
     pub async fn async_reload(&mut self) -> ZhangResult<()> {
-        let (entry, endpoint) = &mut self.entry;
+        let (entry, endpoint) = &self.entry;
         let transform_result = self.data_source.async_load(entry.to_string_lossy().to_string(), endpoint.clone()).await?;
+        if self.incremental_process(&transform_result.directives)? {
+            self.visited_files = transform_result.visited_files;
+            return Ok(());
+        }
+        let (entry, endpoint) = &self.entry;
         let reload_ledger = Ledger::async_process(LedgerProcessContext {
             directives: transform_result.directives,
             entry: (entry.clone(), endpoint.clone()),
             visited_files: transform_result.visited_files,
             data_source: self.data_source.clone(),
+            custom_registry: self.custom_registry.clone(),
+            keep_checkpoints: true,
         })
         .await?;
         *self = reload_ledger;
         Ok(())
     }
 
+    /// project the balances of accounts at the end of this month and the following `months` months
+    pub fn forecast(&self, months: u32) -> ZhangResult<ForecastDomain> {
+        forecast(self, self.today(), months)
+    }
+
+    /// clear the income and expenses from `from` to `to` into equity, and open the balances in the next period
+    pub fn close_period(&self, from: NaiveDate, to: NaiveDate) -> ZhangResult<PeriodClosingDomain> {
+        close_period(self, from, to)
+    }
+
+    /// the lots with cost held by the account and its sub-accounts at the datetime, valued by the prices at that time
+    pub fn account_holdings(&self, account_name: &str, datetime: DateTime<Tz>) -> ZhangResult<Vec<HoldingDomain>> {
+        let sub_account_prefix = format!("{}:", account_name);
+        let lots = self.commodity_lots_at(&datetime, |account| account == account_name || account.starts_with(&sub_account_prefix))?;
+        self.operations().lot_holdings(lots, datetime.naive_local())
+    }
+
+    /// the commodity lots of the accounts at the datetime. the store only keeps the latest lots,
+    /// so the directives until the datetime are processed again if any of the accounts has postings after it
+    pub(crate) fn commodity_lots_at<T: TimeZone>(
+        &self, datetime: &DateTime<T>, accounts: impl Fn(&str) -> bool,
+    ) -> ZhangResult<HashMap<String, Vec<CommodityLotRecord>>> {
+        let accounts_lots = |lots: &HashMap<String, Vec<CommodityLotRecord>>| {
+            lots.iter()
+                .filter(|(account, _)| accounts(account))
+                .map(|(account, lots)| (account.clone(), lots.clone()))
+                .collect()
+        };
+        {
+            let store = self.store.read().expect("poison lock detect");
+            if !store.posted_after(&accounts, datetime) {
+                return Ok(accounts_lots(&store.commodity_lots));
+            }
+        }
+        let until = datetime.with_timezone(&self.options.timezone).naive_local();
+        let directives = self
+            .source_directives()
+            .into_iter()
+            .filter(|directive| directive.datetime().map(|it| it <= until).unwrap_or(true))
+            .collect_vec();
+        let ledger = Ledger::process(LedgerProcessContext {
+            directives,
+            entry: self.entry.clone(),
+            visited_files: self.visited_files.clone(),
+            data_source: self.data_source.clone(),
+            custom_registry: self.custom_registry.clone(),
+            keep_checkpoints: false,
+        })?;
+        let store = ledger.store.read().expect("poison lock detect");
+        Ok(accounts_lots(&store.commodity_lots))
+    }
+
     pub fn operations(&self) -> Operations {
         let timezone = self.options.timezone;
         Operations {
@@ -295,22 +432,122 @@ impl Ledger {
         Ok(())
     }
 
-    fn handle_other_directives(&mut self, mut other_directives: Vec<Spanned<Directive>>) -> Result<(), ZhangError> {
-        // handle other directives
-        for directive in other_directives.iter_mut() {
+    /// reprocess the directives from the latest checkpoint before the first changed directive, see [Checkpoints].
+    /// checkpoints are taken before the first directive of every month, and directives only appended after the processed ones
+    /// are replayed on the current store.
+    ///
+    /// return false if the ledger need to be rebuilt, which happens if:
+    /// - options, plugins or custom schema declarations are changed
+    /// - the first changed directive is before all checkpoints, e.g. the ledger is processed without checkpoints
+    pub(crate) fn incremental_process(&mut self, directives: &[Spanned<Directive>]) -> ZhangResult<bool> {
+        self.keep_checkpoints = true;
+        let SplitDirectives {
+            meta_directives,
+            dated_directives,
+            options_directives: _,
+            plugin_directives,
+            other_directives,
+        } = SplitDirectives::new(directives.to_vec());
+
+        let options = |metas: &[Spanned<Directive>]| metas.iter().filter(|it| matches!(it.data, Directive::Option(_))).cloned().collect_vec();
+        if !plugin_directives.is_empty() || options(&meta_directives) != options(&self.metas) {
+            return Ok(false);
+        }
+
+        let other_directives = self.expand_recurring_directives(other_directives);
+        let other_directives = self.handle_plugin_execution(other_directives)?;
+        // declared schemas apply to the directives before them as well
+        let schema_declarations = |directives: &[Spanned<Directive>]| {
+            directives
+                .iter()
+                .filter(|it| matches!(&it.data, Directive::Custom(custom) if custom.custom_type.as_str() == CUSTOM_SCHEMA))
+                .cloned()
+                .collect_vec()
+        };
+        if schema_declarations(&other_directives) != schema_declarations(&self.processed_directives) {
+            return Ok(false);
+        }
+        let changed_position = self
+            .processed_directives
+            .iter()
+            .zip(other_directives.iter())
+            .position(|(previous, current)| previous != current)
+            .unwrap_or_else(|| self.processed_directives.len().min(other_directives.len()));
+
+        if changed_position < self.processed_directives.len() || changed_position < other_directives.len() {
+            let (start, store, trx_counter) = if changed_position == self.processed_directives.len() {
+                // directives are only appended, the current store is the state before them
+                let store = self.store.read().expect("poison lock detect").clone();
+                (changed_position, store, self.trx_counter.load(AtomicOrdering::Relaxed))
+            } else if let Some((position, trx_counter, store)) = self.checkpoints.restore(changed_position, &self.store.read().expect("poison lock detect")) {
+                (position, store, trx_counter)
+            } else {
+                return Ok(false);
+            };
+            info!("reprocess directives from position {} of {}", start, other_directives.len());
+            // replay on a new store, so that the previous one is kept if any error occurs
+            let previous_store = std::mem::replace(&mut self.store, Arc::new(RwLock::new(store)));
+            let previous_trx_counter = self.trx_counter.swap(trx_counter, AtomicOrdering::Relaxed);
+            if let Err(e) = self.replay_other_directives(other_directives, start) {
+                self.store = previous_store;
+                self.trx_counter.store(previous_trx_counter, AtomicOrdering::Relaxed);
+                self.checkpoints.truncate(start);
+                return Err(e);
+            }
+        }
+
+        self.metas = meta_directives;
+        self.directives = dated_directives;
+        let mut operations = self.operations();
+        let errors = operations.errors()?;
+        if !errors.is_empty() {
+            error!("Ledger reloaded with {} error", errors.len());
+        } else {
+            info!("Ledger reloaded");
+        }
+        Ok(true)
+    }
+
+    /// whether a checkpoint is taken before the directive at `position`, which is the first directive of a month
+    fn is_checkpoint(&self, directives: &[Spanned<Directive>], position: usize) -> bool {
+        let month = |position: usize| directives[position].datetime().map(|datetime| (datetime.year(), datetime.month()));
+        self.keep_checkpoints && month(position).is_some() && (position == 0 || month(position - 1) != month(position))
+    }
+
+    fn handle_other_directives(&mut self, other_directives: Vec<Spanned<Directive>>) -> Result<(), ZhangError> {
+        self.replay_other_directives(other_directives, 0)
+    }
+
+    /// process the directives from `start`, the store must be in the state that all directives before `start` are processed.
+    fn replay_other_directives(&mut self, other_directives: Vec<Spanned<Directive>>, start: usize) -> Result<(), ZhangError> {
+        // checkpoints before `start` are still valid since the directives before `start` are not changed
+        self.checkpoints.truncate(start);
+
+        self.account_lifetimes = account_lifetimes(&other_directives);
+        let mut directives = other_directives.clone();
+        for (position, directive) in directives.iter_mut().enumerate().skip(start) {
+            if self.is_checkpoint(&other_directives, position) {
+                let store = self.store.read().expect("poison lock detect");
+                self.checkpoints.take(position, &store, self.trx_counter.load(AtomicOrdering::Relaxed));
+            }
             match &mut directive.data {
                 Directive::Option(_) => unreachable!("option directive should not be passed into the processor here"),
                 Directive::Open(open) => open.handler(self, &directive.span)?,
                 Directive::Close(close) => close.handler(self, &directive.span)?,
                 Directive::Commodity(commodity) => commodity.handler(self, &directive.span)?,
-                Directive::Transaction(trx) => trx.handler(self, &directive.span)?,
+                Directive::Transaction(trx) => {
+                    let errors = self.store.read().expect("poison lock detect").errors.len();
+                    trx.handler(self, &directive.span)?;
+                    mark_occurrence_errors(self, trx, errors);
+                }
+                Directive::Recurring(_) => unreachable!("recurring directive should be expanded before processing"),
                 Directive::BalancePad(pad) => pad.handler(self, &directive.span)?,
                 Directive::BalanceCheck(check) => check.handler(self, &directive.span)?,
-                Directive::Note(_) => {}
+                Directive::Note(note) => note.handler(self, &directive.span)?,
                 Directive::Document(document) => document.handler(self, &directive.span)?,
                 Directive::Price(price) => price.handler(self, &directive.span)?,
-                Directive::Event(_) => {}
-                Directive::Custom(_) => {}
+                Directive::Event(event) => event.handler(self, &directive.span)?,
+                Directive::Custom(custom) => custom.handler(self, &directive.span)?,
                 Directive::Plugin(_) => unreachable!("plugin directive should not be passed into the processor here"),
                 Directive::Include(_) => {}
                 Directive::Comment(_) => {}
@@ -320,9 +557,25 @@ impl Ledger {
                 Directive::BudgetClose(budget_close) => budget_close.handler(self, &directive.span)?,
             }
         }
+        self.processed_directives = other_directives;
         Ok(())
     }
 
+    fn today(&self) -> NaiveDate {
+        Utc::now().with_timezone(&self.options.timezone).date_naive()
+    }
+
+    /// recurring directives are expanded until the recorded date of ledger, see [recorded_until].
+    /// forecast transactions after the recorded date are kept out of store, they are only used by [Ledger::forecast]
+    fn expand_recurring_directives(&self, other_directives: Vec<Spanned<Directive>>) -> Vec<Spanned<Directive>> {
+        let recorded_until = recorded_until(&other_directives);
+        let other_directives = other_directives
+            .into_iter()
+            .filter(|directive| !matches!(&directive.data, Directive::Transaction(trx) if is_projected(trx, recorded_until)))
+            .collect_vec();
+        expand_recurring_directives(other_directives, recorded_until)
+    }
+
     fn handle_plugin_execution(&mut self, other_directives: Vec<Spanned<Directive>>) -> ZhangResult<Vec<Spanned<Directive>>> {
         let other_directives = Ledger::sort_directives_datetime(other_directives);
         let d = if self.options.features.plugins {
@@ -660,6 +913,57 @@ mod test {
                 .unwrap();
             assert_eq!(BigDecimal::from(7), option.amount)
         }
+
+        #[test]
+        fn should_get_latest_price_through_intermediate_commodity() {
+            let ledger = load_from_temp_str(indoc! {r#"
+                    1970-01-01 commodity CNY
+                    1970-01-01 commodity USD
+                    1970-01-01 commodity AAPL
+                    1970-02-01 price USD 7 CNY
+                    1970-02-02 price USD 8 CNY
+                    1970-02-01 price AAPL 100 USD
+                "#});
+
+            let mut operations = ledger.operations();
+
+            let option = operations
+                .get_price(
+                    NaiveDateTime::new(NaiveDate::from_ymd_opt(1970, 2, 3).unwrap(), NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
+                    "AAPL",
+                    "CNY",
+                )
+                .unwrap()
+                .unwrap();
+            assert_eq!(BigDecimal::from(800), option.amount);
+            assert_eq!(vec!["AAPL", "USD", "CNY"], option.path);
+        }
+
+        #[test]
+        fn should_get_latest_price_at_or_before_date() {
+            let ledger = load_from_temp_str(indoc! {r#"
+                    1970-01-01 commodity CNY
+                    1970-01-01 commodity USD
+                    1970-01-15 price USD 6 CNY
+                    1970-02-05 price USD 8 CNY
+                    1970-02-01 price USD 7 CNY
+                "#});
+
+            let mut operations = ledger.operations();
+            let price_at = |operations: &mut crate::domains::Operations, day: u32| {
+                operations
+                    .get_price(
+                        NaiveDateTime::new(NaiveDate::from_ymd_opt(1970, 2, day).unwrap(), NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
+                        "USD",
+                        "CNY",
+                    )
+                    .unwrap()
+                    .map(|price| price.amount)
+            };
+            assert_eq!(Some(BigDecimal::from(7)), price_at(&mut operations, 3));
+            assert_eq!(Some(BigDecimal::from(8)), price_at(&mut operations, 5));
+            assert_eq!(Some(BigDecimal::from(7)), price_at(&mut operations, 1));
+        }
     }
 
     mod account {
@@ -679,4 +983,199 @@ mod test {
             Ok(())
         }
     }
+
+    mod reload {
+        use std::path::Path;
+        use std::sync::Arc;
+
+        use indoc::indoc;
+        use tempfile::tempdir;
+
+        use crate::data_source::LocalFileSystemDataSource;
+        use crate::data_type::text::ZhangDataType;
+        use crate::ledger::Ledger;
+
+        fn load(path: &Path) -> Ledger {
+            let source = LocalFileSystemDataSource::new(ZhangDataType {});
+            Ledger::load_with_data_source(path.to_path_buf(), "main.zhang".to_string(), Arc::new(source)).unwrap()
+        }
+
+        fn store_json(ledger: &Ledger) -> serde_json::Value {
+            serde_json::to_value(&*ledger.store.read().unwrap()).unwrap()
+        }
+
+        fn setup() -> std::path::PathBuf {
+            let temp_dir = tempdir().unwrap().into_path();
+            std::fs::write(
+                temp_dir.join("main.zhang"),
+                indoc! {r#"
+                    option "operating_currency" "CNY"
+                    1970-01-01 commodity CNY
+                    1970-01-01 open Assets:Bank
+                    1970-01-01 open Expenses:Food
+                    include "2024-01.zhang"
+                    include "2024-02.zhang"
+                "#},
+            )
+            .unwrap();
+            std::fs::write(
+                temp_dir.join("2024-01.zhang"),
+                indoc! {r#"
+                    2024-01-05 "Lunch"
+                      Expenses:Food 10 CNY
+                      Assets:Bank
+                "#},
+            )
+            .unwrap();
+            std::fs::write(
+                temp_dir.join("2024-02.zhang"),
+                indoc! {r#"
+                    2024-02-05 "Lunch"
+                      Expenses:Food 20 CNY
+                      Assets:Bank
+                "#},
+            )
+            .unwrap();
+            temp_dir
+        }
+
+        #[test]
+        fn should_reprocess_changed_month_incrementally() {
+            let temp_dir = setup();
+            let mut ledger = load(&temp_dir);
+
+            std::fs::write(
+                temp_dir.join("2024-02.zhang"),
+                indoc! {r#"
+                    2024-02-05 "Lunch"
+                      Expenses:Food 20 CNY
+                      Assets:Bank
+                    2024-02-06 "Dinner"
+                      Expenses:Food 30 CNY
+                      Assets:Bank
+                    2024-02-28 balance Assets:Bank -10 CNY
+                "#},
+            )
+            .unwrap();
+            let load_result = ledger
+                .data_source
+                .load(temp_dir.to_string_lossy().to_string(), "main.zhang".to_string())
+                .unwrap();
+            assert!(ledger.incremental_process(&load_result.directives).unwrap());
+
+            assert_eq!(store_json(&ledger), store_json(&load(&temp_dir)));
+            assert_eq!(ledger.operations().errors().unwrap().len(), 1);
+        }
+
+        #[test]
+        fn should_rebuild_given_options_changed() {
+            let temp_dir = setup();
+            let mut ledger = load(&temp_dir);
+
+            let main_file = std::fs::read_to_string(temp_dir.join("main.zhang")).unwrap();
+            std::fs::write(temp_dir.join("main.zhang"), main_file.replace("\"CNY\"", "\"USD\"")).unwrap();
+            let load_result = ledger
+                .data_source
+                .load(temp_dir.to_string_lossy().to_string(), "main.zhang".to_string())
+                .unwrap();
+            assert!(!ledger.incremental_process(&load_result.directives).unwrap());
+
+            ledger.reload().unwrap();
+            assert_eq!(ledger.options.operating_currency, "USD");
+            assert_eq!(store_json(&ledger), store_json(&load(&temp_dir)));
+        }
+
+        #[test]
+        fn should_reload_earlier_change_same_as_loading() {
+            let temp_dir = setup();
+            let mut ledger = load(&temp_dir);
+
+            std::fs::write(
+                temp_dir.join("2024-01.zhang"),
+                indoc! {r#"
+                    2024-01-05 "Lunch"
+                      Expenses:Food 15 CNY
+                      Assets:Bank
+                "#},
+            )
+            .unwrap();
+            ledger.reload().unwrap();
+
+            assert_eq!(store_json(&ledger), store_json(&load(&temp_dir)));
+        }
+
+        #[test]
+        fn should_take_checkpoint_before_every_month() {
+            let temp_dir = setup();
+            let ledger = load(&temp_dir);
+
+            let months = ledger
+                .checkpoints
+                .positions()
+                .map(|position| ledger.processed_directives[position].datetime().unwrap().format("%Y-%m").to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(months, vec!["1970-01", "2024-01", "2024-02"]);
+        }
+
+        #[test]
+        fn should_reprocess_early_change_from_checkpoint_without_rebuilding() {
+            let temp_dir = tempdir().unwrap().into_path();
+            std::fs::write(
+                temp_dir.join("main.zhang"),
+                indoc! {r#"
+                    option "operating_currency" "CNY"
+                    1970-01-01 commodity CNY
+                    1970-01-01 commodity AAPL
+                    1970-01-01 open Assets:Bank
+                    1970-01-01 open Assets:Stock
+                    1970-01-01 open Expenses:Food
+                      budget: food
+                    1970-01-01 open Income:Gain
+                    2024-01-01 budget food CNY
+                    include "2024-01.zhang"
+                    include "2024-02.zhang"
+                    include "2024-03.zhang"
+                "#},
+            )
+            .unwrap();
+            let month = |month: u32, lunch: u32| {
+                format!(
+                    indoc! {r#"
+                        2024-{month:02}-01 budget-add food 100 CNY
+                        2024-{month:02}-02 price AAPL {month}00 CNY
+                        2024-{month:02}-03 event "location" "city {month}"
+                        2024-{month:02}-05 "Lunch"
+                          Expenses:Food {lunch} CNY
+                          Assets:Bank
+                        2024-{month:02}-06 "Buy"
+                          Assets:Stock 2 AAPL {{{month}00 CNY}}
+                          Assets:Bank
+                        2024-{month:02}-07 "Sell"
+                          Assets:Stock -1 AAPL {{}} @ {month}50 CNY
+                          Assets:Bank
+                          Income:Gain
+                        2024-{month:02}-08 note Assets:Bank "checked"
+                        2024-{month:02}-28 balance Assets:Bank -1000 CNY
+                    "#},
+                    month = month,
+                    lunch = lunch
+                )
+            };
+            for idx in 1..=3 {
+                std::fs::write(temp_dir.join(format!("2024-{:02}.zhang", idx)), month(idx, 10)).unwrap();
+            }
+            let mut ledger = load(&temp_dir);
+            let positions = ledger.checkpoints.positions().collect::<Vec<_>>();
+
+            std::fs::write(temp_dir.join("2024-01.zhang"), month(1, 15)).unwrap();
+            let load_result = ledger
+                .data_source
+                .load(temp_dir.to_string_lossy().to_string(), "main.zhang".to_string())
+                .unwrap();
+            assert!(ledger.incremental_process(&load_result.directives).unwrap());
+
+            assert_eq!(ledger.checkpoints.positions().collect::<Vec<_>>(), positions);
+            assert_eq!(store_json(&ledger), store_json(&load(&temp_dir)));
+        }
+    }
 }
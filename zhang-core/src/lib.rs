@@ -3,16 +3,21 @@ pub use error::ZhangError;
 #[macro_use]
 pub mod utils;
 
+pub mod cache;
+pub mod closing;
 pub mod constants;
+pub mod custom;
 pub mod data_source;
 pub mod data_type;
 pub mod domains;
 pub mod error;
+pub mod forecast;
 pub mod ledger;
 pub mod options;
 #[cfg(feature = "plugin_runtime")]
 pub mod plugin;
 pub(crate) mod process;
+pub mod query;
 pub mod store;
 
 pub mod features;
@@ -33,7 +38,7 @@ mod test {
     use crate::data_type::text::ZhangDataType;
     use crate::ledger::Ledger;
 
-    fn load_from_text(content: &str) -> Ledger {
+    pub(crate) fn load_from_text(content: &str) -> Ledger {
         let temp_dir = tempdir().unwrap().into_path();
         let example = temp_dir.join("example.zhang");
         std::fs::write(example, content).unwrap();
@@ -158,6 +163,8 @@ mod test {
         }
     }
     mod account {
+        use bigdecimal::BigDecimal;
+        use chrono::Utc;
         use indoc::indoc;
 
         use crate::domains::schemas::AccountStatus;
@@ -211,9 +218,56 @@ mod test {
             assert!(result.contains(&"Assets:A".to_owned()));
             assert!(result.contains(&"Expenses:A".to_owned()));
         }
+
+        #[test]
+        fn should_roll_up_balances_in_account_tree() {
+            let ledger = load_from_text(indoc! {r#"
+                option "operating_currency" "CNY"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Food
+                1970-01-01 open Expenses:Food:Lunch
+                1970-01-01 open Expenses:Food:Dinner
+                1970-01-01 price USD 7 CNY
+
+                1970-01-02 "KFC"
+                  Expenses:Food:Lunch 50 CNY
+                  Assets:Bank
+
+                1970-01-03 "Steak"
+                  Expenses:Food:Dinner 10 USD
+                  Assets:Bank
+
+                1970-01-04 "Snack"
+                  Expenses:Food 5 CNY
+                  Assets:Bank
+            "#});
+            let mut operations = ledger.operations();
+            let tree = operations.account_tree(Utc::now().with_timezone(&ledger.options.timezone)).unwrap();
+
+            assert_eq!(tree.iter().map(|it| it.name.as_str()).collect::<Vec<_>>(), vec!["Assets", "Expenses"]);
+            let expenses = &tree[1];
+            assert_eq!(expenses.status, None);
+            assert!(expenses.balance.detail.is_empty());
+            assert_eq!(expenses.total.calculated.number, BigDecimal::from(125));
+
+            let food = &expenses.children[0];
+            assert_eq!(food.name, "Expenses:Food");
+            assert_eq!(food.status, Some(AccountStatus::Open));
+            assert_eq!(food.balance.calculated.number, BigDecimal::from(5));
+            assert_eq!(food.total.calculated.number, BigDecimal::from(125));
+            assert_eq!(food.total.detail.get("CNY"), Some(&BigDecimal::from(55)));
+            assert_eq!(food.total.detail.get("USD"), Some(&BigDecimal::from(10)));
+            assert_eq!(
+                food.children.iter().map(|it| it.name.as_str()).collect::<Vec<_>>(),
+                vec!["Expenses:Food:Dinner", "Expenses:Food:Lunch"]
+            );
+            assert_eq!(food.children[0].total.calculated.number, BigDecimal::from(70));
+        }
     }
 
     mod account_balance {
+        use std::str::FromStr;
+
         use bigdecimal::BigDecimal;
         use indoc::indoc;
 
@@ -278,12 +332,71 @@ mod test {
             assert_eq!(balance.balance_number, BigDecimal::from(2970i32));
             assert_eq!(balance.balance_commodity, "CNY");
         }
+
+        #[test]
+        fn should_keep_balance_given_balance_check_within_tolerance() {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 commodity CNY
+                1970-01-01 open Assets:A
+                1970-01-01 open Expenses:B
+
+                2023-01-01 "Shopping" ""
+                    Assets:A -10.004 CNY
+                    Expenses:B
+
+                2023-01-02 balance Assets:A -10.00 CNY
+            "#});
+
+            let mut operations = ledger.operations();
+            assert_eq!(operations.errors().unwrap().len(), 0);
+
+            let balance = operations.single_account_latest_balances("Assets:A").unwrap().pop().unwrap();
+            assert_eq!(balance.balance_number, BigDecimal::from_str("-10.004").unwrap());
+        }
     }
     mod commodity {
+        use std::str::FromStr;
+
+        use bigdecimal::BigDecimal;
         use indoc::indoc;
+        use zhang_ast::amount::Amount;
 
         use crate::test::load_from_text;
 
+        #[test]
+        fn should_value_holdings_at_date_before_later_postings() {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 commodity USD
+                1970-01-01 commodity AAPL
+                1970-01-01 open Assets:Broker
+                1970-01-01 open Assets:Cash
+
+                2024-01-03 * "Buy AAPL"
+                  Assets:Broker 10 AAPL {100 USD}
+                  Assets:Cash -1000 USD
+
+                2024-01-04 price AAPL 120 USD
+
+                2024-01-05 * "Buy AAPL"
+                  Assets:Broker 5 AAPL {110 USD}
+                  Assets:Cash -550 USD
+
+                2024-01-05 price AAPL 130 USD
+            "#});
+
+            let date =
+                |day: u32| crate::utils::date_range::end_of_date(&ledger.options.timezone, chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap()).unwrap();
+            let holdings = ledger.account_holdings("Assets:Broker", date(4)).unwrap();
+            assert_eq!(holdings.len(), 1);
+            assert_eq!(holdings[0].amount, BigDecimal::from(10));
+            assert_eq!(holdings[0].market_value.as_ref().unwrap().number, BigDecimal::from(1200));
+
+            let holdings = ledger.account_holdings("Assets:Broker", date(5)).unwrap();
+            assert_eq!(holdings.len(), 2);
+            assert_eq!(holdings[1].amount, BigDecimal::from(5));
+            assert_eq!(holdings[1].market_value.as_ref().unwrap().number, BigDecimal::from(650));
+        }
+
         #[test]
         fn should_get_commodity() -> Result<(), Box<dyn std::error::Error>> {
             let ledger = load_from_text(indoc! {r#"
@@ -377,6 +490,239 @@ mod test {
             assert_eq!(None, commodity.suffix);
             Ok(())
         }
+
+        #[test]
+        fn should_operating_currency_use_default_commodity_precision() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                option "default_commodity_precision" "3"
+                option "operating_currency" "USD"
+            "#});
+
+            let operations = ledger.operations();
+            let commodity = operations.commodity("USD")?.unwrap();
+            assert_eq!(3, commodity.precision);
+            Ok(())
+        }
+
+        #[test]
+        fn should_format_amount_with_commodity() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 commodity USD
+                  prefix: "$"
+            "#});
+
+            let formatter = ledger.operations().amount_formatter();
+            let amount = Amount::new(BigDecimal::from_str("1234.5")?, "USD");
+            assert_eq!("$1,234.50", formatter.display(&amount));
+            assert_eq!("1234.50", formatter.pad(&amount).number.to_string());
+            Ok(())
+        }
+    }
+    mod budget {
+        use bigdecimal::BigDecimal;
+        use indoc::indoc;
+
+        use zhang_ast::error::ErrorKind;
+
+        use crate::store::BudgetEventType;
+        use crate::test::load_from_text;
+
+        const LEDGER: &str = indoc! {r#"
+            1970-01-01 open Assets:Bank CNY
+            1970-01-01 open Expenses:Food CNY
+              budget: food
+            1970-01-01 open Expenses:Game CNY
+              budget: game
+
+            2024-01-01 budget food CNY
+              rollover: "true"
+            2024-01-01 budget game CNY
+
+            2024-01-01 budget-add food 1000 CNY
+            2024-01-01 budget-add game 100 CNY
+            2024-01-05 "KFC"
+              Expenses:Food 300 CNY
+              Assets:Bank
+            2024-01-06 "Steam"
+              Expenses:Game 150 CNY
+              Assets:Bank
+
+            2024-02-01 budget-add food 500 CNY
+            2024-02-10 "Dinner"
+              Expenses:Food 1500 CNY
+              Assets:Bank
+        "#};
+
+        #[test]
+        fn should_carry_over_leftover_into_next_interval() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(LEDGER);
+            let operations = ledger.operations();
+
+            let january = operations.budget_interval_detail("food", 202401)?.unwrap();
+            assert_eq!(BigDecimal::from(700), january.available_amount.number);
+
+            let february = operations.budget_interval_detail("food", 202402)?.unwrap();
+            assert_eq!(BigDecimal::from(500), february.assigned_amount.number);
+            assert_eq!(BigDecimal::from(1500), february.activity_amount.number);
+            assert_eq!(BigDecimal::from(-300), february.available_amount.number);
+            assert!(matches!(february.events[0].event_type, BudgetEventType::Rollover));
+            assert_eq!(BigDecimal::from(700), february.events[0].amount.number);
+
+            let march = operations.budget_interval_detail("food", 202403)?.unwrap();
+            assert_eq!(BigDecimal::from(0), march.assigned_amount.number);
+            assert_eq!(BigDecimal::from(-300), march.available_amount.number);
+            Ok(())
+        }
+
+        #[test]
+        fn should_carry_over_leftover_as_assigned_amount_without_rollover() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(LEDGER);
+            let operations = ledger.operations();
+
+            let january = operations.budget_interval_detail("game", 202401)?.unwrap();
+            assert_eq!(BigDecimal::from(-50), january.available_amount.number);
+
+            let february = operations.budget_interval_detail("game", 202402)?.unwrap();
+            assert_eq!(BigDecimal::from(-50), february.assigned_amount.number);
+            assert_eq!(BigDecimal::from(0), february.activity_amount.number);
+            assert_eq!(BigDecimal::from(-50), february.available_amount.number);
+            assert!(february.events.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn should_not_have_detail_before_budget_created() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(LEDGER);
+            let operations = ledger.operations();
+
+            assert!(operations.budget_interval_detail("food", 202312)?.is_none());
+            Ok(())
+        }
+
+        #[test]
+        fn should_convert_activity_into_budget_commodity() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 commodity CNY
+                1970-01-01 commodity EUR
+                1970-01-01 commodity USD
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Travel
+                  budget: travel
+
+                2024-01-01 budget travel CNY
+                2024-01-01 budget-add travel 5000 CNY
+                2024-01-01 price EUR 7.8 CNY
+                2024-01-10 price EUR 8 CNY
+
+                2024-01-05 "Hotel"
+                  Expenses:Travel 100 EUR
+                  Assets:Bank
+                2024-01-12 "Museum"
+                  Expenses:Travel 20 EUR
+                  Assets:Bank
+                2024-01-15 "Taxi"
+                  Expenses:Travel 30 USD
+                  Assets:Bank
+            "#});
+            let mut operations = ledger.operations();
+
+            let detail = operations.budget_interval_detail("travel", 202401)?.unwrap();
+            assert_eq!(BigDecimal::from(940), detail.activity_amount.number);
+            assert_eq!("CNY", detail.activity_amount.currency);
+            assert_eq!(BigDecimal::from(4060), detail.available_amount.number);
+
+            let unconverted = detail
+                .events
+                .iter()
+                .filter(|event| matches!(event.event_type, BudgetEventType::UnconvertedActivity))
+                .collect::<Vec<_>>();
+            assert_eq!(unconverted.len(), 1);
+            assert_eq!(BigDecimal::from(30), unconverted[0].amount.number);
+            assert_eq!("USD", unconverted[0].amount.currency);
+
+            let errors = operations.errors()?;
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].error_type, ErrorKind::BudgetActivityPriceNotFound);
+            assert_eq!(errors[0].metas.get("amount").unwrap(), "30 USD");
+            Ok(())
+        }
+
+        #[test]
+        fn should_get_progress_of_targets() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank CNY
+                1970-01-01 open Expenses:Travel CNY
+                  budget: travel
+
+                2024-01-01 budget travel CNY
+                  rollover: "true"
+                  target: "300"
+                  goal: "1000"
+                  goal_date: "2024-12-31"
+                  cap: "400"
+
+                2024-01-01 budget-add travel 500 CNY
+                2024-01-10 "Train"
+                  Expenses:Travel 100 CNY
+                  Assets:Bank
+            "#});
+            let operations = ledger.operations();
+
+            let progress = operations.budget_target_progress("travel", 202401)?;
+            assert_eq!(progress.len(), 3);
+            let summary = progress
+                .iter()
+                .map(|it| (it.current.number.to_string(), it.remaining.number.to_string(), it.achieved))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                summary,
+                vec![
+                    ("500".to_owned(), "0".to_owned(), true),
+                    ("400".to_owned(), "600".to_owned(), false),
+                    ("100".to_owned(), "300".to_owned(), true),
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn should_use_period_of_budget() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank CNY
+                1970-01-01 open Expenses:Insurance CNY
+                  budget: insurance
+                1970-01-01 open Expenses:Groceries CNY
+                  budget: groceries
+
+                2024-01-01 budget insurance CNY
+                  period: "yearly"
+                2024-01-01 budget groceries CNY
+                  period: "weekly"
+                  rollover: "true"
+
+                2024-01-01 budget-add insurance 3000 CNY
+                2024-01-01 budget-add groceries 200 CNY
+                2024-01-03 "Market"
+                  Expenses:Groceries 150 CNY
+                  Assets:Bank
+                2024-06-01 "Insurance"
+                  Expenses:Insurance 2500 CNY
+                  Assets:Bank
+            "#});
+            let operations = ledger.operations();
+
+            let insurance = operations.budget_interval_detail("insurance", 202401)?.unwrap();
+            assert_eq!(BigDecimal::from(3000), insurance.assigned_amount.number);
+            assert_eq!(BigDecimal::from(500), insurance.available_amount.number);
+
+            let first_week = operations.budget_interval_detail("groceries", 202401)?.unwrap();
+            assert_eq!(BigDecimal::from(50), first_week.available_amount.number);
+            let second_week = operations.budget_interval_detail("groceries", 202402)?.unwrap();
+            assert_eq!(BigDecimal::from(0), second_week.assigned_amount.number);
+            assert_eq!(BigDecimal::from(50), second_week.available_amount.number);
+            assert_eq!("2024-01-08", second_week.events[0].datetime.date_naive().to_string());
+            Ok(())
+        }
     }
     mod error {
         use indoc::indoc;
@@ -438,6 +784,227 @@ mod test {
             assert_eq!(domain.metas.get("account_name").unwrap(), "Assets:MyCard");
             Ok(())
         }
+
+        mod budget {
+            use indoc::indoc;
+            use zhang_ast::error::ErrorKind;
+
+            use crate::test::load_from_text;
+
+            #[test]
+            fn should_raise_overspent_once_available_becomes_negative() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    1970-01-01 open Assets:Bank CNY
+                    1970-01-01 open Expenses:Food CNY
+                      budget: food
+                    2024-01-01 budget food CNY
+                    2024-01-01 budget-add food 100 CNY
+                    2024-01-02 "KFC"
+                      Expenses:Food 80 CNY
+                      Assets:Bank
+                    2024-01-03 "KFC"
+                      Expenses:Food 80 CNY
+                      Assets:Bank
+                    2024-01-04 "KFC"
+                      Expenses:Food 80 CNY
+                      Assets:Bank
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].error_type, ErrorKind::BudgetOverspent);
+                assert_eq!(errors[0].metas.get("budget_name").unwrap(), "food");
+                assert_eq!(errors[0].metas.get("interval").unwrap(), "202401");
+                assert_eq!(errors[0].metas.get("available_amount").unwrap(), "-60 CNY");
+                Ok(())
+            }
+
+            #[test]
+            fn should_raise_spending_cap_exceeded() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    1970-01-01 open Assets:Bank CNY
+                    1970-01-01 open Expenses:Food CNY
+                      budget: food
+                    2024-01-01 budget food CNY
+                      cap: "100"
+                    2024-01-01 budget-add food 500 CNY
+                    2024-01-02 "KFC"
+                      Expenses:Food 80 CNY
+                      Assets:Bank
+                    2024-01-03 "KFC"
+                      Expenses:Food 80 CNY
+                      Assets:Bank
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].error_type, ErrorKind::BudgetSpendingCapExceeded);
+                assert_eq!(errors[0].metas.get("cap").unwrap(), "100 CNY");
+                Ok(())
+            }
+
+            #[test]
+            fn should_raise_error_when_closing_non_empty_budget() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    2024-01-01 budget food CNY
+                    2024-01-01 budget-add food 100 CNY
+                    2024-01-31 budget-close food
+                    2024-02-01 budget vacation CNY
+                    2024-02-01 budget-add vacation 100 CNY
+                    2024-02-02 budget-transfer vacation food 100 CNY
+                    2024-02-03 budget-close vacation
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].error_type, ErrorKind::BudgetCloseNonEmpty);
+                assert_eq!(errors[0].metas.get("budget_name").unwrap(), "food");
+                assert_eq!(errors[0].metas.get("available_amount").unwrap(), "100 CNY");
+                Ok(())
+            }
+
+            #[test]
+            fn should_raise_error_for_invalid_target() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    2024-01-01 budget food CNY
+                      target: "a lot"
+                      goal: "1000"
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert_eq!(errors.len(), 2);
+                assert!(errors.iter().all(|error| error.error_type == ErrorKind::ParseInvalidMeta));
+                assert!(operations.budget("food")?.unwrap().targets.is_empty());
+                Ok(())
+            }
+
+            #[test]
+            fn should_fall_back_to_monthly_for_invalid_period() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    2024-01-01 budget food CNY
+                      period: "fortnightly"
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].error_type, ErrorKind::ParseInvalidMeta);
+                assert_eq!(errors[0].metas.get("period").unwrap(), "fortnightly");
+                assert_eq!(operations.budget("food")?.unwrap().period, zhang_ast::BudgetPeriod::Monthly);
+                Ok(())
+            }
+        }
+
+        mod account_not_open_at_date {
+            use indoc::indoc;
+            use zhang_ast::error::ErrorKind;
+
+            use crate::test::load_from_text;
+
+            #[test]
+            fn should_raise_error_for_posting_before_open() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    1970-01-01 open Expenses:Lunch
+                    1970-01-02 "KFC" "Crazy Thursday"
+                      Assets:MyCard -50 CNY
+                      Expenses:Lunch 50 CNY
+                    1970-01-03 open Assets:MyCard
+                "#});
+
+                let mut operations = ledger.operations();
+                let mut errors = operations.errors()?;
+                assert_eq!(errors.len(), 1);
+                let error = errors.pop().unwrap();
+                assert_eq!(error.error_type, ErrorKind::AccountNotOpenAtDate);
+                assert_eq!(error.metas.get("account_name").unwrap(), "Assets:MyCard");
+                assert_eq!(error.metas.get("open_date").unwrap(), "1970-01-03");
+                assert_eq!(error.metas.get("date").unwrap(), "1970-01-02");
+                Ok(())
+            }
+
+            #[test]
+            fn should_raise_error_for_directives_after_close() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    1970-01-01 open Assets:MyCard
+                    1970-01-01 open Expenses:Lunch
+                    1970-01-03 close Assets:MyCard
+                    1970-01-04 "KFC" "Crazy Thursday"
+                      Assets:MyCard -50 CNY
+                      Expenses:Lunch 50 CNY
+                    1970-01-05 note Assets:MyCard "card is cut"
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert_eq!(errors.len(), 2);
+                assert!(errors.iter().all(|error| error.error_type == ErrorKind::AccountNotOpenAtDate));
+                assert!(errors.iter().all(|error| error.metas.get("close_date").unwrap() == "1970-01-03"));
+                Ok(())
+            }
+
+            #[test]
+            fn should_raise_error_for_directives_between_close_and_reopen() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    1970-01-01 open Assets:MyCard
+                    1970-01-01 open Expenses:Lunch
+                    1970-01-03 close Assets:MyCard
+                    1970-01-04 "KFC" "Crazy Thursday"
+                      Assets:MyCard -50 CNY
+                      Expenses:Lunch 50 CNY
+                    1970-01-05 open Assets:MyCard
+                    1970-01-06 "KFC" "Crazy Thursday"
+                      Assets:MyCard -50 CNY
+                      Expenses:Lunch 50 CNY
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].error_type, ErrorKind::AccountNotOpenAtDate);
+                assert_eq!(errors[0].metas.get("close_date").unwrap(), "1970-01-03");
+                assert_eq!(errors[0].metas.get("date").unwrap(), "1970-01-04");
+                Ok(())
+            }
+
+            #[test]
+            fn should_allow_directives_on_the_day_of_open_and_close() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    1970-01-01 open Expenses:Lunch
+                    1970-01-02 "KFC" "Crazy Thursday"
+                      Assets:MyCard -50 CNY
+                      Expenses:Lunch 50 CNY
+                    1970-01-02 open Assets:MyCard
+                    1970-01-03 note Assets:MyCard "card is cut"
+                    1970-01-03 close Assets:MyCard
+                    1970-01-03 document Assets:MyCard "card.png"
+                "#});
+
+                let mut operations = ledger.operations();
+                let errors = operations.errors()?;
+                assert!(errors.iter().all(|error| error.error_type != ErrorKind::AccountNotOpenAtDate));
+                Ok(())
+            }
+
+            #[test]
+            fn should_raise_account_does_not_exist_for_never_opened_account() -> Result<(), Box<dyn std::error::Error>> {
+                let ledger = load_from_text(indoc! {r#"
+                    1970-01-01 open Expenses:Lunch
+                    1970-01-02 "KFC" "Crazy Thursday"
+                      Assets:MyCard -50 CNY
+                      Expenses:Lunch 50 CNY
+                "#});
+
+                let mut operations = ledger.operations();
+                let mut errors = operations.errors()?;
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors.pop().unwrap().error_type, ErrorKind::AccountDoesNotExist);
+                Ok(())
+            }
+        }
     }
     mod timezone {
         use indoc::indoc;
@@ -530,4 +1097,121 @@ mod test {
             assert_eq!(1, result.len());
         }
     }
+
+    mod recurring {
+        use bigdecimal::BigDecimal;
+        use chrono::NaiveDate;
+        use indoc::indoc;
+        use itertools::Itertools;
+
+        use crate::constants::{OCCURRENCE_DATE, RECURRING_ID};
+        use crate::test::load_from_text;
+
+        fn transaction_dates(ledger: &crate::ledger::Ledger) -> Vec<NaiveDate> {
+            let store = ledger.store.read().unwrap();
+            store.transactions.values().map(|trx| trx.datetime.date_naive()).sorted().collect_vec()
+        }
+
+        fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+            NaiveDate::from_ymd_opt(year, month, day).unwrap()
+        }
+
+        #[test]
+        fn should_expand_recurring_until_given_date() {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Rent
+                2024-01-31 recurring "monthly" "Landlord" "Rent"
+                  Expenses:Rent 1000 CNY
+                  Assets:Bank
+                  until: 2024-04-30
+                2024-12-31 note Assets:Bank "statement checked"
+            "#});
+
+            assert_eq!(
+                transaction_dates(&ledger),
+                vec![date(2024, 1, 31), date(2024, 2, 29), date(2024, 3, 31), date(2024, 4, 30)]
+            );
+            let balance = ledger.operations().single_account_latest_balances("Expenses:Rent").unwrap().pop().unwrap();
+            assert_eq!(balance.balance_number, BigDecimal::from(4000));
+
+            let store = ledger.store.read().unwrap();
+            let recurring_ids = store.metas.iter().filter(|meta| meta.key == RECURRING_ID).map(|meta| &meta.value).collect_vec();
+            assert_eq!(recurring_ids.len(), 4);
+            assert!(recurring_ids.iter().all_equal());
+        }
+
+        #[test]
+        fn should_override_generated_transaction_by_explicit_transaction_with_same_link() {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Rent
+                2024-01-01 recurring "monthly" "Landlord" "Rent" ^rent
+                  Expenses:Rent 1000 CNY
+                  Assets:Bank
+                  until: 2024-03-31
+                2024-02-03 "Landlord" "Rent with repair fee" ^rent
+                  Expenses:Rent 1200 CNY
+                  Assets:Bank
+                2024-12-31 note Assets:Bank "statement checked"
+            "#});
+
+            assert_eq!(transaction_dates(&ledger), vec![date(2024, 1, 1), date(2024, 2, 3), date(2024, 3, 1)]);
+            let balance = ledger.operations().single_account_latest_balances("Expenses:Rent").unwrap().pop().unwrap();
+            assert_eq!(balance.balance_number, BigDecimal::from(3200));
+        }
+
+        #[test]
+        fn should_expand_weekly_recurring() {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Subscription
+                2024-01-01 recurring "weekly" "Newspaper"
+                  Expenses:Subscription 5 CNY
+                  Assets:Bank
+                  until: 2024-01-20
+                2024-12-31 note Assets:Bank "statement checked"
+            "#});
+
+            assert_eq!(transaction_dates(&ledger), vec![date(2024, 1, 1), date(2024, 1, 8), date(2024, 1, 15)]);
+        }
+
+        #[test]
+        fn should_expand_recurring_until_recorded_date() {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Rent
+                2024-01-01 recurring "monthly" "Landlord" "Rent"
+                  Expenses:Rent 1000 CNY
+                  Assets:Bank
+                2024-03-15 note Assets:Bank "statement checked"
+                2024-05-01 # "Landlord" "Rent"
+                  Expenses:Rent 1000 CNY
+                  Assets:Bank
+            "#});
+
+            assert_eq!(transaction_dates(&ledger), vec![date(2024, 1, 1), date(2024, 2, 1), date(2024, 3, 1)]);
+        }
+
+        #[test]
+        fn should_mark_errors_of_generated_transactions_with_occurrence_date() {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                2024-01-01 recurring "monthly" "Landlord" "Rent"
+                  Expenses:Rent 1000 CNY
+                  Assets:Bank
+                  until: 2024-02-01
+                2024-03-01 note Assets:Bank "statement checked"
+            "#});
+
+            let errors = ledger.operations().errors().unwrap();
+            let occurrence_dates = errors
+                .iter()
+                .filter_map(|error| error.metas.get(OCCURRENCE_DATE))
+                .unique()
+                .sorted()
+                .collect_vec();
+            assert_eq!(occurrence_dates, vec!["2024-01-01", "2024-02-01"]);
+        }
+    }
 }
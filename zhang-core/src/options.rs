@@ -17,11 +17,12 @@ use crate::domains::Operations;
 use crate::features::Features;
 use crate::{ZhangError, ZhangResult};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct InMemoryOptions {
     pub operating_currency: String,
     pub default_rounding: Rounding,
     pub default_balance_tolerance_precision: i32,
+    pub default_commodity_precision: i32,
     pub default_booking_method: BookingMethod,
     pub timezone: Tz,
     pub features: Features,
@@ -98,7 +99,7 @@ impl InMemoryOptions {
         if let Ok(option) = BuiltinOption::from_str(&key) {
             match option {
                 BuiltinOption::OperatingCurrency => {
-                    let precision = self.default_balance_tolerance_precision;
+                    let precision = self.default_commodity_precision;
                     let prefix: Option<String> = None;
                     let suffix: Option<String> = None;
                     let rounding = self.default_rounding;
@@ -119,7 +120,11 @@ impl InMemoryOptions {
                         self.default_balance_tolerance_precision = ret
                     }
                 }
-                BuiltinOption::DefaultCommodityPrecision => {}
+                BuiltinOption::DefaultCommodityPrecision => {
+                    if let Ok(ret) = value.parse::<i32>() {
+                        self.default_commodity_precision = ret
+                    }
+                }
                 BuiltinOption::Timezone => match value.parse::<Tz>() {
                     Ok(tz) => {
                         self.timezone = tz;
@@ -146,6 +151,7 @@ impl Default for InMemoryOptions {
             operating_currency: "CNY".to_string(),
             default_rounding: Rounding::RoundDown,
             default_balance_tolerance_precision: 2,
+            default_commodity_precision: DEFAULT_COMMODITY_PRECISION,
             default_booking_method: BookingMethod::Fifo,
             timezone: BuiltinOption::Timezone.default_value().parse().expect("invalid timezone"),
             features: Features::default(),
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 use std::ops::Sub;
+use std::str::FromStr;
 
 use bigdecimal::{BigDecimal, Zero};
 use zhang_ast::amount::Amount;
 use zhang_ast::error::ErrorKind;
 use zhang_ast::{BalanceCheck, BalancePad, Flag, Posting, SpanInfo, Transaction, ZhangString};
 
+use crate::constants::{BALANCE_INCLUDE_SUB_ACCOUNTS, COMMODITY_BALANCE_TOLERANCE, KEY_BALANCE_INCLUDE_SUB_ACCOUNTS, TRUE};
+use crate::domains::schemas::MetaType;
 use crate::ledger::Ledger;
 use crate::process::DirectiveProcess;
 use crate::utils::hashmap::HashMapOfExt;
@@ -13,10 +16,10 @@ use crate::{process, ZhangResult};
 
 impl DirectiveProcess for BalancePad {
     fn validate(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<bool> {
-        process::check_account_existed(self.account.name(), ledger, span)?;
-        process::check_account_existed(self.pad.name(), ledger, span)?;
-        process::check_account_closed(self.account.name(), ledger, span)?;
-        process::check_account_closed(self.pad.name(), ledger, span)?;
+        process::check_account_open_at(self.account.name(), self.date.naive_date(), ledger, span)?;
+        process::check_account_open_at(self.pad.name(), self.date.naive_date(), ledger, span)?;
+        process::check_commodity_allowed(self.account.name(), &self.amount.currency, ledger, span)?;
+        process::check_commodity_allowed(self.pad.name(), &self.amount.currency, ledger, span)?;
         Ok(true)
     }
 
@@ -69,29 +72,56 @@ impl DirectiveProcess for BalancePad {
 
 impl DirectiveProcess for BalanceCheck {
     fn validate(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<bool> {
-        process::check_account_existed(self.account.name(), ledger, span)?;
-        process::check_account_closed(self.account.name(), ledger, span)?;
+        process::check_account_open_at(self.account.name(), self.date.naive_date(), ledger, span)?;
+        process::check_commodity_allowed(self.account.name(), &self.amount.currency, ledger, span)?;
         Ok(true)
     }
 
     fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
-        let option = operations.account_target_day_balance(
-            self.account.name(),
-            self.date.to_timezone_datetime(&ledger.options.timezone),
-            &self.amount.currency,
-        )?;
+        let datetime = self.date.to_timezone_datetime(&ledger.options.timezone);
 
-        let current_balance_amount = option.map(|it| it.number).unwrap_or_else(BigDecimal::zero);
+        // balances of sub-accounts are only collected when the assertion covers the whole account tree
+        let sub_account_balances = if include_sub_accounts(self, ledger)? {
+            Some(operations.account_tree_target_day_balances(self.account.name(), datetime, &self.amount.currency)?)
+        } else {
+            None
+        };
+        let current_balance_amount = match &sub_account_balances {
+            Some(balances) => balances.values().sum(),
+            None => operations
+                .account_target_day_balance(self.account.name(), datetime, &self.amount.currency)?
+                .map(|it| it.number)
+                .unwrap_or_else(BigDecimal::zero),
+        };
 
         let distance = Amount::new((&self.amount.number).sub(&current_balance_amount), self.amount.currency.clone());
-        if !distance.is_zero() {
-            operations.new_error(
-                ErrorKind::AccountBalanceCheckError,
-                span,
-                HashMap::of("account_name", self.account.name().to_string()),
-            )?;
+        let tolerance = balance_tolerance(self, ledger, span)?;
+        let exceeded = distance.number.abs() > tolerance;
+        if exceeded {
+            let mut metas = HashMap::of3(
+                "account_name",
+                self.account.name(),
+                "distance",
+                distance.number.to_string(),
+                "tolerance",
+                tolerance.to_string(),
+            );
+            // per-account breakdown, keyed by account name
+            metas.extend(
+                sub_account_balances
+                    .into_iter()
+                    .flatten()
+                    .map(|(account, balance)| (account, format!("{} {}", balance, self.amount.currency))),
+            );
+            operations.new_error(ErrorKind::AccountBalanceCheckError, span, metas)?;
         }
+        // the difference within tolerance is accepted as it is, so the balance is not moved
+        let adjustment = if exceeded {
+            distance
+        } else {
+            Amount::new(BigDecimal::zero(), self.amount.currency.clone())
+        };
 
         let mut transformed_trx = Transaction {
             date: self.date.clone(),
@@ -103,7 +133,7 @@ impl DirectiveProcess for BalanceCheck {
             postings: vec![Posting {
                 flag: None,
                 account: self.account.clone(),
-                units: Some(distance),
+                units: Some(adjustment),
                 cost: None,
                 price: None,
                 comment: None,
@@ -115,3 +145,46 @@ impl DirectiveProcess for BalanceCheck {
         Ok(())
     }
 }
+
+/// whether the balance assertion covers the account and all of its sub-accounts, like beancount does.
+/// the `include_sub_accounts` meta of the directive has higher priority than the `balance_include_sub_accounts` option
+fn include_sub_accounts(check: &BalanceCheck, ledger: &Ledger) -> ZhangResult<bool> {
+    if let Some(value) = check.meta.get_one(BALANCE_INCLUDE_SUB_ACCOUNTS) {
+        return Ok(value.as_str().to_lowercase().eq(TRUE));
+    }
+    let operations = ledger.operations();
+    Ok(operations
+        .option::<String>(KEY_BALANCE_INCLUDE_SUB_ACCOUNTS)?
+        .map(|value| value.to_lowercase().eq(TRUE))
+        .unwrap_or(false))
+}
+
+/// the tolerance of balance assertion is determined by, in order:
+/// - the explicit tolerance given by `~ 0.01` syntax
+/// - the `balance_tolerance` meta of the commodity
+/// - half unit of the last digit of the asserted number, or of `default_balance_tolerance_precision` if the number has no fractional part
+fn balance_tolerance(check: &BalanceCheck, ledger: &Ledger, span: &SpanInfo) -> ZhangResult<BigDecimal> {
+    if let Some(tolerance) = &check.tolerance {
+        return Ok(tolerance.abs());
+    }
+
+    let mut operations = ledger.operations();
+    if let Some(meta) = operations.meta(MetaType::CommodityMeta, &check.amount.currency, COMMODITY_BALANCE_TOLERANCE)? {
+        match BigDecimal::from_str(&meta.value) {
+            Ok(tolerance) => return Ok(tolerance.abs()),
+            Err(_) => operations.new_error(
+                ErrorKind::ParseInvalidMeta,
+                span,
+                HashMap::of2("commodity", check.amount.currency.as_str(), COMMODITY_BALANCE_TOLERANCE, meta.value.as_str()),
+            )?,
+        }
+    }
+
+    let (_, scale) = check.amount.number.as_bigint_and_exponent();
+    let precision = if scale > 0 {
+        scale
+    } else {
+        ledger.options.default_balance_tolerance_precision as i64
+    };
+    Ok(BigDecimal::new(5.into(), precision + 1))
+}
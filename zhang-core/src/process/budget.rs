@@ -1,11 +1,18 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
+use bigdecimal::{BigDecimal, Signed};
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+use zhang_ast::amount::Amount;
 use zhang_ast::error::ErrorKind;
-use zhang_ast::{Budget, BudgetAdd, BudgetClose, BudgetTransfer, SpanInfo};
+use zhang_ast::{Budget, BudgetAdd, BudgetClose, BudgetPeriod, BudgetTransfer, SpanInfo};
 
+use crate::domains::Operations;
 use crate::ledger::Ledger;
 use crate::process::DirectiveProcess;
-use crate::store::BudgetEventType;
+use crate::store::{BudgetDomain, BudgetEventType, BudgetTarget};
+use crate::utils::hashmap::HashMapOfExt;
 use crate::ZhangResult;
 
 impl DirectiveProcess for Budget {
@@ -19,14 +26,23 @@ impl DirectiveProcess for Budget {
         }
     }
 
-    fn process(&mut self, ledger: &mut Ledger, _span: &SpanInfo) -> ZhangResult<()> {
+    fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
+        let targets = budget_targets(self, &mut operations, span)?;
+        let period = budget_period(self, &mut operations, span)?;
         operations.init_budget(
-            &self.name,
-            &self.commodity,
+            BudgetDomain {
+                name: self.name.clone(),
+                alias: self.meta.get_one("alias").map(|it| it.as_str().to_owned()),
+                category: self.meta.get_one("category").map(|it| it.as_str().to_owned()),
+                closed: false,
+                rollover: self.meta.get_one("rollover").map(|it| it.as_str() == "true").unwrap_or(false),
+                period,
+                targets,
+                detail: Default::default(),
+                commodity: self.commodity.clone(),
+            },
             self.date.to_timezone_datetime(&ledger.options.timezone),
-            self.meta.get_one("alias").map(|it| it.as_str().to_owned()),
-            self.meta.get_one("category").map(|it| it.as_str().to_owned()),
         )?;
         Ok(())
     }
@@ -94,11 +110,117 @@ impl DirectiveProcess for BudgetClose {
         }
     }
 
-    fn process(&mut self, ledger: &mut Ledger, _span: &SpanInfo) -> ZhangResult<()> {
+    fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
-        // todo: check if budget is empty
+        let interval = operations.budget_interval(&self.name, self.date.to_timezone_datetime(&ledger.options.timezone));
+        if let Some(detail) = operations.budget_interval_detail(&self.name, interval)? {
+            if !detail.available_amount.is_zero() {
+                operations.new_error(
+                    ErrorKind::BudgetCloseNonEmpty,
+                    span,
+                    HashMap::of2("budget_name", self.name.as_str(), "available_amount", detail.available_amount.to_string()),
+                )?;
+            }
+        }
 
         operations.budget_close(&self.name, self.date.clone())?;
         Ok(())
     }
 }
+
+/// parse an amount in the commodity of budget from the meta, invalid value is reported and ignored
+fn amount_meta(budget: &Budget, key: &str, operations: &mut Operations, span: &SpanInfo) -> ZhangResult<Option<Amount>> {
+    let Some(value) = budget.meta.get_one(key).map(|it| it.as_str().to_owned()) else {
+        return Ok(None);
+    };
+    match BigDecimal::from_str(&value) {
+        Ok(number) => Ok(Some(Amount::new(number, &budget.commodity))),
+        Err(_) => {
+            operations.new_error(ErrorKind::ParseInvalidMeta, span, HashMap::of2("budget_name", budget.name.as_str(), key, value))?;
+            Ok(None)
+        }
+    }
+}
+
+/// the period of budget from the meta `period`, monthly if absent or invalid
+fn budget_period(budget: &Budget, operations: &mut Operations, span: &SpanInfo) -> ZhangResult<BudgetPeriod> {
+    let Some(value) = budget.meta.get_one("period").map(|it| it.as_str().to_owned()) else {
+        return Ok(BudgetPeriod::default());
+    };
+    match BudgetPeriod::from_str(&value) {
+        Ok(period) => Ok(period),
+        Err(_) => {
+            operations.new_error(
+                ErrorKind::ParseInvalidMeta,
+                span,
+                HashMap::of2("budget_name", budget.name.as_str(), "period", value),
+            )?;
+            Ok(BudgetPeriod::default())
+        }
+    }
+}
+
+/// targets of budget from the metas `target`, `goal` with `goal_date`, and `cap`
+fn budget_targets(budget: &Budget, operations: &mut Operations, span: &SpanInfo) -> ZhangResult<Vec<BudgetTarget>> {
+    let mut targets = vec![];
+    if let Some(amount) = amount_meta(budget, "target", operations, span)? {
+        targets.push(BudgetTarget::Interval { amount });
+    }
+    if let Some(amount) = amount_meta(budget, "goal", operations, span)? {
+        let goal_date = budget.meta.get_one("goal_date").map(|it| it.as_str().to_owned()).unwrap_or_default();
+        match NaiveDate::from_str(&goal_date) {
+            Ok(date) => targets.push(BudgetTarget::SaveBy { amount, date }),
+            Err(_) => operations.new_error(
+                ErrorKind::ParseInvalidMeta,
+                span,
+                HashMap::of2("budget_name", budget.name.as_str(), "goal_date", goal_date),
+            )?,
+        }
+    }
+    if let Some(amount) = amount_meta(budget, "cap", operations, span)? {
+        targets.push(BudgetTarget::SpendingCap { amount });
+    }
+    Ok(targets)
+}
+
+/// report the budget once its available amount becomes negative, or its activity exceeds the spending cap in the interval.
+/// `activity` is the amount just added into the activity of budget
+pub(crate) fn check_budget_activity(operations: &mut Operations, name: &str, datetime: DateTime<Tz>, activity: &Amount, span: &SpanInfo) -> ZhangResult<()> {
+    let Some(budget) = operations.budget(name)? else {
+        return Ok(());
+    };
+    let interval = budget.period.interval(datetime.date_naive());
+    let Some(detail) = operations.budget_interval_detail(name, interval)? else {
+        return Ok(());
+    };
+
+    let previous_available = &detail.available_amount.number + &activity.number;
+    if detail.available_amount.number.is_negative() && !previous_available.is_negative() {
+        operations.new_error(
+            ErrorKind::BudgetOverspent,
+            span,
+            HashMap::of3(
+                "budget_name",
+                name,
+                "interval",
+                interval.to_string(),
+                "available_amount",
+                detail.available_amount.to_string(),
+            ),
+        )?;
+    }
+
+    let previous_activity = &detail.activity_amount.number - &activity.number;
+    for target in budget.targets.iter() {
+        if let BudgetTarget::SpendingCap { amount } = target {
+            if detail.activity_amount.number > amount.number && previous_activity <= amount.number {
+                operations.new_error(
+                    ErrorKind::BudgetSpendingCapExceeded,
+                    span,
+                    HashMap::of3("budget_name", name, "interval", interval.to_string(), "cap", amount.to_string()),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
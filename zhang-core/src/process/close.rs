@@ -13,7 +13,7 @@ impl DirectiveProcess for Close {
         let mut operations = ledger.operations();
 
         // check if account exist
-        process::check_account_existed(self.account.name(), ledger, span)?;
+        process::check_account_open_at(self.account.name(), self.date.naive_date(), ledger, span)?;
         process::check_account_closed(self.account.name(), ledger, span)?;
 
         let balances = operations.single_account_latest_balances(self.account.name())?;
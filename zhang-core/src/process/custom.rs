@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use uuid::Uuid;
+use zhang_ast::error::ErrorKind;
+use zhang_ast::{Custom, SpanInfo};
+
+use crate::custom::{value_as_str, CustomSchema, CUSTOM_SCHEMA};
+use crate::ledger::Ledger;
+use crate::process::DirectiveProcess;
+use crate::utils::hashmap::HashMapOfExt;
+use crate::utils::id::FromSpan;
+use crate::ZhangResult;
+
+impl DirectiveProcess for Custom {
+    fn validate(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<bool> {
+        let violations = if self.custom_type.as_str() == CUSTOM_SCHEMA {
+            CustomSchema::from_declaration(&self.values).err().into_iter().collect_vec()
+        } else {
+            match ledger.custom_registry.schema(self.custom_type.as_str()) {
+                Some(schema) => schema.validate(&self.values),
+                None => return Ok(true),
+            }
+        };
+        let mut operations = ledger.operations();
+        for violation in violations.iter() {
+            let mut metas = HashMap::of3(
+                "custom_type",
+                self.custom_type.as_str(),
+                "argument",
+                violation.argument.as_str(),
+                "expected",
+                violation.expected.as_str(),
+            );
+            metas.insert("actual".to_owned(), violation.actual.clone());
+            operations.new_error(ErrorKind::InvalidCustomArgument, span, metas)?;
+        }
+        Ok(violations.is_empty())
+    }
+
+    fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
+        let arguments: IndexMap<String, String> = ledger
+            .custom_registry
+            .schema(self.custom_type.as_str())
+            .map(|schema| schema.named_arguments(&self.values).into_iter().collect())
+            .unwrap_or_default();
+
+        let mut operations = ledger.operations();
+        operations.insert_custom(
+            &Uuid::from_span(span),
+            self.date.to_timezone_datetime(&ledger.options.timezone),
+            self.custom_type.as_str(),
+            self.values.iter().map(|value| value_as_str(value).to_owned()).collect_vec(),
+            arguments,
+            span,
+        )?;
+        Ok(())
+    }
+}
@@ -9,8 +9,7 @@ use crate::{process, ZhangResult};
 
 impl DirectiveProcess for Document {
     fn validate(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<bool> {
-        process::check_account_existed(self.account.name(), ledger, span)?;
-        process::check_account_closed(self.account.name(), ledger, span)?;
+        process::check_account_open_at(self.account.name(), self.date.naive_date(), ledger, span)?;
         Ok(true)
     }
 
@@ -0,0 +1,22 @@
+use uuid::Uuid;
+use zhang_ast::{Event, SpanInfo};
+
+use crate::ledger::Ledger;
+use crate::process::DirectiveProcess;
+use crate::utils::id::FromSpan;
+use crate::ZhangResult;
+
+impl DirectiveProcess for Event {
+    fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
+        let mut operations = ledger.operations();
+
+        operations.insert_event(
+            &Uuid::from_span(span),
+            self.date.to_timezone_datetime(&ledger.options.timezone),
+            self.event_type.as_str(),
+            self.description.as_str(),
+            span,
+        )?;
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use chrono::NaiveDate;
 use log::trace;
 use zhang_ast::error::ErrorKind;
 use zhang_ast::*;
@@ -13,11 +14,15 @@ pub(crate) mod balance;
 pub(crate) mod budget;
 pub(crate) mod close;
 pub(crate) mod commodity;
+pub(crate) mod custom;
 pub(crate) mod document;
+pub(crate) mod event;
+pub(crate) mod note;
 pub(crate) mod open;
 pub(crate) mod options;
 pub(crate) mod plugin;
 pub(crate) mod price;
+pub(crate) mod recurring;
 pub(crate) mod transaction;
 /// Directive Process is used to handle how a directive be validated, how we process directives and store the result into [Store]
 pub(crate) trait DirectiveProcess: std::fmt::Debug {
@@ -57,12 +62,89 @@ pub(crate) trait DirectivePreProcess {
     }
 }
 
-fn check_account_existed(account_name: &str, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
+/// the periods in which an account is open, from the open date to the close date, both inclusive.
+/// an account can be reopened after closed, so it may have several periods ordered by date
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AccountLifetime {
+    periods: Vec<(NaiveDate, Option<NaiveDate>)>,
+}
+
+impl AccountLifetime {
+    fn is_open_at(&self, date: NaiveDate) -> bool {
+        self.periods
+            .iter()
+            .any(|(open, close)| *open <= date && close.map(|close| date <= close).unwrap_or(true))
+    }
+}
+
+/// the lifetimes of accounts, collected from all directives before processing any of them,
+/// so that a directive before the open of account can be reported with the open date.
+/// the lifetimes only depend on the directives, they are collected again whenever the directives are replayed.
+pub(crate) fn account_lifetimes(directives: &[Spanned<Directive>]) -> HashMap<String, AccountLifetime> {
+    let mut lifetimes: HashMap<String, AccountLifetime> = HashMap::new();
+    for directive in directives {
+        match &directive.data {
+            Directive::Open(open) => {
+                let periods = &mut lifetimes.entry(open.account.name().to_owned()).or_default().periods;
+                // opening an account which is still open does not start a new period
+                if periods.last().map(|(_, close)| close.is_some()).unwrap_or(true) {
+                    periods.push((open.date.naive_date(), None));
+                }
+            }
+            Directive::Close(close) => {
+                let periods = &mut lifetimes.entry(close.account.name().to_owned()).or_default().periods;
+                if let Some((_, period_close @ None)) = periods.last_mut() {
+                    *period_close = Some(close.date.naive_date());
+                }
+            }
+            _ => {}
+        }
+    }
+    lifetimes
+}
+
+/// check the account is open at the date, directives on the day of open or close are allowed
+fn check_account_open_at(account_name: &str, date: NaiveDate, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
     let mut operations = ledger.operations();
-    let existed = operations.exist_account(account_name)?;
+    let lifetime = ledger.account_lifetimes.get(account_name).cloned().unwrap_or_default();
+    if lifetime.is_open_at(date) {
+        return Ok(());
+    }
 
-    if !existed {
-        operations.new_error(ErrorKind::AccountDoesNotExist, span, HashMap::of("account_name", account_name.to_string()))?;
+    // the latest period before the date, or the first period if the date is before all of them
+    let period = lifetime.periods.iter().rev().find(|(open, _)| *open <= date).or(lifetime.periods.first());
+    match period {
+        None => {
+            operations.new_error(ErrorKind::AccountDoesNotExist, span, HashMap::of("account_name", account_name.to_string()))?;
+        }
+        Some((open, _)) if date < *open => {
+            operations.new_error(
+                ErrorKind::AccountNotOpenAtDate,
+                span,
+                HashMap::of3(
+                    "account_name",
+                    account_name.to_string(),
+                    "open_date",
+                    open.to_string(),
+                    "date",
+                    date.to_string(),
+                ),
+            )?;
+        }
+        Some((_, close)) => {
+            operations.new_error(
+                ErrorKind::AccountNotOpenAtDate,
+                span,
+                HashMap::of3(
+                    "account_name",
+                    account_name.to_string(),
+                    "close_date",
+                    close.map(|close| close.to_string()).unwrap_or_default(),
+                    "date",
+                    date.to_string(),
+                ),
+            )?;
+        }
     }
     Ok(())
 }
@@ -77,6 +159,23 @@ fn check_account_closed(account_name: &str, ledger: &mut Ledger, span: &SpanInfo
     Ok(())
 }
 
+fn check_commodity_allowed(account_name: &str, commodity_name: &str, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
+    let mut operations = ledger.operations();
+
+    let Some(account) = operations.account(account_name)? else {
+        return Ok(());
+    };
+    // account without commodities declared in open directive can hold any commodity
+    if !account.commodities.is_empty() && !account.commodities.iter().any(|it| it.eq(commodity_name)) {
+        operations.new_error(
+            ErrorKind::CommodityNotAllowedInAccount,
+            span,
+            HashMap::of2("account_name", account_name.to_string(), "commodity_name", commodity_name.to_string()),
+        )?;
+    }
+    Ok(())
+}
+
 fn check_commodity_define(commodity_name: &str, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
     let mut operations = ledger.operations();
     let existed = operations.exist_commodity(commodity_name)?;
@@ -0,0 +1,30 @@
+use itertools::Itertools;
+use uuid::Uuid;
+use zhang_ast::{Note, SpanInfo};
+
+use crate::ledger::Ledger;
+use crate::process::DirectiveProcess;
+use crate::utils::id::FromSpan;
+use crate::{process, ZhangResult};
+
+impl DirectiveProcess for Note {
+    fn validate(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<bool> {
+        process::check_account_open_at(self.account.name(), self.date.naive_date(), ledger, span)?;
+        Ok(true)
+    }
+
+    fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
+        let mut operations = ledger.operations();
+
+        operations.insert_note(
+            &Uuid::from_span(span),
+            self.date.to_timezone_datetime(&ledger.options.timezone),
+            self.account.clone(),
+            self.comment.as_str(),
+            self.tags.iter().flatten().cloned().sorted().collect_vec(),
+            self.links.iter().flatten().cloned().sorted().collect_vec(),
+            span,
+        )?;
+        Ok(())
+    }
+}
@@ -21,6 +21,7 @@ impl DirectiveProcess for Open {
             self.account.clone(),
             AccountStatus::Open,
             self.meta.get_one("alias").map(|it| it.as_str()),
+            &self.commodities,
         )?;
 
         operations.insert_meta(MetaType::AccountMeta, self.account.name(), self.meta.clone())?;
@@ -1,5 +1,6 @@
 use zhang_ast::{Price, SpanInfo};
 
+use crate::domains::schemas::PriceSource;
 use crate::ledger::Ledger;
 use crate::process::DirectiveProcess;
 use crate::{process, ZhangResult};
@@ -19,6 +20,7 @@ impl DirectiveProcess for Price {
             &self.currency,
             &self.amount.number,
             &self.amount.currency,
+            PriceSource::Explicit,
         )?;
 
         Ok(())
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use chrono::{Days, Months, NaiveDate};
+use itertools::Itertools;
+use uuid::Uuid;
+use zhang_ast::{Date, Directive, Recurring, RecurringFrequency, SpanInfo, Spanned, Transaction, ZhangString};
+
+use crate::constants::{OCCURRENCE_DATE, RECURRING_ID};
+use crate::ledger::Ledger;
+use crate::utils::id::FromSpan;
+
+/// expand recurring directives into the transactions occurred until `recorded_until` or the `until` date of recurring.
+/// the generated transactions keep the span of recurring directive, errors of them are marked by [mark_occurrence_errors].
+pub(crate) fn expand_recurring_directives(directives: Vec<Spanned<Directive>>, recorded_until: NaiveDate) -> Vec<Spanned<Directive>> {
+    let (recurring_directives, mut directives): (Vec<Spanned<Directive>>, Vec<Spanned<Directive>>) =
+        directives.into_iter().partition(|it| matches!(it.data, Directive::Recurring(_)));
+    if recurring_directives.is_empty() {
+        return directives;
+    }
+
+    let linked_dates = linked_dates(directives.iter().map(|it| &it.data));
+    for Spanned { data, span } in recurring_directives {
+        let Directive::Recurring(recurring) = data else {
+            continue;
+        };
+        let transactions = recurring_transactions(&recurring, &span, &linked_dates, None, recorded_until);
+        directives.extend(transactions.into_iter().map(|trx| Spanned {
+            data: Directive::Transaction(trx),
+            span: span.clone(),
+        }));
+    }
+    directives
+}
+
+/// errors of generated transactions share the span of recurring directive, so the occurrence date is added into the metas of
+/// the errors raised since `from`
+pub(crate) fn mark_occurrence_errors(ledger: &Ledger, trx: &Transaction, from: usize) {
+    if trx.meta.get_one(RECURRING_ID).is_none() {
+        return;
+    }
+    let date = trx.date.naive_date().to_string();
+    let mut store = ledger.store.write().expect("poison lock detect");
+    for error in store.errors.iter_mut().skip(from) {
+        error.metas.insert(OCCURRENCE_DATE.to_owned(), date.clone());
+    }
+}
+
+/// the dates of transactions grouped by their links
+pub(crate) fn linked_dates<'a>(directives: impl Iterator<Item = &'a Directive>) -> HashMap<String, Vec<NaiveDate>> {
+    let mut linked_dates: HashMap<String, Vec<NaiveDate>> = HashMap::new();
+    for directive in directives {
+        if let Directive::Transaction(trx) = directive {
+            for link in trx.links.iter() {
+                linked_dates.entry(link.clone()).or_default().push(trx.date.naive_date());
+            }
+        }
+    }
+    linked_dates
+}
+
+/// the transactions generated by recurring directive, which occur after `after` and no later than `until`.
+/// the occurrence is skipped if any transaction sharing a link with the recurring directive is dated in its period.
+pub(crate) fn recurring_transactions(
+    recurring: &Recurring, span: &SpanInfo, linked_dates: &HashMap<String, Vec<NaiveDate>>, after: Option<NaiveDate>, until: NaiveDate,
+) -> Vec<Transaction> {
+    let recurring_id = Uuid::from_span(span);
+    let explicit_dates = recurring.links.iter().filter_map(|link| linked_dates.get(link)).flatten().collect_vec();
+
+    let start = recurring.date.naive_date();
+    let until = recurring.until.map(|it| it.min(until)).unwrap_or(until);
+    let occurrences = (0..)
+        .map_while(|nth| occurrence(recurring.frequency, start, nth))
+        .take_while(|date| date <= &until)
+        .collect_vec();
+
+    let mut transactions = vec![];
+    for (idx, date) in occurrences.iter().enumerate() {
+        if after.map(|after| date <= &after).unwrap_or(false) {
+            continue;
+        }
+        let next = occurrence(recurring.frequency, start, idx as u32 + 1);
+        let overridden = explicit_dates
+            .iter()
+            .any(|explicit_date| *explicit_date >= date && next.map(|next| **explicit_date < next).unwrap_or(true));
+        if !overridden {
+            transactions.push(generate_transaction(recurring, &recurring_id, *date));
+        }
+    }
+    transactions
+}
+
+/// the nth occurrence counted from `start`, dates beyond the end of month are clamped to the last day of month
+fn occurrence(frequency: RecurringFrequency, start: NaiveDate, nth: u32) -> Option<NaiveDate> {
+    match frequency {
+        RecurringFrequency::Daily => start.checked_add_days(Days::new(nth as u64)),
+        RecurringFrequency::Weekly => start.checked_add_days(Days::new(nth as u64 * 7)),
+        RecurringFrequency::Monthly => start.checked_add_months(Months::new(nth)),
+        RecurringFrequency::Quarterly => start.checked_add_months(Months::new(nth * 3)),
+        RecurringFrequency::Yearly => start.checked_add_months(Months::new(nth * 12)),
+    }
+}
+
+fn generate_transaction(recurring: &Recurring, recurring_id: &Uuid, date: NaiveDate) -> Transaction {
+    let date = match recurring.date {
+        Date::Date(_) => Date::Date(date),
+        Date::DateHour(datetime) => Date::DateHour(date.and_time(datetime.time())),
+        Date::Datetime(datetime) => Date::Datetime(date.and_time(datetime.time())),
+    };
+    let mut meta = recurring.meta.clone();
+    meta.insert(RECURRING_ID.to_owned(), ZhangString::quote(recurring_id.to_string()));
+    Transaction {
+        date,
+        flag: None,
+        payee: recurring.payee.clone(),
+        narration: recurring.narration.clone(),
+        tags: recurring.tags.clone(),
+        links: recurring.links.clone(),
+        postings: recurring.postings.clone(),
+        meta,
+    }
+}
@@ -9,22 +9,32 @@ use log::trace;
 use uuid::Uuid;
 use zhang_ast::amount::Amount;
 use zhang_ast::error::ErrorKind;
-use zhang_ast::{Flag, SpanInfo, Transaction};
+use zhang_ast::utils::inventory::BookingMethod;
+use zhang_ast::{Flag, PostingCost, SpanInfo, Transaction, TxnPosting};
 
-use crate::constants::TXN_ID;
-use crate::domains::schemas::MetaType;
+use crate::constants::{KEY_CAPITAL_GAINS_ACCOUNT, KEY_IMPLICIT_PRICES, RECURRING_ID, TRUE, TXN_ID};
+use crate::domains::schemas::{MetaType, PriceSource};
 use crate::domains::AccountAmount;
 use crate::ledger::Ledger;
-use crate::process::DirectiveProcess;
+use crate::process::budget::check_budget_activity;
+use crate::process::{check_account_open_at, check_commodity_allowed, DirectiveProcess};
 use crate::store::DocumentType;
 use crate::utils::hashmap::HashMapOfExt;
 use crate::utils::id::FromSpan;
 use crate::{ZhangError, ZhangResult};
 
+/// transactions generated by the same recurring directive share its span, so they are identified by the occurrence date
+fn transaction_id(trx: &Transaction, span: &SpanInfo) -> Uuid {
+    match trx.meta.get_one(RECURRING_ID) {
+        Some(recurring_id) => Uuid::from_recurring_occurrence(recurring_id.as_str(), &trx.date.naive_date()),
+        None => Uuid::from_span(span),
+    }
+}
+
 impl DirectiveProcess for Transaction {
     fn validate(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<bool> {
         let mut operations = ledger.operations();
-        let id = Uuid::from_span(span);
+        let id = transaction_id(self, span);
         let txn_error = operations.check_transaction(self)?;
         if let Some(txn_error) = txn_error {
             let meta = HashMap::of(TXN_ID, id.to_string());
@@ -44,13 +54,23 @@ impl DirectiveProcess for Transaction {
             }
         }
 
+        for account in self.postings.iter().map(|posting| posting.account.name()).unique() {
+            check_account_open_at(account, self.date.naive_date(), ledger, span)?;
+        }
+        for txn_posting in self.txn_postings() {
+            let commodity = txn_posting.units().or_else(|| txn_posting.infer_trade_amount().ok()).map(|it| it.currency);
+            if let Some(commodity) = commodity {
+                check_commodity_allowed(txn_posting.posting.account.name(), &commodity, ledger, span)?;
+            }
+        }
+
         Ok(true)
     }
 
     fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
 
-        let id = Uuid::from_span(span);
+        let id = transaction_id(self, span);
         let txn_error = operations.check_transaction(self)?;
 
         let sequence = ledger.trx_counter.fetch_add(1, Ordering::Relaxed);
@@ -68,6 +88,11 @@ impl DirectiveProcess for Transaction {
 
         let mut balance_checker = BigDecimal::zero();
         trace!("new balance checker starting with {}", &balance_checker);
+        let mut realized_gains: Vec<Amount> = vec![];
+        let implicit_prices = operations
+            .option::<String>(KEY_IMPLICIT_PRICES)?
+            .map(|value| value.to_lowercase().eq(TRUE))
+            .unwrap_or(false);
 
         for (posting_idx, txn_posting) in self.txn_postings().into_iter().enumerate() {
             let inferred_amount = txn_posting.units().unwrap_or(
@@ -98,15 +123,37 @@ impl DirectiveProcess for Transaction {
                 Amount::new(after_number, previous.commodity),
             )?;
 
+            if implicit_prices {
+                if let Some(price) = implicit_price(&txn_posting) {
+                    operations.insert_price(
+                        self.date.to_timezone_datetime(&ledger.options.timezone),
+                        &inferred_amount.currency,
+                        &price.number,
+                        &price.currency,
+                        PriceSource::Implicit,
+                    )?;
+                }
+            }
+
             // budget related
             let budgets_name = operations.get_account_budget(txn_posting.posting.account.name())?;
             for budget in budgets_name {
                 let budget_activity_amount = inferred_amount.mul(BigDecimal::from(txn_posting.posting.account.get_account_sign()));
-                operations.budget_add_activity(budget, self.date.to_timezone_datetime(&ledger.options.timezone), budget_activity_amount)?;
+                let datetime = self.date.to_timezone_datetime(&ledger.options.timezone);
+                match operations.budget_add_activity(&budget, datetime, budget_activity_amount.clone())? {
+                    Some(converted) => check_budget_activity(&mut operations, &budget, datetime, &converted, span)?,
+                    None => operations.new_error(
+                        ErrorKind::BudgetActivityPriceNotFound,
+                        span,
+                        HashMap::of2("budget_name", budget.as_str(), "amount", budget_activity_amount.to_string()),
+                    )?,
+                }
             }
 
             let amount = txn_posting.units().unwrap_or(inferred_amount);
             let lot_meta = txn_posting.lot_meta();
+            // price of total price is divided by the units, so the sign need to be removed
+            let sale_price = lot_meta.price.as_ref().map(|price| Amount::new(price.number.abs(), price.currency.clone()));
             let booking_method = operations
                 .typed_meta_value(MetaType::AccountMeta, txn_posting.account_name(), "booking_method")
                 .map_err(|kind| ZhangError::ProcessError { span: span.clone(), kind })?
@@ -114,6 +161,33 @@ impl DirectiveProcess for Transaction {
 
             // handle implicit posting cost
             if let Some(cost) = lot_meta.cost {
+                let average_booking = matches!(booking_method, BookingMethod::Average | BookingMethod::AverageOnly);
+                // lots of average booking account are merged into one lot, which is reduced whatever cost or date the posting gives
+                let cost = if average_booking && amount.number.is_negative() {
+                    PostingCost { base: None, date: None }
+                } else {
+                    cost
+                };
+                if booking_method == BookingMethod::Strict && amount.number.is_negative() {
+                    let matched_lots =
+                        operations.account_lots_by_meta(&txn_posting.account_name(), &amount.currency, &cost, txn_posting.txn.date.naive_date())?;
+                    let matched_amount: BigDecimal = matched_lots.iter().map(|lot| &lot.amount).sum();
+                    // reduction is ambiguous if multiple lots are matched, unless all of them are reduced
+                    if matched_lots.len() > 1 && matched_amount.ne(&(&amount.number).neg()) {
+                        operations.new_error(
+                            ErrorKind::AmbiguousCommodityLot,
+                            span,
+                            HashMap::of3(
+                                "account_name",
+                                txn_posting.account_name(),
+                                "commodity",
+                                amount.currency.clone(),
+                                "matched_lots",
+                                matched_lots.len().to_string(),
+                            ),
+                        )?;
+                    }
+                }
                 let mut accr_amount = amount.number.clone();
                 loop {
                     let target_lot_record = operations.account_lot_by_meta(
@@ -124,9 +198,22 @@ impl DirectiveProcess for Transaction {
                         booking_method,
                     )?;
                     let calculated = (&target_lot_record.amount).add(&accr_amount);
-                    if !calculated.is_negative() {
+                    if !calculated.is_negative() || booking_method == BookingMethod::None {
                         // the calculated amount is positive, means it is normal case
+                        // none booking allows the lot to be negative
                         operations.update_account_lot(&txn_posting.account_name(), &target_lot_record, &calculated)?;
+                        if let (true, Some(sale_price)) = (accr_amount.is_negative(), sale_price.as_ref()) {
+                            let reduced_amount = (&accr_amount).neg();
+                            let gain = operations.insert_realized_gain(
+                                &id,
+                                posting_idx,
+                                &txn_posting.account_name(),
+                                &target_lot_record,
+                                &reduced_amount,
+                                sale_price,
+                            )?;
+                            realized_gains.extend(gain);
+                        }
 
                         balance_checker.add_assign(accr_amount.mul(target_lot_record.cost.map(|it| it.number).unwrap_or(BigDecimal::one())));
                         trace!("balance checker current value is {}", &balance_checker);
@@ -152,6 +239,17 @@ impl DirectiveProcess for Transaction {
                         // if calculated amount is negative, means the matched lots record has no enough amount to do reduction
                         // then set lots record's amount to zero( delete it)
                         operations.update_account_lot(&txn_posting.account_name(), &target_lot_record, &BigDecimal::zero())?;
+                        if let Some(sale_price) = sale_price.as_ref() {
+                            let gain = operations.insert_realized_gain(
+                                &id,
+                                posting_idx,
+                                &txn_posting.account_name(),
+                                &target_lot_record,
+                                &target_lot_record.amount,
+                                sale_price,
+                            )?;
+                            realized_gains.extend(gain);
+                        }
 
                         balance_checker.add_assign(
                             (&target_lot_record.amount)
@@ -163,6 +261,9 @@ impl DirectiveProcess for Transaction {
                         accr_amount.add_assign(&target_lot_record.amount);
                     }
                 }
+                if average_booking {
+                    operations.average_account_lots(&txn_posting.account_name(), &amount.currency)?;
+                }
             } else {
                 // reduction in default lot
                 let target_lot_record = operations.default_account_lot(&txn_posting.account_name(), &amount.currency)?;
@@ -177,6 +278,53 @@ impl DirectiveProcess for Transaction {
                 trace!("balance checker current value is {}", &balance_checker);
             }
         }
+
+        // auto balance realized gains against the capital gains account, unless the transaction posts to the account explicitly
+        if let Some(capital_gains_account) = operations.option::<String>(KEY_CAPITAL_GAINS_ACCOUNT)? {
+            if !realized_gains.is_empty() && !self.has_account(&capital_gains_account) {
+                check_account_open_at(&capital_gains_account, self.date.naive_date(), ledger, span)?;
+                let gains = realized_gains
+                    .into_iter()
+                    .into_group_map_by(|gain| gain.currency.clone())
+                    .into_iter()
+                    .sorted_by(|a, b| a.0.cmp(&b.0));
+                for (gain_idx, (currency, gains)) in gains.enumerate() {
+                    let gain_number: BigDecimal = gains.into_iter().map(|it| it.number).sum();
+                    if gain_number.is_zero() {
+                        continue;
+                    }
+                    // gain is recorded as negative amount in income account
+                    let amount = Amount::new(gain_number.neg(), currency);
+                    let previous = operations
+                        .account_target_day_balance(
+                            &capital_gains_account,
+                            self.date.to_timezone_datetime(&ledger.options.timezone),
+                            &amount.currency,
+                        )?
+                        .unwrap_or(AccountAmount {
+                            number: BigDecimal::zero(),
+                            commodity: amount.currency.clone(),
+                        });
+                    let after_number = (&previous.number).add(&amount.number);
+                    operations.insert_transaction_posting(
+                        &id,
+                        self.postings.len() + gain_idx,
+                        &capital_gains_account,
+                        Some(amount.clone()),
+                        None,
+                        amount.clone(),
+                        Amount::new(previous.number, previous.commodity.clone()),
+                        Amount::new(after_number, previous.commodity),
+                    )?;
+                    let target_lot_record = operations.default_account_lot(&capital_gains_account, &amount.currency)?;
+                    operations.update_account_lot(&capital_gains_account, &target_lot_record, &(&target_lot_record.amount).add(&amount.number))?;
+
+                    balance_checker.add_assign(&amount.number);
+                    trace!("balance checker current value is {}", &balance_checker);
+                }
+            }
+        }
+
         trace!("final balance checker current value is {}, txn_error is {:?}", &balance_checker, &txn_error);
         if txn_error == Some(ErrorKind::UnbalancedTransaction) && !balance_checker.is_zero() {
             operations.new_error(ErrorKind::UnbalancedTransaction, span, HashMap::of(TXN_ID, id.to_string()))?;
@@ -198,3 +346,17 @@ impl DirectiveProcess for Transaction {
         Ok(())
     }
 }
+
+/// the per-unit price implied by the posting, the `@` price takes precedence over the cost
+fn implicit_price(txn_posting: &TxnPosting) -> Option<Amount> {
+    let units = txn_posting.posting.units.as_ref()?;
+    let lot_meta = txn_posting.lot_meta();
+    let price = lot_meta
+        .price
+        .map(|price| Amount::new(price.number.abs(), price.currency))
+        .or_else(|| lot_meta.cost.and_then(|cost| cost.base))?;
+    if price.currency.eq(&units.currency) {
+        return None;
+    }
+    Some(price)
+}
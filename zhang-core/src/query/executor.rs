@@ -0,0 +1,451 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use chrono::Datelike;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use zhang_ast::amount::Amount;
+
+use crate::domains::schemas::{MetaDomain, PriceDomain, PriceSource};
+use crate::domains::Operations;
+use crate::query::{BinaryOperator, Expr, Query, QueryResult, Table, Target, Value};
+use crate::store::{PostingDomain, Store, TransactionDomain};
+use crate::{ZhangError, ZhangResult};
+
+const AGGREGATE_FUNCTIONS: [&str; 6] = ["count", "sum", "min", "max", "first", "last"];
+const SCALAR_FUNCTIONS: [&str; 9] = ["year", "month", "day", "abs", "number", "currency", "root", "parent", "leaf"];
+
+pub fn execute(operations: &Operations, query: &Query) -> ZhangResult<QueryResult> {
+    let store = operations.read();
+    let rows = table_rows(&store, query);
+    let evaluator = Evaluator {
+        table: query.table,
+        columns: table_columns(query.table),
+    };
+
+    let targets = match &query.targets {
+        Some(targets) => targets.clone(),
+        None => evaluator
+            .columns
+            .iter()
+            .map(|column| Target {
+                expr: Expr::Column(column.to_string()),
+                name: column.to_string(),
+            })
+            .collect_vec(),
+    };
+    for target in targets.iter() {
+        evaluator.validate(&target.expr)?;
+    }
+    if let Some(filter) = &query.filter {
+        evaluator.validate(filter)?;
+        if has_aggregate(filter) {
+            return Err(ZhangError::InvalidQuery("aggregate functions are not allowed in WHERE".to_owned()));
+        }
+    }
+    let group_by = query
+        .group_by
+        .iter()
+        .map(|expr| {
+            target_position(expr, &targets)
+                .map(|idx| targets[idx].expr.clone())
+                .unwrap_or_else(|| expr.clone())
+        })
+        .collect_vec();
+    for expr in group_by.iter() {
+        evaluator.validate(expr)?;
+        if has_aggregate(expr) {
+            return Err(ZhangError::InvalidQuery("aggregate functions are not allowed in GROUP BY".to_owned()));
+        }
+    }
+    for order in query.order_by.iter() {
+        if target_position(&order.expr, &targets).is_none() {
+            evaluator.validate(&order.expr)?;
+        }
+    }
+
+    let mut filtered_rows = vec![];
+    for row in rows.iter() {
+        if let Some(filter) = &query.filter {
+            if !as_bool(evaluator.eval(filter, &[row])?)? {
+                continue;
+            }
+        }
+        filtered_rows.push(row);
+    }
+
+    // each group is turned into one row of result, aggregate functions are calculated over rows in the group
+    let groups: Vec<Vec<&Row>> = if !group_by.is_empty() {
+        let mut groups: IndexMap<Vec<Value>, Vec<&Row>> = IndexMap::new();
+        for row in filtered_rows {
+            let key = group_by.iter().map(|expr| evaluator.eval(expr, &[row])).collect::<ZhangResult<Vec<_>>>()?;
+            groups.entry(key).or_default().push(row);
+        }
+        groups.into_values().collect_vec()
+    } else if targets.iter().any(|target| has_aggregate(&target.expr)) {
+        vec![filtered_rows]
+    } else {
+        filtered_rows.into_iter().map(|row| vec![row]).collect_vec()
+    };
+
+    let mut result_rows = vec![];
+    for group in groups.iter() {
+        let values = targets
+            .iter()
+            .map(|target| evaluator.eval(&target.expr, group))
+            .collect::<ZhangResult<Vec<_>>>()?;
+        let mut order_keys = Vec::with_capacity(query.order_by.len());
+        for order in query.order_by.iter() {
+            let key = match target_position(&order.expr, &targets) {
+                Some(idx) => values[idx].clone(),
+                None => evaluator.eval(&order.expr, group)?,
+            };
+            order_keys.push(key);
+        }
+        result_rows.push((order_keys, values));
+    }
+    result_rows.sort_by(|(a, _), (b, _)| {
+        a.iter()
+            .zip(b.iter())
+            .zip(query.order_by.iter())
+            .map(|((a, b), order)| {
+                let ordering = a.compare(b).unwrap_or(Ordering::Equal);
+                if order.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut rows = result_rows.into_iter().map(|(_, values)| values).collect_vec();
+    if let Some(limit) = query.limit {
+        rows.truncate(limit);
+    }
+    Ok(QueryResult {
+        columns: targets.into_iter().map(|target| target.name).collect_vec(),
+        rows,
+    })
+}
+
+/// the target referred by position like `GROUP BY 1`, or by the name of target
+fn target_position(expr: &Expr, targets: &[Target]) -> Option<usize> {
+    match expr {
+        Expr::Literal(Value::Number(position)) => position.to_usize().filter(|it| (1..=targets.len()).contains(it)).map(|it| it - 1),
+        Expr::Column(name) => targets.iter().position(|target| target.name.eq(name)),
+        _ => None,
+    }
+}
+
+fn has_aggregate(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) | Expr::Column(_) => false,
+        Expr::Function(name, args) => AGGREGATE_FUNCTIONS.contains(&name.as_str()) || args.iter().any(has_aggregate),
+        Expr::Binary(_, lhs, rhs) => has_aggregate(lhs) || has_aggregate(rhs),
+        Expr::Not(expr) | Expr::Negative(expr) => has_aggregate(expr),
+    }
+}
+
+/// a row of table borrowed from store, the value of a column is only built when it is evaluated
+enum Row<'a> {
+    Posting(&'a PostingDomain, Option<&'a TransactionDomain>),
+    Transaction(&'a TransactionDomain),
+    /// the latest posting of account and commodity
+    Balance(&'a PostingDomain),
+    Price(&'a PriceDomain),
+    Meta(&'a MetaDomain),
+}
+
+impl Row<'_> {
+    /// the value of column, the column must be one of the columns of table
+    fn value(&self, column: &str) -> Value {
+        let optional_string = |value: &Option<String>| value.clone().map(Value::String).unwrap_or(Value::Null);
+        match (self, column) {
+            (Row::Posting(posting, _), "id") => Value::String(posting.id.to_string()),
+            (Row::Posting(posting, _), "trx_id") => Value::String(posting.trx_id.to_string()),
+            (Row::Posting(posting, _), "date") => Value::Date(posting.trx_datetime.date_naive()),
+            (Row::Posting(_, trx), "flag") => trx.map(|trx| Value::String(trx.flag.to_string())).unwrap_or(Value::Null),
+            (Row::Posting(_, trx), "payee") => trx.map(|trx| optional_string(&trx.payee)).unwrap_or(Value::Null),
+            (Row::Posting(_, trx), "narration") => trx.map(|trx| optional_string(&trx.narration)).unwrap_or(Value::Null),
+            (Row::Posting(_, trx), "tags") => Value::List(trx.map(|trx| trx.tags.clone()).unwrap_or_default()),
+            (Row::Posting(_, trx), "links") => Value::List(trx.map(|trx| trx.links.clone()).unwrap_or_default()),
+            (Row::Posting(posting, _), "account") => Value::String(posting.account.name().to_owned()),
+            (Row::Posting(posting, _), "number") => Value::Number(posting.inferred_amount.number.clone()),
+            (Row::Posting(posting, _), "currency") => Value::String(posting.inferred_amount.currency.clone()),
+            (Row::Posting(posting, _), "inferred_amount") => Value::Amount(posting.inferred_amount.clone()),
+            (Row::Posting(posting, _), "cost") => posting.cost.clone().map(Value::Amount).unwrap_or(Value::Null),
+            (Row::Posting(posting, _), "balance") => Value::Amount(posting.after_amount.clone()),
+
+            (Row::Transaction(trx), "id") => Value::String(trx.id.to_string()),
+            (Row::Transaction(trx), "date") => Value::Date(trx.datetime.date_naive()),
+            (Row::Transaction(trx), "flag") => Value::String(trx.flag.to_string()),
+            (Row::Transaction(trx), "payee") => optional_string(&trx.payee),
+            (Row::Transaction(trx), "narration") => optional_string(&trx.narration),
+            (Row::Transaction(trx), "tags") => Value::List(trx.tags.clone()),
+            (Row::Transaction(trx), "links") => Value::List(trx.links.clone()),
+
+            (Row::Balance(posting), "account") => Value::String(posting.account.name().to_owned()),
+            (Row::Balance(posting), "currency") => Value::String(posting.after_amount.currency.clone()),
+            (Row::Balance(posting), "number") => Value::Number(posting.after_amount.number.clone()),
+            (Row::Balance(posting), "balance") => Value::Amount(posting.after_amount.clone()),
+            (Row::Balance(posting), "date") => Value::Date(posting.trx_datetime.date_naive()),
+
+            (Row::Price(price), "date") => Value::Date(price.datetime.date()),
+            (Row::Price(price), "commodity") => Value::String(price.commodity.clone()),
+            (Row::Price(price), "number") => Value::Number(price.amount.clone()),
+            (Row::Price(price), "currency") => Value::String(price.target_commodity.clone()),
+            (Row::Price(price), "source") => Value::String(
+                match price.source {
+                    PriceSource::Explicit => "explicit",
+                    PriceSource::Implicit => "implicit",
+                }
+                .to_owned(),
+            ),
+
+            (Row::Meta(meta), "meta_type") => Value::String(meta.meta_type.clone()),
+            (Row::Meta(meta), "type_identifier") => Value::String(meta.type_identifier.clone()),
+            (Row::Meta(meta), "key") => Value::String(meta.key.clone()),
+            (Row::Meta(meta), "value") => Value::String(meta.value.clone()),
+            _ => Value::Null,
+        }
+    }
+}
+
+fn table_columns(table: Table) -> Vec<&'static str> {
+    match table {
+        Table::Postings => vec![
+            "id",
+            "trx_id",
+            "date",
+            "flag",
+            "payee",
+            "narration",
+            "tags",
+            "links",
+            "account",
+            "number",
+            "currency",
+            "inferred_amount",
+            "cost",
+            "balance",
+        ],
+        Table::Transactions => vec!["id", "date", "flag", "payee", "narration", "tags", "links"],
+        Table::Balances => vec!["account", "currency", "number", "balance", "date"],
+        Table::Prices => vec!["date", "commodity", "number", "currency", "source"],
+        Table::Metas => vec!["meta_type", "type_identifier", "key", "value"],
+    }
+}
+
+fn table_rows<'a>(store: &'a Store, query: &Query) -> Vec<Row<'a>> {
+    match query.table {
+        Table::Postings => store
+            .postings
+            .iter()
+            .map(|posting| Row::Posting(posting, store.transactions.get(&posting.trx_id)))
+            .collect_vec(),
+        Table::Transactions => store
+            .transactions
+            .values()
+            .sorted_by_key(|trx| (trx.datetime, trx.sequence))
+            .map(Row::Transaction)
+            .collect_vec(),
+        Table::Balances => store
+            .posting_index
+            .iter()
+            .flat_map(|(account, index)| index.iter().map(move |(currency, positions)| (account, currency, positions)))
+            .sorted_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)))
+            .filter_map(|(_, _, positions)| {
+                // positions are ordered by datetime, so the balance at a date is the one of the last posting on or before it
+                let end = match query.at {
+                    Some(at) => positions.partition_point(|position| store.postings[*position].trx_datetime.date_naive() <= at),
+                    None => positions.len(),
+                };
+                end.checked_sub(1).map(|index| Row::Balance(&store.postings[positions[index]]))
+            })
+            .collect_vec(),
+        Table::Prices => store.prices.iter().map(Row::Price).collect_vec(),
+        Table::Metas => store.metas.iter().map(Row::Meta).collect_vec(),
+    }
+}
+
+struct Evaluator {
+    table: Table,
+    columns: Vec<&'static str>,
+}
+
+impl Evaluator {
+    fn column(&self, name: &str) -> ZhangResult<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.eq(&name))
+            .ok_or_else(|| ZhangError::InvalidQuery(format!("unknown column `{}` of table `{}`", name, self.table.as_ref())))
+    }
+
+    /// check the columns and functions used in the expression
+    fn validate(&self, expr: &Expr) -> ZhangResult<()> {
+        match expr {
+            Expr::Literal(_) => Ok(()),
+            Expr::Column(name) => self.column(name).map(|_| ()),
+            Expr::Function(name, args) => {
+                if !AGGREGATE_FUNCTIONS.contains(&name.as_str()) && !SCALAR_FUNCTIONS.contains(&name.as_str()) {
+                    return Err(ZhangError::InvalidQuery(format!("unknown function `{}`", name)));
+                }
+                args.iter().try_for_each(|arg| self.validate(arg))
+            }
+            Expr::Binary(_, lhs, rhs) => {
+                self.validate(lhs)?;
+                self.validate(rhs)
+            }
+            Expr::Not(expr) | Expr::Negative(expr) => self.validate(expr),
+        }
+    }
+
+    /// evaluate the expression over rows of a group, columns outside aggregate functions take the value of the first row
+    fn eval(&self, expr: &Expr, rows: &[&Row]) -> ZhangResult<Value> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Column(name) => {
+                let idx = self.column(name)?;
+                Ok(rows.first().map(|row| row.value(self.columns[idx])).unwrap_or(Value::Null))
+            }
+            Expr::Function(name, args) if AGGREGATE_FUNCTIONS.contains(&name.as_str()) => self.aggregate(name, args, rows),
+            Expr::Function(name, args) => {
+                let args = args.iter().map(|arg| self.eval(arg, rows)).collect::<ZhangResult<Vec<_>>>()?;
+                scalar(name, args)
+            }
+            Expr::Binary(operator, lhs, rhs) => binary(*operator, self.eval(lhs, rows)?, self.eval(rhs, rows)?),
+            Expr::Not(expr) => Ok(Value::Boolean(!as_bool(self.eval(expr, rows)?)?)),
+            Expr::Negative(expr) => match self.eval(expr, rows)? {
+                Value::Null => Ok(Value::Null),
+                Value::Number(number) => Ok(Value::Number(-number)),
+                Value::Amount(amount) => Ok(Value::Amount(amount.neg())),
+                value => Err(ZhangError::InvalidQuery(format!("cannot negate `{}`", value))),
+            },
+        }
+    }
+
+    fn aggregate(&self, name: &str, args: &[Expr], rows: &[&Row]) -> ZhangResult<Value> {
+        if name == "count" && args.is_empty() {
+            return Ok(Value::Number(BigDecimal::from(rows.len() as u64)));
+        }
+        let [arg] = args else {
+            return Err(ZhangError::InvalidQuery(format!("function `{}` requires one argument", name)));
+        };
+        let mut values = vec![];
+        for row in rows {
+            let value = self.eval(arg, std::slice::from_ref(row))?;
+            if value != Value::Null {
+                values.push(value);
+            }
+        }
+        let compare = |a: &Value, b: &Value| a.compare(b).unwrap_or(Ordering::Equal);
+        Ok(match name {
+            "count" => Value::Number(BigDecimal::from(values.len() as u64)),
+            "first" => values.into_iter().next().unwrap_or(Value::Null),
+            "last" => values.into_iter().last().unwrap_or(Value::Null),
+            "min" => values.into_iter().min_by(compare).unwrap_or(Value::Null),
+            "max" => values.into_iter().max_by(compare).unwrap_or(Value::Null),
+            _ => sum(values)?,
+        })
+    }
+}
+
+/// numbers are summed into a number, amounts are summed into an inventory
+fn sum(values: Vec<Value>) -> ZhangResult<Value> {
+    let mut number: Option<BigDecimal> = None;
+    let mut inventory: BTreeMap<String, BigDecimal> = BTreeMap::new();
+    for value in values {
+        match value {
+            Value::Number(value) => number = Some(number.unwrap_or_default() + value),
+            Value::Amount(amount) => *inventory.entry(amount.currency).or_default() += amount.number,
+            Value::Inventory(amounts) => {
+                for amount in amounts {
+                    *inventory.entry(amount.currency).or_default() += amount.number;
+                }
+            }
+            value => return Err(ZhangError::InvalidQuery(format!("cannot sum `{}`", value))),
+        }
+    }
+    match (number, inventory.is_empty()) {
+        (Some(_), false) => Err(ZhangError::InvalidQuery("cannot sum numbers and amounts together".to_owned())),
+        (Some(number), true) => Ok(Value::Number(number)),
+        (None, false) => Ok(Value::Inventory(
+            inventory.into_iter().map(|(currency, number)| Amount::new(number, currency)).collect_vec(),
+        )),
+        (None, true) => Ok(Value::Null),
+    }
+}
+
+fn scalar(name: &str, args: Vec<Value>) -> ZhangResult<Value> {
+    Ok(match (name, args.as_slice()) {
+        (_, [Value::Null, ..]) => Value::Null,
+        ("year", [Value::Date(date)]) => Value::Number(BigDecimal::from(date.year())),
+        ("month", [Value::Date(date)]) => Value::Number(BigDecimal::from(date.month())),
+        ("day", [Value::Date(date)]) => Value::Number(BigDecimal::from(date.day())),
+        ("abs", [Value::Number(number)]) => Value::Number(number.abs()),
+        ("abs", [Value::Amount(amount)]) => Value::Amount(Amount::new(amount.number.abs(), amount.currency.clone())),
+        ("number", [Value::Amount(amount)]) => Value::Number(amount.number.clone()),
+        ("currency", [Value::Amount(amount)]) => Value::String(amount.currency.clone()),
+        ("root", [Value::String(account), Value::Number(depth)]) => {
+            let depth = depth.to_usize().unwrap_or_default();
+            Value::String(account.split(':').take(depth).join(":"))
+        }
+        ("parent", [Value::String(account)]) => account
+            .rsplit_once(':')
+            .map(|(parent, _)| Value::String(parent.to_owned()))
+            .unwrap_or(Value::Null),
+        ("leaf", [Value::String(account)]) => Value::String(account.rsplit(':').next().unwrap_or_default().to_owned()),
+        _ => return Err(ZhangError::InvalidQuery(format!("invalid arguments of function `{}`", name))),
+    })
+}
+
+fn as_bool(value: Value) -> ZhangResult<bool> {
+    match value {
+        Value::Null => Ok(false),
+        Value::Boolean(value) => Ok(value),
+        value => Err(ZhangError::InvalidQuery(format!("`{}` is not a boolean", value))),
+    }
+}
+
+fn binary(operator: BinaryOperator, lhs: Value, rhs: Value) -> ZhangResult<Value> {
+    let ordering = |lhs: &Value, rhs: &Value| {
+        lhs.compare(rhs)
+            .ok_or_else(|| ZhangError::InvalidQuery(format!("cannot compare `{}` with `{}`", lhs, rhs)))
+    };
+    let is_null = lhs == Value::Null || rhs == Value::Null;
+    Ok(match operator {
+        BinaryOperator::Or => Value::Boolean(as_bool(lhs)? || as_bool(rhs)?),
+        BinaryOperator::And => Value::Boolean(as_bool(lhs)? && as_bool(rhs)?),
+        BinaryOperator::Equal => Value::Boolean(lhs.compare(&rhs) == Some(Ordering::Equal)),
+        BinaryOperator::NotEqual => Value::Boolean(lhs.compare(&rhs) != Some(Ordering::Equal)),
+        BinaryOperator::Less => Value::Boolean(!is_null && ordering(&lhs, &rhs)?.is_lt()),
+        BinaryOperator::LessEqual => Value::Boolean(!is_null && ordering(&lhs, &rhs)?.is_le()),
+        BinaryOperator::Greater => Value::Boolean(!is_null && ordering(&lhs, &rhs)?.is_gt()),
+        BinaryOperator::GreaterEqual => Value::Boolean(!is_null && ordering(&lhs, &rhs)?.is_ge()),
+        BinaryOperator::In => match (lhs, rhs) {
+            (Value::String(value), Value::List(values)) => Value::Boolean(values.contains(&value)),
+            (_, Value::Null) | (Value::Null, _) => Value::Boolean(false),
+            (lhs, rhs) => return Err(ZhangError::InvalidQuery(format!("cannot check `{}` in `{}`", lhs, rhs))),
+        },
+        BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply | BinaryOperator::Divide => arithmetic(operator, lhs, rhs)?,
+    })
+}
+
+fn arithmetic(operator: BinaryOperator, lhs: Value, rhs: Value) -> ZhangResult<Value> {
+    Ok(match (operator, lhs, rhs) {
+        (_, Value::Null, _) | (_, _, Value::Null) => Value::Null,
+        (BinaryOperator::Divide, _, Value::Number(rhs)) if rhs.is_zero() => Value::Null,
+        (BinaryOperator::Add, Value::Number(lhs), Value::Number(rhs)) => Value::Number(lhs + rhs),
+        (BinaryOperator::Subtract, Value::Number(lhs), Value::Number(rhs)) => Value::Number(lhs - rhs),
+        (BinaryOperator::Multiply, Value::Number(lhs), Value::Number(rhs)) => Value::Number(lhs * rhs),
+        (BinaryOperator::Divide, Value::Number(lhs), Value::Number(rhs)) => Value::Number(lhs / rhs),
+        (BinaryOperator::Multiply, Value::Amount(amount), Value::Number(number)) | (BinaryOperator::Multiply, Value::Number(number), Value::Amount(amount)) => {
+            Value::Amount(Amount::new(amount.number * number, amount.currency))
+        }
+        (BinaryOperator::Divide, Value::Amount(amount), Value::Number(number)) => Value::Amount(Amount::new(amount.number / number, amount.currency)),
+        (operator, lhs, rhs) => return Err(ZhangError::InvalidQuery(format!("cannot apply {:?} on `{}` and `{}`", operator, lhs, rhs))),
+    })
+}
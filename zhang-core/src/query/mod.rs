@@ -0,0 +1,337 @@
+//! a small SQL-like query language over the store, like
+//! ```sql
+//! SELECT account, sum(inferred_amount) WHERE date >= 2024-01-01 GROUP BY account ORDER BY account
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use itertools::Itertools;
+use serde::Serialize;
+use strum::{AsRefStr, EnumString};
+use zhang_ast::amount::Amount;
+
+pub use crate::query::executor::execute;
+pub use crate::query::parser::parse;
+
+mod executor;
+#[allow(clippy::upper_case_acronyms)]
+mod parser;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// `None` means all columns of the table are selected
+    pub targets: Option<Vec<Target>>,
+    pub table: Table,
+    /// the date the balances are taken at, given by `FROM balances AT <date>`
+    pub at: Option<NaiveDate>,
+    pub filter: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub expr: Expr,
+    /// the alias, or the text of expression if alias is not given
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub expr: Expr,
+    pub descending: bool,
+}
+
+/// the tables can be queried, `postings` is used if `FROM` is not given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, AsRefStr, EnumString)]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+pub enum Table {
+    #[default]
+    Postings,
+    Transactions,
+    /// the latest balance of each account and commodity, or the balance at the end of the date given by `AT`
+    Balances,
+    Prices,
+    Metas,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Column(String),
+    /// `count(*)` is represented as `count` without arguments
+    Function(String, Vec<Expr>),
+    Binary(BinaryOperator, Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Negative(Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    /// `value IN list`, e.g. `'travel' IN tags`
+    In,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Number(BigDecimal),
+    String(String),
+    Date(NaiveDate),
+    Amount(Amount),
+    /// the sum of amounts, one amount per commodity ordered by commodity
+    Inventory(Vec<Amount>),
+    List(Vec<String>),
+}
+
+impl Value {
+    /// compare values of the same type, null is less than any other value
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Null, Value::Null) => Some(Ordering::Equal),
+            (Value::Null, _) => Some(Ordering::Less),
+            (_, Value::Null) => Some(Ordering::Greater),
+            (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+            (Value::Number(a), Value::Number(b)) => Some(a.cmp(b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+            (Value::Amount(a), Value::Amount(b)) if a.currency == b.currency => Some(a.number.cmp(&b.number)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Number(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Date(value) => write!(f, "{}", value.format("%Y-%m-%d")),
+            Value::Amount(value) => write!(f, "{}", value),
+            Value::Inventory(amounts) => write!(f, "{}", amounts.iter().join(", ")),
+            Value::List(values) => write!(f, "{}", values.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use indoc::indoc;
+    use zhang_ast::amount::Amount;
+
+    use crate::query::{parse, BinaryOperator, Expr, QueryResult, Table, Value};
+    use crate::test::load_from_text;
+    use crate::ZhangError;
+
+    fn query(query: &str) -> QueryResult {
+        let ledger = load_from_text(indoc! {r#"
+            1970-01-01 open Assets:Bank
+            1970-01-01 open Expenses:Food:Lunch
+            1970-01-01 open Expenses:Food:Dinner
+            1970-01-01 open Expenses:Travel
+
+            2023-12-31 "KFC" "Lunch"
+              Expenses:Food:Lunch 5 CNY
+              Assets:Bank
+
+            2024-01-05 "KFC" "Lunch"
+              Expenses:Food:Lunch 10 CNY
+              Assets:Bank
+
+            2024-01-06 "Dinner" #family
+              Expenses:Food:Dinner 20 CNY
+              Assets:Bank
+
+            2024-02-01 "Flight" #travel
+              Expenses:Travel 100 USD
+              Assets:Bank
+        "#});
+        ledger.operations().query(query).unwrap()
+    }
+
+    fn number(value: &str) -> Value {
+        Value::Number(BigDecimal::from_str(value).unwrap())
+    }
+    fn amount(number: i32, currency: &str) -> Amount {
+        Amount::new(BigDecimal::from(number), currency)
+    }
+
+    #[test]
+    fn should_parse_query() {
+        let query = parse("select account, sum(inferred_amount) as total from postings where date >= 2024-01-01 and not payee = 'KFC' group by 1 order by total desc limit 5;").unwrap();
+        let targets = query.targets.unwrap();
+        assert_eq!(targets[0].name, "account");
+        assert_eq!(targets[1].name, "total");
+        assert_eq!(
+            targets[1].expr,
+            Expr::Function("sum".to_owned(), vec![Expr::Column("inferred_amount".to_owned())])
+        );
+        assert_eq!(query.table, Table::Postings);
+        assert!(matches!(query.filter, Some(Expr::Binary(BinaryOperator::And, _, _))));
+        assert_eq!(query.group_by, vec![Expr::Literal(number("1"))]);
+        assert!(query.order_by[0].descending);
+        assert_eq!(query.limit, Some(5));
+    }
+
+    #[test]
+    fn should_respect_operator_precedence() {
+        let query = parse("SELECT 1 + 2 * 3 = 7 OR false").unwrap();
+        let expected = Expr::Binary(
+            BinaryOperator::Or,
+            Box::new(Expr::Binary(
+                BinaryOperator::Equal,
+                Box::new(Expr::Binary(
+                    BinaryOperator::Add,
+                    Box::new(Expr::Literal(number("1"))),
+                    Box::new(Expr::Binary(
+                        BinaryOperator::Multiply,
+                        Box::new(Expr::Literal(number("2"))),
+                        Box::new(Expr::Literal(number("3"))),
+                    )),
+                )),
+                Box::new(Expr::Literal(number("7"))),
+            )),
+            Box::new(Expr::Literal(Value::Boolean(false))),
+        );
+        assert_eq!(query.targets.unwrap()[0].expr, expected);
+    }
+
+    #[test]
+    fn should_reject_invalid_query() {
+        assert!(matches!(parse("SELECT FROM postings"), Err(ZhangError::InvalidQuery(_))));
+        assert!(matches!(parse("SELECT * FROM unknown"), Err(ZhangError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn should_select_postings_with_filter() {
+        let result = query("SELECT date, account, number WHERE account = 'Expenses:Food:Lunch' AND date >= 2024-01-01");
+        assert_eq!(result.columns, vec!["date", "account", "number"]);
+        assert_eq!(
+            result.rows,
+            vec![vec![
+                Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 5).unwrap()),
+                Value::String("Expenses:Food:Lunch".to_owned()),
+                number("10"),
+            ]]
+        );
+    }
+
+    #[test]
+    fn should_group_and_sum_amounts() {
+        let result = query("SELECT root(account, 2) AS category, sum(inferred_amount), count(*) GROUP BY category ORDER BY category");
+        assert_eq!(result.columns, vec!["category", "sum(inferred_amount)", "count(*)"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![
+                    Value::String("Assets:Bank".to_owned()),
+                    Value::Inventory(vec![amount(-35, "CNY"), amount(-100, "USD")]),
+                    number("4"),
+                ],
+                vec![
+                    Value::String("Expenses:Food".to_owned()),
+                    Value::Inventory(vec![amount(35, "CNY")]),
+                    number("3"),
+                ],
+                vec![
+                    Value::String("Expenses:Travel".to_owned()),
+                    Value::Inventory(vec![amount(100, "USD")]),
+                    number("1"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn should_aggregate_without_group_by() {
+        let result = query("SELECT sum(number), max(date) WHERE currency = 'CNY' AND number > 0");
+        assert_eq!(
+            result.rows,
+            vec![vec![number("35"), Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 6).unwrap())]]
+        );
+    }
+
+    #[test]
+    fn should_filter_by_tags_and_order_with_limit() {
+        let result = query("SELECT payee FROM transactions WHERE 'travel' IN tags OR 'family' IN tags ORDER BY date DESC LIMIT 1");
+        assert_eq!(result.rows, vec![vec![Value::String("Flight".to_owned())]]);
+    }
+
+    #[test]
+    fn should_query_latest_balances() {
+        let result = query("SELECT account, balance FROM balances WHERE account = 'Assets:Bank' ORDER BY currency");
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![Value::String("Assets:Bank".to_owned()), Value::Amount(amount(-35, "CNY"))],
+                vec![Value::String("Assets:Bank".to_owned()), Value::Amount(amount(-100, "USD"))],
+            ]
+        );
+    }
+
+    #[test]
+    fn should_query_balances_at_date() {
+        let result = query("SELECT account, balance, date FROM balances AT 2024-01-05 WHERE account = 'Assets:Bank' ORDER BY currency");
+        assert_eq!(
+            result.rows,
+            vec![vec![
+                Value::String("Assets:Bank".to_owned()),
+                Value::Amount(amount(-15, "CNY")),
+                Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 5).unwrap()),
+            ]]
+        );
+        let result = query("SELECT account FROM balances AT 2023-12-31 ORDER BY account");
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![Value::String("Assets:Bank".to_owned())],
+                vec![Value::String("Expenses:Food:Lunch".to_owned())],
+            ]
+        );
+        assert_eq!(
+            parse("SELECT * FROM balances at 2024-01-05").unwrap().at,
+            chrono::NaiveDate::from_ymd_opt(2024, 1, 5)
+        );
+        assert!(matches!(parse("SELECT * FROM postings AT 2024-01-05"), Err(ZhangError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn should_reject_unknown_column_and_aggregate_in_where() {
+        let ledger = load_from_text("1970-01-01 open Assets:Bank");
+        assert!(matches!(ledger.operations().query("SELECT unknown"), Err(ZhangError::InvalidQuery(_))));
+        assert!(matches!(ledger.operations().query("SELECT unknown(account)"), Err(ZhangError::InvalidQuery(_))));
+        assert!(matches!(
+            ledger.operations().query("SELECT account WHERE count(*) > 1"),
+            Err(ZhangError::InvalidQuery(_))
+        ));
+    }
+}
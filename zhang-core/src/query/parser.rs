@@ -0,0 +1,226 @@
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use once_cell::sync::OnceCell;
+use pest::iterators::Pairs;
+use pest::pratt_parser::PrattParser;
+use pest_consume::{match_nodes, Error, Parser};
+
+use crate::query::{BinaryOperator, Expr, OrderBy, Query, Table, Target, Value};
+use crate::{ZhangError, ZhangResult};
+
+type Result<T> = std::result::Result<T, Error<Rule>>;
+type Node<'i> = pest_consume::Node<'i, Rule, ()>;
+
+#[derive(Parser)]
+#[grammar = "query/query.pest"]
+struct QueryParser;
+
+/// Construct a global [PrattParser] to handle query expressions, from the lowest precedence to the highest.
+fn pratt_expr_parser() -> &'static PrattParser<Rule> {
+    static PARSER: OnceCell<PrattParser<Rule>> = OnceCell::new();
+    PARSER.get_or_init(|| {
+        use pest::pratt_parser::Assoc::*;
+        use pest::pratt_parser::Op;
+        use Rule::*;
+        PrattParser::new()
+            .op(Op::infix(or, Left))
+            .op(Op::infix(and, Left))
+            .op(Op::prefix(not))
+            .op(Op::infix(equal, Left)
+                | Op::infix(not_equal, Left)
+                | Op::infix(less, Left)
+                | Op::infix(less_equal, Left)
+                | Op::infix(greater, Left)
+                | Op::infix(greater_equal, Left)
+                | Op::infix(in_list, Left))
+            .op(Op::infix(add, Left) | Op::infix(subtract, Left))
+            .op(Op::infix(multiply, Left) | Op::infix(divide, Left))
+            .op(Op::prefix(negative))
+    })
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> Result<Expr> {
+    pratt_expr_parser()
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::expr => parse_expr(primary.into_inner()),
+            Rule::function => QueryParser::function(Node::new(primary)),
+            Rule::column => QueryParser::column(Node::new(primary)),
+            Rule::date => QueryParser::date(Node::new(primary)).map(Expr::Literal),
+            Rule::number => QueryParser::number(Node::new(primary)).map(Expr::Literal),
+            Rule::string => QueryParser::string(Node::new(primary)).map(Expr::Literal),
+            Rule::boolean => QueryParser::boolean(Node::new(primary)).map(Expr::Literal),
+            Rule::null => Ok(Expr::Literal(Value::Null)),
+            rule => unreachable!("Unexpected query expr {:?}", rule),
+        })
+        .map_infix(|lhs, op, rhs| {
+            let operator = match op.as_rule() {
+                Rule::or => BinaryOperator::Or,
+                Rule::and => BinaryOperator::And,
+                Rule::equal => BinaryOperator::Equal,
+                Rule::not_equal => BinaryOperator::NotEqual,
+                Rule::less => BinaryOperator::Less,
+                Rule::less_equal => BinaryOperator::LessEqual,
+                Rule::greater => BinaryOperator::Greater,
+                Rule::greater_equal => BinaryOperator::GreaterEqual,
+                Rule::in_list => BinaryOperator::In,
+                Rule::add => BinaryOperator::Add,
+                Rule::subtract => BinaryOperator::Subtract,
+                Rule::multiply => BinaryOperator::Multiply,
+                Rule::divide => BinaryOperator::Divide,
+                rule => unreachable!("Unexpected infix operation {:?}", rule),
+            };
+            Ok(Expr::Binary(operator, Box::new(lhs?), Box::new(rhs?)))
+        })
+        .map_prefix(|op, rhs| match op.as_rule() {
+            Rule::not => Ok(Expr::Not(Box::new(rhs?))),
+            Rule::negative => Ok(Expr::Negative(Box::new(rhs?))),
+            rule => unreachable!("Unexpected prefix operation {:?}", rule),
+        })
+        .parse(pairs)
+}
+
+#[pest_consume::parser]
+impl QueryParser {
+    fn expr(input: Node) -> Result<Expr> {
+        parse_expr(input.into_pair().into_inner())
+    }
+    fn identifier(input: Node) -> Result<String> {
+        Ok(input.as_str().to_owned())
+    }
+    fn column(input: Node) -> Result<Expr> {
+        let name = match_nodes!(input.into_children();
+            [identifier(name)] => name,
+        );
+        Ok(Expr::Column(name.to_lowercase()))
+    }
+    fn count_all(_input: Node) -> Result<()> {
+        Ok(())
+    }
+    fn function(input: Node) -> Result<Expr> {
+        let (name, args) = match_nodes!(input.into_children();
+            [identifier(name), count_all(_)] => (name, vec![]),
+            [identifier(name), expr(args)..] => (name, args.collect()),
+        );
+        Ok(Expr::Function(name.to_lowercase(), args))
+    }
+    fn date(input: Node) -> Result<Value> {
+        NaiveDate::parse_from_str(input.as_str(), "%Y-%m-%d")
+            .map(Value::Date)
+            .map_err(|e| input.error(e))
+    }
+    fn number(input: Node) -> Result<Value> {
+        BigDecimal::from_str(input.as_str()).map(Value::Number).map_err(|e| input.error(e))
+    }
+    fn integer(input: Node) -> Result<usize> {
+        input.as_str().parse::<usize>().map_err(|e| input.error(e))
+    }
+    fn string(input: Node) -> Result<Value> {
+        let quoted = input.as_str();
+        Ok(Value::String(quoted[1..quoted.len() - 1].to_owned()))
+    }
+    fn boolean(input: Node) -> Result<Value> {
+        Ok(Value::Boolean(input.as_str().eq_ignore_ascii_case("true")))
+    }
+
+    fn target(input: Node) -> Result<Target> {
+        let text = input.children().next().map(|it| it.as_str().trim().to_owned()).unwrap_or_default();
+        let (expr, alias) = match_nodes!(input.into_children();
+            [expr(expr)] => (expr, None),
+            [expr(expr), identifier(alias)] => (expr, Some(alias)),
+        );
+        Ok(Target {
+            expr,
+            name: alias.unwrap_or(text),
+        })
+    }
+    fn wildcard(_input: Node) -> Result<()> {
+        Ok(())
+    }
+    fn select_clause(input: Node) -> Result<Option<Vec<Target>>> {
+        Ok(match_nodes!(input.into_children();
+            [wildcard(_)] => None,
+            [target(targets)..] => Some(targets.collect()),
+        ))
+    }
+    fn at(_input: Node) -> Result<()> {
+        Ok(())
+    }
+    fn from_clause(input: Node) -> Result<(Table, Option<NaiveDate>)> {
+        let (name, at) = match_nodes!(input.children();
+            [identifier(name)] => (name, None),
+            [identifier(name), at(_), date(at)] => (name, Some(at)),
+        );
+        let table = Table::from_str(&name).map_err(|_| input.error(format!("unknown table `{}`", name)))?;
+        let at = match at {
+            Some(Value::Date(date)) if table == Table::Balances => Some(date),
+            Some(_) => return Err(input.error(format!("table `{}` cannot be queried at a date", name))),
+            None => None,
+        };
+        Ok((table, at))
+    }
+    fn where_clause(input: Node) -> Result<Expr> {
+        Ok(match_nodes!(input.into_children();
+            [expr(expr)] => expr,
+        ))
+    }
+    fn group_clause(input: Node) -> Result<Vec<Expr>> {
+        Ok(match_nodes!(input.into_children();
+            [expr(exprs)..] => exprs.collect(),
+        ))
+    }
+    fn asc(_input: Node) -> Result<bool> {
+        Ok(false)
+    }
+    fn desc(_input: Node) -> Result<bool> {
+        Ok(true)
+    }
+    fn order_item(input: Node) -> Result<OrderBy> {
+        Ok(match_nodes!(input.into_children();
+            [expr(expr)] => OrderBy { expr, descending: false },
+            [expr(expr), asc(descending)] => OrderBy { expr, descending },
+            [expr(expr), desc(descending)] => OrderBy { expr, descending },
+        ))
+    }
+    fn order_clause(input: Node) -> Result<Vec<OrderBy>> {
+        Ok(match_nodes!(input.into_children();
+            [order_item(items)..] => items.collect(),
+        ))
+    }
+    fn limit_clause(input: Node) -> Result<usize> {
+        Ok(match_nodes!(input.into_children();
+            [integer(limit)] => limit,
+        ))
+    }
+
+    fn query(input: Node) -> Result<Query> {
+        let mut query = Query {
+            targets: None,
+            table: Table::default(),
+            at: None,
+            filter: None,
+            group_by: vec![],
+            order_by: vec![],
+            limit: None,
+        };
+        for clause in input.into_children() {
+            match clause.as_rule() {
+                Rule::select_clause => query.targets = QueryParser::select_clause(clause)?,
+                Rule::from_clause => (query.table, query.at) = QueryParser::from_clause(clause)?,
+                Rule::where_clause => query.filter = Some(QueryParser::where_clause(clause)?),
+                Rule::group_clause => query.group_by = QueryParser::group_clause(clause)?,
+                Rule::order_clause => query.order_by = QueryParser::order_clause(clause)?,
+                Rule::limit_clause => query.limit = Some(QueryParser::limit_clause(clause)?),
+                _ => {}
+            }
+        }
+        Ok(query)
+    }
+}
+
+pub fn parse(query: &str) -> ZhangResult<Query> {
+    let inputs = QueryParser::parse(Rule::query, query).map_err(|e| ZhangError::InvalidQuery(e.to_string()))?;
+    let input = inputs.single().map_err(|e| ZhangError::InvalidQuery(e.to_string()))?;
+    QueryParser::query(input).map_err(|e| ZhangError::InvalidQuery(e.to_string()))
+}
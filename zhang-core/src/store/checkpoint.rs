@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono_tz::Tz;
+use indexmap::IndexMap;
+
+use crate::domains::schemas::{AccountDomain, CommodityDomain};
+use crate::store::{BudgetDomain, CommodityLotRecord, Store};
+
+/// checkpoints of store taken while processing, ordered by position. reloading restores the store at the latest checkpoint
+/// before the first changed directive and replays the directives from there.
+///
+/// a checkpoint does not copy the store. collections which are only appended while processing are kept as their lengths,
+/// and the states updated in place, which are keyed by account, commodity or budget, are kept as the entries changed
+/// since the previous checkpoint.
+#[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Checkpoints {
+    checkpoints: Vec<Checkpoint>,
+    /// the keyed states at the latest checkpoint, which the next checkpoint is compared with
+    #[serde(skip)]
+    latest: KeyedStates,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Checkpoint {
+    /// the position of the directive in processed directives, which the checkpoint is taken before
+    position: usize,
+    /// transactions from this sequence are processed after the checkpoint
+    trx_counter: i32,
+    lengths: Lengths,
+    changed: KeyedStates,
+}
+
+/// the lengths of collections which are only appended while processing
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Lengths {
+    postings: usize,
+    prices: usize,
+    realized_gains: usize,
+    documents: usize,
+    notes: usize,
+    metas: usize,
+    errors: usize,
+    events: HashMap<String, usize>,
+    customs: HashMap<String, usize>,
+}
+
+#[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
+struct KeyedStates {
+    accounts: IndexMap<String, AccountDomain>,
+    commodities: IndexMap<String, CommodityDomain>,
+    commodity_lots: IndexMap<String, Vec<CommodityLotRecord>>,
+    /// budgets of changed checkpoints only have the details of changed intervals
+    budgets: IndexMap<String, BudgetDomain>,
+}
+
+impl Checkpoints {
+    #[cfg(test)]
+    pub(crate) fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.checkpoints.iter().map(|checkpoint| checkpoint.position)
+    }
+
+    /// take a checkpoint before processing the directive at `position`, unless there is one already
+    pub(crate) fn take(&mut self, position: usize, store: &Store, trx_counter: i32) {
+        if self.checkpoints.last().is_some_and(|checkpoint| checkpoint.position >= position) {
+            return;
+        }
+        let changed = self.latest.update(store);
+        self.checkpoints.push(Checkpoint {
+            position,
+            trx_counter,
+            lengths: Lengths::new(store),
+            changed,
+        });
+    }
+
+    /// drop the checkpoints after `position`
+    pub(crate) fn truncate(&mut self, position: usize) {
+        let len = self.checkpoints.partition_point(|checkpoint| checkpoint.position <= position);
+        if len < self.checkpoints.len() {
+            self.checkpoints.truncate(len);
+            self.rebuild_latest();
+        }
+    }
+
+    /// the store at the latest checkpoint at or before `position`, together with the position and transaction sequence of the checkpoint.
+    /// `store` must be processed from the same directives before `position`. the checkpoints after the restored one are dropped
+    pub(crate) fn restore(&mut self, position: usize, store: &Store) -> Option<(usize, i32, Store)> {
+        let len = self.checkpoints.partition_point(|checkpoint| checkpoint.position <= position);
+        let checkpoint = self.checkpoints.get(len.checked_sub(1)?)?.clone();
+        self.truncate(checkpoint.position);
+
+        let lengths = &checkpoint.lengths;
+        let states = self.latest.clone();
+        let mut restored = Store {
+            options: store.options.clone(),
+            accounts: states.accounts.into_iter().collect(),
+            commodities: states.commodities,
+            transactions: store
+                .transactions
+                .iter()
+                .filter(|(_, trx)| trx.sequence < checkpoint.trx_counter)
+                .map(|(id, trx)| (*id, trx.clone()))
+                .collect(),
+            postings: store.postings[..lengths.postings].to_vec(),
+            posting_index: HashMap::new(),
+            prices: store.prices[..lengths.prices].to_vec(),
+            price_index: HashMap::new(),
+            budgets: states.budgets.into_iter().collect(),
+            commodity_lots: states.commodity_lots.into_iter().collect(),
+            realized_gains: store.realized_gains[..lengths.realized_gains].to_vec(),
+            documents: store.documents[..lengths.documents].to_vec(),
+            notes: store.notes[..lengths.notes].to_vec(),
+            events: truncate_grouped(&store.events, &lengths.events),
+            customs: truncate_grouped(&store.customs, &lengths.customs),
+            metas: store.metas[..lengths.metas].to_vec(),
+            errors: store.errors[..lengths.errors].to_vec(),
+        };
+        restored.rebuild_indexes();
+        Some((checkpoint.position, checkpoint.trx_counter, restored))
+    }
+
+    /// rebuild the keyed states at the latest checkpoint, which are skipped in serialization
+    pub(crate) fn rebuild_latest(&mut self) {
+        let mut latest = KeyedStates::default();
+        for checkpoint in self.checkpoints.iter() {
+            latest.merge(&checkpoint.changed);
+        }
+        self.latest = latest;
+    }
+
+    /// convert the datetimes of checkpoints into the timezone, see [Store::convert_timezone]
+    pub(crate) fn convert_timezone(&mut self, timezone: &Tz) {
+        self.checkpoints
+            .iter_mut()
+            .flat_map(|checkpoint| checkpoint.changed.budgets.values_mut())
+            .flat_map(|budget| budget.detail.values_mut())
+            .flat_map(|detail| detail.events.iter_mut())
+            .for_each(|event| event.datetime = event.datetime.with_timezone(timezone));
+    }
+}
+
+impl Lengths {
+    fn new(store: &Store) -> Self {
+        Self {
+            postings: store.postings.len(),
+            prices: store.prices.len(),
+            realized_gains: store.realized_gains.len(),
+            documents: store.documents.len(),
+            notes: store.notes.len(),
+            metas: store.metas.len(),
+            errors: store.errors.len(),
+            events: grouped_lengths(&store.events),
+            customs: grouped_lengths(&store.customs),
+        }
+    }
+}
+
+impl KeyedStates {
+    /// the entries of store which are changed since the states, the states are updated to the store
+    fn update(&mut self, store: &Store) -> KeyedStates {
+        let mut changed = KeyedStates {
+            accounts: changed_entries(&store.accounts, &self.accounts),
+            commodities: changed_entries(&store.commodities, &self.commodities),
+            commodity_lots: changed_entries(&store.commodity_lots, &self.commodity_lots),
+            budgets: IndexMap::new(),
+        };
+        for (name, budget) in store.budgets.iter() {
+            let latest = self.budgets.get(name);
+            let detail: BTreeMap<_, _> = budget
+                .detail
+                .iter()
+                .filter(|(interval, detail)| latest.and_then(|latest| latest.detail.get(*interval)) != Some(*detail))
+                .map(|(interval, detail)| (*interval, detail.clone()))
+                .collect();
+            let unchanged = detail.is_empty() && latest.is_some_and(|latest| without_detail(latest) == without_detail(budget));
+            if !unchanged {
+                changed.budgets.insert(
+                    name.clone(),
+                    BudgetDomain {
+                        detail,
+                        ..without_detail(budget)
+                    },
+                );
+            }
+        }
+        self.merge(&changed);
+        changed
+    }
+
+    fn merge(&mut self, changed: &KeyedStates) {
+        self.accounts.extend(changed.accounts.clone());
+        self.commodities.extend(changed.commodities.clone());
+        self.commodity_lots.extend(changed.commodity_lots.clone());
+        for (name, budget) in changed.budgets.iter() {
+            let merged = self.budgets.entry(name.clone()).or_insert_with(|| without_detail(budget));
+            let mut detail = std::mem::take(&mut merged.detail);
+            detail.extend(budget.detail.clone());
+            *merged = BudgetDomain {
+                detail,
+                ..without_detail(budget)
+            };
+        }
+    }
+}
+
+/// the entries which are new or different from the ones in `latest`
+fn changed_entries<'a, V: Clone + PartialEq + 'a>(current: impl IntoIterator<Item = (&'a String, &'a V)>, latest: &IndexMap<String, V>) -> IndexMap<String, V> {
+    current
+        .into_iter()
+        .filter(|(key, value)| latest.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+fn without_detail(budget: &BudgetDomain) -> BudgetDomain {
+    BudgetDomain {
+        name: budget.name.clone(),
+        alias: budget.alias.clone(),
+        category: budget.category.clone(),
+        closed: budget.closed,
+        rollover: budget.rollover,
+        period: budget.period,
+        targets: budget.targets.clone(),
+        detail: BTreeMap::new(),
+        commodity: budget.commodity.clone(),
+    }
+}
+
+fn grouped_lengths<T>(grouped: &HashMap<String, Vec<T>>) -> HashMap<String, usize> {
+    grouped.iter().map(|(key, values)| (key.clone(), values.len())).collect()
+}
+
+fn truncate_grouped<T: Clone>(grouped: &HashMap<String, Vec<T>>, lengths: &HashMap<String, usize>) -> HashMap<String, Vec<T>> {
+    lengths
+        .iter()
+        .filter_map(|(key, len)| grouped.get(key).map(|values| (key.clone(), values[..*len].to_vec())))
+        .collect()
+}
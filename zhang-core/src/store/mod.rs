@@ -1,41 +1,183 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, NaiveDate};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
 use chrono_tz::Tz;
 use indexmap::IndexMap;
+use itertools::Itertools;
 use uuid::Uuid;
 use zhang_ast::amount::Amount;
-use zhang_ast::{Account, Flag, SpanInfo};
+use zhang_ast::{Account, BudgetPeriod, Currency, Flag, SpanInfo};
 
 use crate::domains::schemas::{AccountDomain, CommodityDomain, ErrorDomain, MetaDomain, PriceDomain};
 
-#[derive(Default, serde::Serialize)]
+pub(crate) mod checkpoint;
+
+#[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Store {
     pub options: HashMap<String, String>,
     pub accounts: HashMap<String, AccountDomain>,
     pub commodities: IndexMap<String, CommodityDomain>,
     pub transactions: HashMap<Uuid, TransactionDomain>,
     pub postings: Vec<PostingDomain>,
+    // by account and commodity, positions in `postings` ordered by datetime
+    #[serde(skip)]
+    pub posting_index: HashMap<String, HashMap<Currency, Vec<usize>>>,
 
     pub prices: Vec<PriceDomain>,
+    // by commodity and target commodity, positions in `prices` keyed by datetime
+    #[serde(skip)]
+    pub price_index: HashMap<(Currency, Currency), BTreeMap<NaiveDateTime, usize>>,
 
     pub budgets: HashMap<String, BudgetDomain>,
 
     // by account
     pub commodity_lots: HashMap<String, Vec<CommodityLotRecord>>,
 
+    pub realized_gains: Vec<RealizedGainDomain>,
+
     pub documents: Vec<DocumentDomain>,
 
+    pub notes: Vec<NoteDomain>,
+
+    // by event type, ordered by datetime
+    pub events: HashMap<String, Vec<EventDomain>>,
+
+    // by custom type
+    pub customs: HashMap<String, Vec<CustomDomain>>,
+
     pub metas: Vec<MetaDomain>,
 
     pub errors: Vec<ErrorDomain>,
 }
 
-#[derive(Clone, serde::Serialize, Debug)]
+impl Store {
+    /// convert all datetimes of store into the timezone, used after the store is deserialized in UTC
+    pub(crate) fn convert_timezone(&mut self, timezone: &Tz) {
+        let convert = |datetime: &mut DateTime<Tz>| *datetime = datetime.with_timezone(timezone);
+        for trx in self.transactions.values_mut() {
+            convert(&mut trx.datetime);
+            trx.postings.iter_mut().for_each(|posting| convert(&mut posting.trx_datetime));
+        }
+        self.postings.iter_mut().for_each(|posting| convert(&mut posting.trx_datetime));
+        self.realized_gains.iter_mut().for_each(|gain| convert(&mut gain.datetime));
+        self.documents.iter_mut().for_each(|document| convert(&mut document.datetime));
+        self.notes.iter_mut().for_each(|note| convert(&mut note.datetime));
+        self.events.values_mut().flatten().for_each(|event| convert(&mut event.datetime));
+        self.customs.values_mut().flatten().for_each(|custom| convert(&mut custom.datetime));
+        self.budgets
+            .values_mut()
+            .flat_map(|budget| budget.detail.values_mut())
+            .flat_map(|detail| detail.events.iter_mut())
+            .for_each(|event| convert(&mut event.datetime));
+    }
+
+    /// push the posting and keep it ordered by datetime in the index of its account and commodity.
+    /// postings with the same datetime are kept in inserting order
+    pub(crate) fn insert_posting(&mut self, posting: PostingDomain) {
+        self.postings.push(posting);
+        self.index_posting(self.postings.len() - 1);
+    }
+
+    /// push the price and index it by datetime, the later one wins if prices of the same pair share the datetime
+    pub(crate) fn insert_price(&mut self, price: PriceDomain) {
+        self.prices.push(price);
+        self.index_price(self.prices.len() - 1);
+    }
+
+    /// rebuild the posting and price indexes, which are skipped in serialization
+    pub(crate) fn rebuild_indexes(&mut self) {
+        self.posting_index.clear();
+        for position in 0..self.postings.len() {
+            self.index_posting(position);
+        }
+        self.price_index.clear();
+        for position in 0..self.prices.len() {
+            self.index_price(position);
+        }
+    }
+
+    fn index_price(&mut self, position: usize) {
+        let price = &self.prices[position];
+        self.price_index
+            .entry((price.commodity.clone(), price.target_commodity.clone()))
+            .or_default()
+            .insert(price.datetime, position);
+    }
+
+    /// the latest price of each commodity pair at or before the datetime
+    pub fn latest_prices(&self, datetime: NaiveDateTime) -> impl Iterator<Item = &PriceDomain> {
+        self.price_index
+            .values()
+            .filter_map(move |dated| dated.range(..=datetime).next_back())
+            .map(|(_, position)| &self.prices[*position])
+    }
+
+    fn index_posting(&mut self, position: usize) {
+        let postings = &self.postings;
+        let posting = &postings[position];
+        let index = self
+            .posting_index
+            .entry(posting.account.name().to_owned())
+            .or_default()
+            .entry(posting.after_amount.currency.clone())
+            .or_default();
+        let insert_at = index.partition_point(|idx| postings[*idx].trx_datetime <= posting.trx_datetime);
+        index.insert(insert_at, position);
+    }
+
+    /// postings of the account in the commodity, ordered by datetime
+    pub fn account_commodity_postings<'a>(&'a self, account_name: &str, currency: &str) -> impl DoubleEndedIterator<Item = &'a PostingDomain> + 'a {
+        self.posting_index
+            .get(account_name)
+            .and_then(|index| index.get(currency))
+            .into_iter()
+            .flatten()
+            .map(|idx| &self.postings[*idx])
+    }
+
+    /// the latest posting of the account in the commodity at or before the datetime
+    pub fn latest_account_commodity_posting<T: TimeZone>(&self, account_name: &str, currency: &str, datetime: &DateTime<T>) -> Option<&PostingDomain> {
+        let index = self.posting_index.get(account_name)?.get(currency)?;
+        let end = index.partition_point(|idx| self.postings[*idx].trx_datetime.le(datetime));
+        end.checked_sub(1).map(|last| &self.postings[index[last]])
+    }
+
+    /// whether any of the accounts has postings after the datetime
+    pub fn posted_after<T: TimeZone>(&self, accounts: impl Fn(&str) -> bool, datetime: &DateTime<T>) -> bool {
+        self.posting_index
+            .iter()
+            .filter(|(account_name, _)| accounts(account_name))
+            .flat_map(|(_, index)| index.values())
+            .filter_map(|index| index.last())
+            .any(|idx| self.postings[*idx].trx_datetime.gt(datetime))
+    }
+
+    /// commodities of the account, ordered by the datetime of their first postings
+    pub fn account_currencies(&self, account_name: &str) -> Vec<&Currency> {
+        self.posting_index
+            .get(account_name)
+            .into_iter()
+            .flatten()
+            .sorted_by_key(|(_, index)| (self.postings[index[0]].trx_datetime, index[0]))
+            .map(|(currency, _)| currency)
+            .collect_vec()
+    }
+
+    /// accounts having postings, which are the account itself or its sub-accounts
+    pub fn account_tree_names<'a>(&'a self, account_name: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        let sub_account_prefix = format!("{}:", account_name);
+        self.posting_index
+            .keys()
+            .filter(move |name| name.as_str() == account_name || name.starts_with(&sub_account_prefix))
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
 pub struct TransactionDomain {
     pub id: Uuid,
     pub sequence: i32,
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
     pub datetime: DateTime<Tz>,
     pub flag: Flag,
     pub payee: Option<String>,
@@ -103,11 +245,12 @@ impl TransactionDomain {
     }
 }
 
-#[derive(Clone, serde::Serialize, Debug)]
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
 pub struct PostingDomain {
     pub id: Uuid,
     pub trx_id: Uuid,
     pub trx_sequence: i32,
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
     pub trx_datetime: DateTime<Tz>,
     pub account: Account,
     pub unit: Option<Amount>,
@@ -115,9 +258,11 @@ pub struct PostingDomain {
     pub inferred_amount: Amount,
     pub previous_amount: Amount,
     pub after_amount: Amount,
+    /// the sum of realized gains of all lots reduced by the posting
+    pub realized_gain: Option<Amount>,
 }
 
-#[derive(Clone, serde::Serialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub enum DocumentType {
     Trx(Uuid),
     Account(Account),
@@ -144,15 +289,74 @@ impl DocumentType {
     }
 }
 
-#[derive(Clone, serde::Serialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct DocumentDomain {
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
     pub datetime: DateTime<Tz>,
     pub document_type: DocumentType,
     pub filename: Option<String>,
     pub path: String,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, PartialEq)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct EventDomain {
+    pub id: Uuid,
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
+    pub datetime: DateTime<Tz>,
+    pub event_type: String,
+    pub description: String,
+    pub span: SpanInfo,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CustomDomain {
+    pub id: Uuid,
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
+    pub datetime: DateTime<Tz>,
+    pub custom_type: String,
+    pub values: Vec<String>,
+    /// values named by the registered schema, empty if the custom type is not registered
+    pub arguments: IndexMap<String, String>,
+    pub span: SpanInfo,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct NoteDomain {
+    pub id: Uuid,
+    /// processing order of notes, counted apart from transactions
+    pub sequence: i32,
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
+    pub datetime: DateTime<Tz>,
+    pub account: Account,
+    pub comment: String,
+    pub tags: Vec<String>,
+    pub links: Vec<String>,
+    pub span: SpanInfo,
+}
+
+impl NoteDomain {
+    pub fn match_keywords(&self, keyword: Option<&String>, tags: &Option<HashSet<String>>, links: &Option<HashSet<String>>) -> bool {
+        let tag_matched = tags
+            .as_ref()
+            .map(|tag_candidates| self.tags.iter().any(|tag| tag_candidates.contains(tag)))
+            .unwrap_or(true);
+        let link_matched = links
+            .as_ref()
+            .map(|link_candidates| self.links.iter().any(|link| link_candidates.contains(link)))
+            .unwrap_or(true);
+        let keyword_matched = if let Some(keyword) = keyword.map(|it| it.to_lowercase()) {
+            self.comment.to_lowercase().contains(&keyword)
+                || self.account.name().to_lowercase().contains(&keyword)
+                || self.tags.iter().any(|it| it.to_lowercase().contains(&keyword))
+                || self.links.iter().any(|it| it.to_lowercase().contains(&keyword))
+        } else {
+            true
+        };
+        tag_matched && link_matched && keyword_matched
+    }
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct CommodityLotRecord {
     pub commodity: String,
     pub amount: BigDecimal,
@@ -163,48 +367,146 @@ pub struct CommodityLotRecord {
     pub acquisition_date: Option<NaiveDate>,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// realized gain of a single reduced lot
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RealizedGainDomain {
+    pub trx_id: Uuid,
+    pub posting_id: Uuid,
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
+    pub datetime: DateTime<Tz>,
+    pub account: Account,
+    pub commodity: String,
+    /// the reduced amount of the lot
+    pub amount: BigDecimal,
+    /// the cost per unit of the lot
+    pub cost: Amount,
+    /// the sale price per unit
+    pub price: Amount,
+    pub acquisition_date: Option<NaiveDate>,
+    pub gain: Amount,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct BudgetDomain {
     pub name: String,
     pub alias: Option<String>,
     pub category: Option<String>,
     pub closed: bool,
+    /// whether the leftover or overspend of an interval is carried over into the next interval as a rollover event,
+    /// instead of being taken as the assigned amount of the next interval
+    pub rollover: bool,
+    pub period: BudgetPeriod,
+    pub targets: Vec<BudgetTarget>,
+    /// details of intervals keyed by `year*100+index`, see [BudgetPeriod]
     pub detail: BTreeMap<u32, BudgetIntervalDetail>,
     pub commodity: String,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// targets of budget, configured by the metas `target`, `goal` with `goal_date`, and `cap` of budget directive
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum BudgetTarget {
+    /// the amount to be assigned in every interval
+    Interval { amount: Amount },
+    /// the amount to be saved up before the date
+    SaveBy { amount: Amount, date: NaiveDate },
+    /// the maximum amount to be spent in every interval
+    SpendingCap { amount: Amount },
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct BudgetIntervalDetail {
-    /// year and month pair, calculated as `year*100+month`, E.G. `202312`
+    /// the interval of budget period, calculated as `year*100+index`, E.G. `202312` for monthly budgets
     pub date: u32,
     pub assigned_amount: Amount,
     // todo: budget event for addition, transfer and close
     pub events: Vec<BudgetEvent>,
     pub activity_amount: Amount,
+    /// assigned amount minus activity amount, plus the available amount of previous interval if rollover is enabled
+    pub available_amount: Amount,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct BudgetEvent {
+    #[serde(deserialize_with = "crate::cache::deserialize_datetime")]
     pub datetime: DateTime<Tz>,
     pub timestamp: i64,
     pub amount: Amount,
     pub event_type: BudgetEventType,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum BudgetEventType {
     AddAssignedAmount,
     Transfer,
+    /// the available amount carried over from previous interval
+    Rollover,
+    /// the activity which cannot be converted into the commodity of budget because of missing price
+    UnconvertedActivity,
 }
 
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
 
-    use uuid::uuid;
+    use bigdecimal::BigDecimal;
+    use chrono::TimeZone;
+    use chrono_tz::Tz;
+    use uuid::{uuid, Uuid};
+    use zhang_ast::amount::Amount;
     use zhang_ast::Account;
 
-    use crate::store::DocumentType;
+    use crate::store::{DocumentType, PostingDomain, Store};
+
+    fn posting(account: &str, day: u32, number: i32, currency: &str) -> PostingDomain {
+        let amount = Amount::new(BigDecimal::from(number), currency);
+        PostingDomain {
+            id: Uuid::nil(),
+            trx_id: Uuid::nil(),
+            trx_sequence: 0,
+            trx_datetime: Tz::UTC.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+            account: Account::from_str(account).unwrap(),
+            unit: None,
+            cost: None,
+            inferred_amount: amount.clone(),
+            previous_amount: amount.clone(),
+            after_amount: amount,
+            realized_gain: None,
+        }
+    }
+
+    #[test]
+    fn should_keep_posting_index_ordered_by_datetime() {
+        let mut store = Store::default();
+        store.insert_posting(posting("Assets:A", 3, 30, "CNY"));
+        store.insert_posting(posting("Assets:A", 1, 10, "CNY"));
+        store.insert_posting(posting("Assets:A", 2, 5, "USD"));
+        store.insert_posting(posting("Assets:A", 3, 31, "CNY"));
+        store.insert_posting(posting("Assets:A:B", 1, 1, "CNY"));
+
+        let numbers = store
+            .account_commodity_postings("Assets:A", "CNY")
+            .map(|it| it.after_amount.number.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec![BigDecimal::from(10), BigDecimal::from(30), BigDecimal::from(31)]);
+
+        let latest = |day: u32| {
+            store
+                .latest_account_commodity_posting("Assets:A", "CNY", &Tz::UTC.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap())
+                .map(|it| it.after_amount.number.clone())
+        };
+        assert_eq!(latest(1), Some(BigDecimal::from(10)));
+        assert_eq!(latest(2), Some(BigDecimal::from(10)));
+        assert_eq!(latest(3), Some(BigDecimal::from(31)));
+        assert!(store
+            .latest_account_commodity_posting("Assets:A", "USD", &Tz::UTC.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+            .is_none());
+
+        assert_eq!(store.account_currencies("Assets:A"), vec!["CNY", "USD"]);
+        let mut tree = store.account_tree_names("Assets:A").collect::<Vec<_>>();
+        tree.sort();
+        assert_eq!(tree, vec!["Assets:A", "Assets:A:B"]);
+    }
 
     #[test]
     fn should_match_document_type() {
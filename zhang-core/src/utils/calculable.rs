@@ -20,6 +20,7 @@ impl Calculable for Vec<Amount> {
 
         let mut total = BigDecimal::zero();
         let mut detail = HashMap::new();
+        let mut conversions = HashMap::new();
 
         for amount in self.iter() {
             let number = amount.number.clone();
@@ -29,6 +30,7 @@ impl Calculable for Vec<Amount> {
                 total.add_assign(&number);
             } else if let Some(price) = operations.get_price(date.naive_local(), &currency, &operating_currency)? {
                 total.add_assign((&number).mul(price.amount));
+                conversions.insert(currency.clone(), price.path);
             }
 
             let currency_amount = detail.entry(currency).or_insert_with(BigDecimal::zero);
@@ -38,6 +40,7 @@ impl Calculable for Vec<Amount> {
         Ok(CalculatedAmount {
             calculated: Amount::new(total, operating_currency),
             detail,
+            conversions,
         })
     }
 }
@@ -1,6 +1,7 @@
 use std::mem;
 
-use chrono::{Duration, NaiveDate};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
 
 pub struct NaiveDateRange(NaiveDate, NaiveDate);
 
@@ -21,3 +22,45 @@ impl Iterator for NaiveDateRange {
         }
     }
 }
+
+/// the first instant of the date in the timezone. if midnight is skipped by daylight saving time,
+/// it is the first whole hour after the gap
+pub fn beginning_of_date(timezone: &Tz, date: NaiveDate) -> Option<DateTime<Tz>> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    (0..24).find_map(|hour| (midnight + Duration::hours(hour)).and_local_timezone(*timezone).earliest())
+}
+
+/// the last second of the date in the timezone, or `None` if `23:59:59` does not exist on that date
+pub fn end_of_date(timezone: &Tz, date: NaiveDate) -> Option<DateTime<Tz>> {
+    date.and_hms_opt(23, 59, 59)?.and_local_timezone(*timezone).latest()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, Timelike};
+    use chrono_tz::Tz;
+
+    use crate::utils::date_range::{beginning_of_date, end_of_date};
+
+    #[test]
+    fn should_get_beginning_after_skipped_midnight() {
+        // clocks in Santiago jumped from 00:00 to 01:00 on 2022-09-11
+        let date = NaiveDate::from_ymd_opt(2022, 9, 11).unwrap();
+        let beginning = beginning_of_date(&Tz::America__Santiago, date).unwrap();
+        assert_eq!(beginning.date_naive(), date);
+        assert_eq!(beginning.hour(), 1);
+
+        let beginning = beginning_of_date(&Tz::Asia__Shanghai, date).unwrap();
+        assert_eq!(beginning.hour(), 0);
+    }
+
+    #[test]
+    fn should_get_end_of_date_in_timezone() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let end = end_of_date(&Tz::Asia__Shanghai, date).unwrap();
+        assert_eq!(end.naive_utc(), date.and_hms_opt(15, 59, 59).unwrap());
+
+        // Samoa skipped the whole day of 2011-12-30
+        assert!(end_of_date(&Tz::Pacific__Apia, NaiveDate::from_ymd_opt(2011, 12, 30).unwrap()).is_none());
+    }
+}
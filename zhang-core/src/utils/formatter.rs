@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, RoundingMode, Signed};
+use zhang_ast::amount::Amount;
+use zhang_ast::{Directive, Rounding};
+
+use crate::domains::schemas::CommodityDomain;
+
+/// formats amounts with the precision, rounding, prefix and suffix of their commodities.
+/// amounts of undefined commodities use the default precision and rounding of ledger.
+#[derive(Debug, Clone)]
+pub struct AmountFormatter {
+    commodities: HashMap<String, CommodityDomain>,
+    default_precision: i32,
+    default_rounding: Rounding,
+}
+
+impl AmountFormatter {
+    pub fn new(commodities: impl IntoIterator<Item = CommodityDomain>, default_precision: i32, default_rounding: Rounding) -> Self {
+        AmountFormatter {
+            commodities: commodities.into_iter().map(|commodity| (commodity.name.clone(), commodity)).collect(),
+            default_precision,
+            default_rounding,
+        }
+    }
+
+    /// the precision and rounding of commodity, or the defaults if the commodity is undefined
+    fn precision_and_rounding(&self, currency: &str) -> (i64, RoundingMode) {
+        match self.commodities.get(currency) {
+            Some(commodity) => (commodity.precision as i64, commodity.rounding.to_mode()),
+            None => (self.default_precision as i64, self.default_rounding.to_mode()),
+        }
+    }
+
+    /// the number rounded to the precision of commodity
+    pub fn round(&self, amount: &Amount) -> BigDecimal {
+        let (precision, rounding) = self.precision_and_rounding(&amount.currency);
+        amount.number.with_scale_round(precision, rounding)
+    }
+
+    /// the rounded amount for display with thousands separators, like `$1,234.50`,
+    /// or like `1,234.50 USD` if the commodity has neither prefix nor suffix
+    pub fn display(&self, amount: &Amount) -> String {
+        let number = self.round(amount);
+        let sign = if number.is_negative() { "-" } else { "" };
+        let digits = group_thousands(&number.abs().to_string());
+        match self.commodities.get(&amount.currency) {
+            Some(CommodityDomain { prefix, suffix, .. }) if prefix.is_some() || suffix.is_some() => format!(
+                "{}{}{}{}",
+                sign,
+                prefix.as_deref().unwrap_or_default(),
+                digits,
+                suffix.as_deref().unwrap_or_default()
+            ),
+            _ => format!("{}{} {}", sign, digits, amount.currency),
+        }
+    }
+
+    /// the amount padded with zeros to the precision of commodity. digits are never dropped,
+    /// so the padded amount can be written back into ledger files
+    pub fn pad(&self, amount: &Amount) -> Amount {
+        let (_, scale) = amount.number.as_bigint_and_exponent();
+        let (precision, _) = self.precision_and_rounding(&amount.currency);
+        if scale < precision {
+            Amount::new(amount.number.with_scale(precision), amount.currency.clone())
+        } else {
+            amount.clone()
+        }
+    }
+
+    /// pad the amounts of postings and budgets in the directive, used before exporting directives
+    pub fn pad_directive(&self, mut directive: Directive) -> Directive {
+        match &mut directive {
+            Directive::Transaction(trx) => {
+                for posting in trx.postings.iter_mut() {
+                    posting.units = posting.units.as_ref().map(|units| self.pad(units));
+                }
+            }
+            Directive::Recurring(recurring) => {
+                for posting in recurring.postings.iter_mut() {
+                    posting.units = posting.units.as_ref().map(|units| self.pad(units));
+                }
+            }
+            Directive::BudgetAdd(budget_add) => budget_add.amount = self.pad(&budget_add.amount),
+            Directive::BudgetTransfer(budget_transfer) => budget_transfer.amount = self.pad(&budget_transfer.amount),
+            _ => {}
+        }
+        directive
+    }
+}
+
+/// insert `,` between every three digits of the integral part
+fn group_thousands(number: &str) -> String {
+    let (integral, fractional) = match number.split_once('.') {
+        Some((integral, fractional)) => (integral, Some(fractional)),
+        None => (number, None),
+    };
+    let mut grouped = String::with_capacity(number.len() + integral.len() / 3);
+    for (idx, digit) in integral.chars().enumerate() {
+        if idx > 0 && (integral.len() - idx) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fractional) = fractional {
+        grouped.push('.');
+        grouped.push_str(fractional);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use zhang_ast::amount::Amount;
+    use zhang_ast::Rounding;
+
+    use crate::domains::schemas::CommodityDomain;
+    use crate::utils::formatter::AmountFormatter;
+
+    fn formatter() -> AmountFormatter {
+        AmountFormatter::new(
+            vec![
+                CommodityDomain {
+                    name: "USD".to_owned(),
+                    precision: 2,
+                    prefix: Some("$".to_owned()),
+                    suffix: None,
+                    rounding: Rounding::RoundUp,
+                },
+                CommodityDomain {
+                    name: "BTC".to_owned(),
+                    precision: 4,
+                    prefix: None,
+                    suffix: None,
+                    rounding: Rounding::RoundDown,
+                },
+            ],
+            3,
+            Rounding::RoundUp,
+        )
+    }
+
+    fn amount(number: &str, currency: &str) -> Amount {
+        Amount::new(BigDecimal::from_str(number).unwrap(), currency)
+    }
+
+    #[test]
+    fn should_display_with_symbol_and_precision() {
+        let formatter = formatter();
+        assert_eq!(formatter.display(&amount("1234.5", "USD")), "$1,234.50");
+        assert_eq!(formatter.display(&amount("-1234567.125", "USD")), "-$1,234,567.13");
+        assert_eq!(formatter.display(&amount("0.12345", "BTC")), "0.1234 BTC");
+        assert_eq!(formatter.display(&amount("999", "BTC")), "999.0000 BTC");
+    }
+
+    #[test]
+    fn should_use_default_precision_and_rounding_for_undefined_commodity() {
+        let formatter = formatter();
+        assert_eq!(formatter.display(&amount("1234.5", "CNY")), "1,234.500 CNY");
+        assert_eq!(formatter.display(&amount("1234.5675", "CNY")), "1,234.568 CNY");
+        assert_eq!(formatter.pad(&amount("1234.5", "CNY")).number.to_string(), "1234.500");
+        assert_eq!(formatter.pad(&amount("1234.5671", "CNY")).number.to_string(), "1234.5671");
+    }
+
+    #[test]
+    fn should_pad_without_dropping_digits() {
+        let formatter = formatter();
+        assert_eq!(formatter.pad(&amount("1234.5", "USD")).number.to_string(), "1234.50");
+        assert_eq!(formatter.pad(&amount("1234.125", "USD")).number.to_string(), "1234.125");
+    }
+}
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use chrono::NaiveDate;
 use sha256::digest;
 use uuid::Uuid;
 use zhang_ast::SpanInfo;
@@ -9,6 +10,7 @@ const DEFAULT_PATH: &str = "default_path";
 pub trait FromSpan {
     fn from_span(span: &SpanInfo) -> Uuid;
     fn from_txn_posting(txn_id: &Uuid, idx: usize) -> Uuid;
+    fn from_recurring_occurrence(recurring_id: &str, date: &NaiveDate) -> Uuid;
 }
 
 impl FromSpan for Uuid {
@@ -22,6 +24,11 @@ impl FromSpan for Uuid {
         let string = digest(format!("{}-{}", &txn_id, idx));
         Uuid::from_str(&string[0..32]).expect("invalid uuid")
     }
+
+    fn from_recurring_occurrence(recurring_id: &str, date: &NaiveDate) -> Uuid {
+        let string = digest(format!("{}-{}", recurring_id, date));
+        Uuid::from_str(&string[0..32]).expect("invalid uuid")
+    }
 }
 
 #[cfg(test)]
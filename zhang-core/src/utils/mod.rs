@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 pub mod calculable;
 pub mod date_range;
+pub mod formatter;
 pub mod hashmap;
 pub mod id;
 pub mod logging;
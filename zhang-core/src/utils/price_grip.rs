@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, One, Zero};
+use itertools::Itertools;
 use zhang_ast::Currency;
 
 #[derive(Debug, Clone, Default)]
@@ -16,11 +17,61 @@ impl PriceGrip {
     pub fn get(&self, from: &Currency, to: &Currency) -> Option<BigDecimal> {
         self.inner.get(from).and_then(|from_map| from_map.get(to)).cloned()
     }
+
+    /// find the rate from `from` to `to` with the path of commodities it goes through.
+    /// inverse prices are used when needed, and intermediate commodities are chained, e.g. AAPL -> USD -> CNY.
+    /// the path with the fewest hops wins, and direct prices are preferred over inverse prices.
+    pub fn convert(&self, from: &Currency, to: &Currency) -> Option<(BigDecimal, Vec<Currency>)> {
+        if from.eq(to) {
+            return Some((BigDecimal::one(), vec![from.clone()]));
+        }
+        let mut visited = HashSet::from([from.clone()]);
+        let mut queue = VecDeque::from([(from.clone(), BigDecimal::one(), vec![from.clone()])]);
+        while let Some((currency, rate, path)) = queue.pop_front() {
+            for (next, next_rate) in self.neighbours(&currency) {
+                if !visited.insert(next.clone()) {
+                    continue;
+                }
+                let rate = &rate * next_rate;
+                let mut path = path.clone();
+                path.push(next.clone());
+                if next.eq(to) {
+                    return Some((rate, path));
+                }
+                queue.push_back((next, rate, path));
+            }
+        }
+        None
+    }
+
+    /// commodities can be converted into from the given one in one hop, direct prices go first
+    fn neighbours(&self, currency: &Currency) -> Vec<(Currency, BigDecimal)> {
+        let direct = self
+            .inner
+            .get(currency)
+            .into_iter()
+            .flatten()
+            .map(|(to, rate)| (to.clone(), rate.clone()))
+            .sorted_by(|a, b| a.0.cmp(&b.0));
+        let inverse = self
+            .inner
+            .iter()
+            .filter_map(|(from, from_map)| {
+                from_map
+                    .get(currency)
+                    .filter(|rate| !rate.is_zero())
+                    .map(|rate| (from.clone(), BigDecimal::one() / rate))
+            })
+            .sorted_by(|a, b| a.0.cmp(&b.0));
+        direct.chain(inverse).collect_vec()
+    }
 }
 
 #[cfg(test)]
 mod test {
     mod price_grip {
+        use std::str::FromStr;
+
         use bigdecimal::BigDecimal;
 
         use crate::utils::price_grip::PriceGrip;
@@ -43,5 +94,35 @@ mod test {
             assert_eq!(grip.get(&"USD".to_string(), &"CCY".to_string()), None);
             assert_eq!(grip.get(&"CNY".to_string(), &"USD".to_string()), None);
         }
+
+        #[test]
+        fn should_convert_with_inverse_price() {
+            let mut grip = PriceGrip::default();
+            grip.insert("USD".to_string(), "CNY".to_string(), BigDecimal::from(8i32));
+            let (rate, path) = grip.convert(&"CNY".to_string(), &"USD".to_string()).unwrap();
+            assert_eq!(rate, BigDecimal::from_str("0.125").unwrap());
+            assert_eq!(path, vec!["CNY".to_string(), "USD".to_string()]);
+        }
+
+        #[test]
+        fn should_convert_through_intermediate_commodity() {
+            let mut grip = PriceGrip::default();
+            grip.insert("AAPL".to_string(), "USD".to_string(), BigDecimal::from(100i32));
+            grip.insert("USD".to_string(), "CNY".to_string(), BigDecimal::from(7i32));
+            let (rate, path) = grip.convert(&"AAPL".to_string(), &"CNY".to_string()).unwrap();
+            assert_eq!(rate, BigDecimal::from(700i32));
+            assert_eq!(path, vec!["AAPL".to_string(), "USD".to_string(), "CNY".to_string()]);
+
+            assert_eq!(grip.convert(&"AAPL".to_string(), &"HKD".to_string()), None);
+        }
+
+        #[test]
+        fn should_prefer_direct_price() {
+            let mut grip = PriceGrip::default();
+            grip.insert("USD".to_string(), "CNY".to_string(), BigDecimal::from(7i32));
+            grip.insert("CNY".to_string(), "USD".to_string(), BigDecimal::from_str("0.2").unwrap());
+            let (rate, _) = grip.convert(&"USD".to_string(), &"CNY".to_string()).unwrap();
+            assert_eq!(rate, BigDecimal::from(7i32));
+        }
     }
 }
@@ -13,8 +13,11 @@ use routes::account::*;
 use routes::budget::*;
 use routes::commodity::*;
 use routes::common::*;
+use routes::custom::*;
 use routes::document::*;
+use routes::event::*;
 use routes::file::*;
+use routes::query::*;
 use routes::statistics::*;
 use routes::transaction::*;
 use self_update::version::bump_is_greater;
@@ -25,6 +28,7 @@ use tokio::sync::{mpsc, RwLock};
 use tower_http::cors::CorsLayer;
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::validate_request::ValidateRequestHeaderLayer;
+use zhang_core::cache::LedgerCache;
 use zhang_core::data_source::DataSource;
 use zhang_core::ledger::Ledger;
 use zhang_core::utils::has_path_visited;
@@ -74,6 +78,8 @@ pub struct ServeConfig {
     pub data_source: Arc<dyn DataSource>,
     pub auth_credential: Option<String>,
     pub is_local_fs: bool,
+    /// the file to cache the processed ledger, the ledger is not cached if absent
+    pub database: Option<PathBuf>,
 }
 
 pub struct ReloadSender(pub Sender<i32>);
@@ -86,7 +92,13 @@ impl ReloadSender {
 
 pub async fn serve(opts: ServeConfig) -> ZhangResult<()> {
     info!("version: {}, build date: {}", env!("ZHANG_BUILD_VERSION"), env!("ZHANG_BUILD_DATE"));
-    let ledger = Ledger::async_load(opts.path.clone(), opts.endpoint.clone(), opts.data_source.clone()).await?;
+    let ledger = match &opts.database {
+        Some(database) => {
+            let cache = LedgerCache::new(database, env!("ZHANG_BUILD_VERSION"));
+            Ledger::async_load_with_cache(opts.path.clone(), opts.endpoint.clone(), opts.data_source.clone(), &cache).await?
+        }
+        None => Ledger::async_load(opts.path.clone(), opts.endpoint.clone(), opts.data_source.clone()).await?,
+    };
     let ledger_data = Arc::new(RwLock::new(ledger));
     let broadcaster = Broadcaster::create();
     let (tx, rx) = mpsc::channel::<i32>(1);
@@ -248,20 +260,29 @@ pub fn create_server_app(
         .route("/api/transactions/:transaction_id", put(routes::transaction::update_single_transaction))
         .route("/api/transactions/:transaction_id/documents", post(upload_transaction_document))
         .route("/api/accounts", get(get_account_list))
+        .route("/api/accounts/tree", get(get_account_tree))
         .route("/api/accounts/:account_name", get(get_account_info))
         .route("/api/accounts/:account_name/documents", post(upload_account_document))
         .route("/api/accounts/:account_name/documents", get(get_account_documents))
         .route("/api/accounts/:account_name/journals", get(get_account_journals))
         .route("/api/accounts/:account_name/balances", get(get_account_balance_data))
         .route("/api/accounts/:account_name/balances", post(create_account_balance))
+        .route("/api/accounts/:account_name/holdings", get(get_account_holdings))
         .route("/api/accounts/batch-balances", post(create_batch_account_balances))
         .route("/api/documents", get(get_documents))
         .route("/api/documents/:file_path", get(download_document))
         .route("/api/commodities", get(get_all_commodities))
         .route("/api/commodities/:commodity_name", get(get_single_commodity))
+        .route("/api/gains", get(get_realized_gains))
+        .route("/api/events", get(get_events))
+        .route("/api/events/:event_type", get(get_event_timeline))
+        .route("/api/customs/:custom_type", get(get_customs))
         .route("/api/statistic/summary", get(get_statistic_summary))
         .route("/api/statistic/graph", get(get_statistic_graph))
+        .route("/api/statistic/forecast", get(get_statistic_forecast))
+        .route("/api/statistic/closing", get(get_statistic_closing))
         .route("/api/statistic/:account_type", get(get_statistic_rank_detail_by_account_type))
+        .route("/api/query", get(execute_query))
         .route("/api/budgets", get(get_budget_list))
         .route("/api/budgets/:budget_name", get(get_budget_info))
         .route("/api/budgets/:budget_name/interval/:year/:month", get(get_budget_interval_detail))
@@ -2,7 +2,7 @@ use std::cmp::max;
 use std::collections::HashSet;
 
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Datelike, Local, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
 use serde::Deserialize;
 use zhang_ast::Flag;
 
@@ -37,6 +37,19 @@ pub struct StatisticGraphRequest {
     pub interval: StatisticInterval,
 }
 
+#[derive(Deserialize)]
+pub struct ForecastRequest {
+    /// the number of months to forecast after this month
+    pub months: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct PeriodClosingRequest {
+    pub from: NaiveDate,
+    /// the last day of period, inclusive
+    pub to: NaiveDate,
+}
+
 #[derive(Deserialize)]
 pub struct ReportRequest {
     pub from: DateTime<Utc>,
@@ -116,14 +129,48 @@ pub struct MetaRequest {
     pub value: String,
 }
 
+#[derive(Deserialize)]
+pub struct RealizedGainsRequest {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct HoldingsRequest {
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+pub struct AccountTreeRequest {
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+pub struct QueryRequest {
+    pub query: String,
+}
+
+#[derive(Deserialize)]
+pub struct EventRequest {
+    pub date: Option<NaiveDate>,
+}
+
 #[derive(Deserialize)]
 pub struct BudgetListRequest {
     pub month: Option<u32>,
     pub year: Option<u32>,
+    /// any date in the requested interval, which takes precedence over `year` and `month`
+    pub date: Option<NaiveDate>,
 }
 impl BudgetListRequest {
-    pub fn as_interval(&self) -> u32 {
-        let time = Local::now();
-        self.year.unwrap_or(time.year() as u32) * 100 + self.month.unwrap_or(time.month())
+    /// the date to locate the interval of each budget, which is the first day of the requested month,
+    /// or today if neither `year` nor `month` is given
+    pub fn as_date(&self) -> NaiveDate {
+        let today = Local::now().date_naive();
+        match (self.date, self.year, self.month) {
+            (Some(date), _, _) => date,
+            (None, None, None) => today,
+            (None, year, month) => NaiveDate::from_ymd_opt(year.unwrap_or(today.year() as u32) as i32, month.unwrap_or(today.month()), 1).unwrap_or(today),
+        }
     }
 }
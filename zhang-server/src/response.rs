@@ -1,16 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use indexmap::IndexMap;
 use serde::Serialize;
+use serde_json::json;
 use uuid::Uuid;
 use zhang_ast::amount::{Amount, CalculatedAmount};
-use zhang_ast::AccountType;
-use zhang_core::domains::schemas::{AccountJournalDomain, AccountStatus, MetaDomain};
+use zhang_ast::{AccountType, BudgetPeriod};
+use zhang_core::domains::schemas::{AccountJournalDomain, AccountJournalItemDomain, AccountStatus, MetaDomain, PriceSource};
+use zhang_core::domains::BudgetTargetProgressDomain;
 use zhang_core::plugin::PluginType;
-use zhang_core::store::{BudgetEvent, PostingDomain};
+use zhang_core::store::{BudgetEvent, CustomDomain, EventDomain, NoteDomain, PostingDomain};
+use zhang_core::utils::formatter::AmountFormatter;
 
 use crate::ServerResult;
 
@@ -19,6 +23,8 @@ pub enum ResponseWrapper<T: Serialize = ()> {
     Created,
     NotFound,
     BadRequest,
+    /// bad request with the reason in the body
+    BadRequestWithMessage(String),
 }
 
 impl<T: Serialize> ResponseWrapper<T> {
@@ -34,6 +40,9 @@ impl<T: Serialize> ResponseWrapper<T> {
     pub fn bad_request() -> ServerResult<ResponseWrapper<T>> {
         Ok(ResponseWrapper::BadRequest)
     }
+    pub fn bad_request_with_message(message: impl Into<String>) -> ServerResult<ResponseWrapper<T>> {
+        Ok(ResponseWrapper::BadRequestWithMessage(message.into()))
+    }
 }
 
 impl<T: Serialize> IntoResponse for ResponseWrapper<T> {
@@ -50,6 +59,7 @@ impl<T: Serialize> IntoResponse for ResponseWrapper<T> {
             ResponseWrapper::Created => (axum::http::StatusCode::CREATED, "").into_response(),
             ResponseWrapper::NotFound => (axum::http::StatusCode::NOT_FOUND, "").into_response(),
             ResponseWrapper::BadRequest => (axum::http::StatusCode::BAD_REQUEST, "").into_response(),
+            ResponseWrapper::BadRequestWithMessage(message) => (axum::http::StatusCode::BAD_REQUEST, Json(json!({ "message": message }))).into_response(),
         }
     }
 }
@@ -84,6 +94,16 @@ pub struct AccountResponse {
     pub amount: CalculatedAmount,
 }
 
+#[derive(Serialize)]
+pub struct AccountTreeResponse {
+    pub name: String,
+    pub status: Option<AccountStatus>,
+    pub balance: CalculatedAmount,
+    /// balance of the account and all its sub-accounts
+    pub total: CalculatedAmount,
+    pub children: Vec<AccountTreeResponse>,
+}
+
 #[derive(Serialize)]
 pub struct DocumentResponse {
     pub datetime: NaiveDateTime,
@@ -120,6 +140,7 @@ pub enum JournalItemResponse {
     Transaction(JournalTransactionItemResponse),
     BalanceCheck(JournalBalanceCheckItemResponse),
     BalancePad(JournalBalancePadItemResponse),
+    Note(JournalNoteItemResponse),
 }
 
 impl JournalItemResponse {
@@ -128,6 +149,7 @@ impl JournalItemResponse {
             JournalItemResponse::Transaction(inner) => inner.sequence,
             JournalItemResponse::BalanceCheck(inner) => inner.sequence,
             JournalItemResponse::BalancePad(inner) => inner.sequence,
+            JournalItemResponse::Note(inner) => inner.sequence,
         }
     }
 }
@@ -145,6 +167,8 @@ pub struct JournalTransactionItemResponse {
     pub is_balanced: bool,
     pub postings: Vec<JournalTransactionPostingResponse>,
     pub metas: Vec<MetaResponse>,
+    /// values of events active at the transaction datetime, keyed by event type
+    pub events: BTreeMap<String, String>,
 }
 #[derive(Serialize)]
 pub struct JournalTransactionPostingResponse {
@@ -159,6 +183,8 @@ pub struct JournalTransactionPostingResponse {
     pub account_before_commodity: String,
     pub account_after_number: BigDecimal,
     pub account_after_commodity: String,
+    pub realized_gain_number: Option<BigDecimal>,
+    pub realized_gain_commodity: Option<String>,
 }
 
 impl From<PostingDomain> for JournalTransactionPostingResponse {
@@ -175,6 +201,8 @@ impl From<PostingDomain> for JournalTransactionPostingResponse {
             account_before_commodity: arm.previous_amount.currency,
             account_after_number: arm.after_amount.number,
             account_after_commodity: arm.after_amount.currency,
+            realized_gain_number: arm.realized_gain.as_ref().map(|it| it.number.clone()),
+            realized_gain_commodity: arm.realized_gain.map(|it| it.currency),
         }
     }
 }
@@ -201,6 +229,94 @@ pub struct JournalBalancePadItemResponse {
     pub(crate) postings: Vec<JournalTransactionPostingResponse>,
 }
 
+#[derive(Serialize)]
+pub struct JournalNoteItemResponse {
+    pub id: Uuid,
+    pub sequence: i32,
+    pub datetime: NaiveDateTime,
+    pub account: String,
+    pub comment: String,
+    pub tags: Vec<String>,
+    pub links: Vec<String>,
+}
+
+impl From<NoteDomain> for JournalNoteItemResponse {
+    fn from(note: NoteDomain) -> Self {
+        JournalNoteItemResponse {
+            id: note.id,
+            sequence: note.sequence,
+            datetime: note.datetime.naive_local(),
+            account: note.account.name().to_owned(),
+            comment: note.comment,
+            tags: note.tags,
+            links: note.links,
+        }
+    }
+}
+
+/// item of account journals, tagged by `type` like [JournalItemResponse]
+#[derive(Serialize)]
+#[serde(tag = "type")]
+pub enum AccountJournalItemResponse {
+    Posting(AccountJournalDomain),
+    Note(JournalNoteItemResponse),
+}
+
+impl From<AccountJournalItemDomain> for AccountJournalItemResponse {
+    fn from(item: AccountJournalItemDomain) -> Self {
+        match item {
+            AccountJournalItemDomain::Posting(posting) => AccountJournalItemResponse::Posting(posting),
+            AccountJournalItemDomain::Note(note) => AccountJournalItemResponse::Note(JournalNoteItemResponse::from(note)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EventResponse {
+    pub event_type: String,
+    pub datetime: NaiveDateTime,
+    pub value: String,
+}
+
+impl From<EventDomain> for EventResponse {
+    fn from(value: EventDomain) -> Self {
+        Self {
+            event_type: value.event_type,
+            datetime: value.datetime.naive_local(),
+            value: value.description,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EventTimelineResponse {
+    pub event_type: String,
+    /// the value at the requested date
+    pub current: Option<EventResponse>,
+    pub timeline: Vec<EventResponse>,
+}
+
+#[derive(Serialize)]
+pub struct CustomResponse {
+    pub id: Uuid,
+    pub datetime: NaiveDateTime,
+    pub custom_type: String,
+    pub values: Vec<String>,
+    pub arguments: IndexMap<String, String>,
+}
+
+impl From<CustomDomain> for CustomResponse {
+    fn from(value: CustomDomain) -> Self {
+        Self {
+            id: value.id,
+            datetime: value.datetime.naive_local(),
+            custom_type: value.custom_type,
+            values: value.values,
+            arguments: value.arguments,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct InfoForNewTransaction {
     pub payee: Vec<String>,
@@ -211,13 +327,16 @@ pub struct InfoForNewTransaction {
 pub struct AmountResponse {
     pub number: BigDecimal,
     pub commodity: String,
+    /// the amount formatted with the precision and symbols of commodity, like `$1,234.50`
+    pub formatted: String,
 }
 
-impl From<Amount> for AmountResponse {
-    fn from(value: Amount) -> Self {
+impl AmountResponse {
+    pub fn new(amount: Amount, formatter: &AmountFormatter) -> Self {
         AmountResponse {
-            number: value.number,
-            commodity: value.currency,
+            formatted: formatter.display(&amount),
+            number: amount.number,
+            commodity: amount.currency,
         }
     }
 }
@@ -252,6 +371,7 @@ pub struct CommodityPrice {
     pub datetime: NaiveDateTime,
     pub amount: BigDecimal,
     pub target_commodity: Option<String>,
+    pub source: PriceSource,
 }
 
 #[derive(Serialize)]
@@ -261,6 +381,70 @@ pub struct CommodityDetailResponse {
     pub prices: Vec<CommodityPrice>,
 }
 
+#[derive(Serialize)]
+pub struct RealizedGainItemResponse {
+    pub trx_id: Uuid,
+    pub datetime: NaiveDateTime,
+    pub account: String,
+    pub commodity: String,
+    pub amount: BigDecimal,
+    pub cost: AmountResponse,
+    pub price: AmountResponse,
+    pub acquisition_date: Option<NaiveDate>,
+    pub gain: AmountResponse,
+}
+
+#[derive(Serialize)]
+pub struct RealizedGainsResponse {
+    pub total: Vec<AmountResponse>,
+    pub gains: Vec<RealizedGainItemResponse>,
+}
+
+#[derive(Serialize)]
+pub struct HoldingItemResponse {
+    pub account: String,
+    pub commodity: String,
+    pub amount: BigDecimal,
+    pub cost: AmountResponse,
+    pub acquisition_date: Option<NaiveDate>,
+    pub cost_basis: AmountResponse,
+    pub price: Option<AmountResponse>,
+    pub price_datetime: Option<NaiveDateTime>,
+    pub market_value: Option<AmountResponse>,
+    pub unrealized_gain: Option<AmountResponse>,
+}
+
+#[derive(Serialize)]
+pub struct PeriodClosingResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// balances of assets, liabilities and equity at the end of period after closing
+    pub balance_sheet: BTreeMap<String, Vec<AmountResponse>>,
+    /// exported open directives of earnings and opening balances accounts which are not opened in the ledger
+    pub opens: Vec<String>,
+    /// exported closing transaction
+    pub closing: Option<String>,
+    /// exported opening transaction of the next period
+    pub opening: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct HoldingTotalResponse {
+    pub cost_basis: BigDecimal,
+    pub market_value: BigDecimal,
+    pub unrealized_gain: BigDecimal,
+}
+
+#[derive(Serialize)]
+pub struct AccountHoldingsResponse {
+    pub datetime: NaiveDateTime,
+    pub holdings: Vec<HoldingItemResponse>,
+    /// totals of each account, grouped by cost commodity
+    pub accounts: BTreeMap<String, BTreeMap<String, HoldingTotalResponse>>,
+    /// totals of all holdings, grouped by cost commodity
+    pub total: BTreeMap<String, HoldingTotalResponse>,
+}
+
 #[derive(Serialize)]
 pub struct FileDetailResponse {
     pub path: String,
@@ -343,6 +527,7 @@ pub struct AccountInfoResponse {
     pub name: String,
     pub status: AccountStatus,
     pub alias: Option<String>,
+    pub commodities: Vec<String>,
     pub amount: CalculatedAmount,
 }
 
@@ -352,6 +537,10 @@ pub struct BudgetListItemResponse {
     pub alias: Option<String>,
     pub category: Option<String>,
     pub closed: bool,
+    pub rollover: bool,
+    pub period: BudgetPeriod,
+    pub interval_start: NaiveDate,
+    pub interval_end: NaiveDate,
     pub assigned_amount: Amount,
     pub activity_amount: Amount,
     pub available_amount: Amount,
@@ -363,8 +552,13 @@ pub struct BudgetInfoResponse {
     pub alias: Option<String>,
     pub category: Option<String>,
     pub closed: bool,
+    pub rollover: bool,
+    pub period: BudgetPeriod,
+    pub interval_start: NaiveDate,
+    pub interval_end: NaiveDate,
 
     pub related_accounts: Vec<String>,
+    pub targets: Vec<BudgetTargetProgressDomain>,
 
     pub assigned_amount: Amount,
     pub activity_amount: Amount,
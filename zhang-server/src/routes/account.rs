@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -11,12 +11,17 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 use zhang_ast::amount::Amount;
 use zhang_ast::{Account, BalanceCheck, BalancePad, Currency, Date, Directive, Document, ZhangString};
-use zhang_core::domains::schemas::AccountJournalDomain;
+use zhang_core::domains::AccountTreeDomain;
 use zhang_core::ledger::Ledger;
 use zhang_core::utils::calculable::Calculable;
+use zhang_core::utils::date_range::end_of_date;
 
-use crate::request::AccountBalanceRequest;
-use crate::response::{AccountBalanceItemResponse, AccountInfoResponse, AccountResponse, AmountResponse, DocumentResponse, ResponseWrapper};
+use super::Query;
+use crate::request::{AccountBalanceRequest, AccountTreeRequest, HoldingsRequest};
+use crate::response::{
+    AccountBalanceItemResponse, AccountHoldingsResponse, AccountInfoResponse, AccountJournalItemResponse, AccountResponse, AccountTreeResponse, AmountResponse,
+    DocumentResponse, HoldingItemResponse, HoldingTotalResponse, ResponseWrapper,
+};
 use crate::{ApiResult, LedgerState, ReloadSender};
 
 pub async fn get_account_list(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<Vec<AccountResponse>> {
@@ -46,6 +51,32 @@ pub async fn get_account_list(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<V
     ResponseWrapper::json(ret)
 }
 
+/// the account tree with balances at the end of the requested date, or now if date is not given
+pub async fn get_account_tree(ledger: State<Arc<RwLock<Ledger>>>, params: Query<AccountTreeRequest>) -> ApiResult<Vec<AccountTreeResponse>> {
+    let ledger = ledger.read().await;
+    let timezone = &ledger.options.timezone;
+    let mut operations = ledger.operations();
+    let datetime = match params.0.date {
+        Some(date) => match end_of_date(timezone, date) {
+            Some(datetime) => datetime,
+            None => return ResponseWrapper::bad_request(),
+        },
+        None => Utc::now().with_timezone(timezone),
+    };
+
+    fn to_response(node: AccountTreeDomain, operating_currency: &str) -> AccountTreeResponse {
+        AccountTreeResponse {
+            name: node.name,
+            status: node.status,
+            balance: node.balance.persist_commodity(operating_currency),
+            total: node.total.persist_commodity(operating_currency),
+            children: node.children.into_iter().map(|child| to_response(child, operating_currency)).collect_vec(),
+        }
+    }
+    let tree = operations.account_tree(datetime)?;
+    ResponseWrapper::json(tree.into_iter().map(|node| to_response(node, &ledger.options.operating_currency)).collect_vec())
+}
+
 pub async fn get_account_info(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(String,)>) -> ApiResult<AccountInfoResponse> {
     let account_name = path.0 .0;
     let ledger = ledger.read().await;
@@ -72,6 +103,7 @@ pub async fn get_account_info(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(St
         name: account_info.name,
         status: account_info.status,
         alias: account_info.alias,
+        commodities: account_info.commodities,
         amount,
     })
 }
@@ -123,6 +155,7 @@ pub async fn get_account_balance_data(ledger: State<LedgerState>, params: Path<(
     let operations = ledger.operations();
 
     let vec = operations.single_account_all_balances(&account_name)?;
+    let formatter = operations.amount_formatter();
     ResponseWrapper::json(
         vec.into_iter()
             .map(|(commodity, balance_history)| {
@@ -130,10 +163,7 @@ pub async fn get_account_balance_data(ledger: State<LedgerState>, params: Path<(
                     .into_iter()
                     .map(|(date, amount)| AccountBalanceItemResponse {
                         date,
-                        balance: AmountResponse {
-                            number: amount.number,
-                            commodity: amount.currency,
-                        },
+                        balance: AmountResponse::new(amount, &formatter),
                     })
                     .collect_vec();
                 (commodity, data)
@@ -167,16 +197,84 @@ pub async fn get_account_documents(ledger: State<Arc<RwLock<Ledger>>>, params: P
     ResponseWrapper::json(rows)
 }
 
-pub async fn get_account_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Path<(String,)>) -> ApiResult<Vec<AccountJournalDomain>> {
+pub async fn get_account_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Path<(String,)>) -> ApiResult<Vec<AccountJournalItemResponse>> {
     let account_name = params.0 .0;
     let ledger = ledger.read().await;
     let mut operations = ledger.operations();
 
-    let journals = operations.account_journals(&account_name)?;
+    let journals = operations
+        .account_journals(&account_name)?
+        .into_iter()
+        .map(AccountJournalItemResponse::from)
+        .collect_vec();
 
     ResponseWrapper::json(journals)
 }
 
+/// holdings of the account and its sub-accounts, which is valued at the end of the requested date, or now if date is not given.
+/// lots without market price are valued at cost in totals
+pub async fn get_account_holdings(
+    ledger: State<Arc<RwLock<Ledger>>>, path: Path<(String,)>, params: Query<HoldingsRequest>,
+) -> ApiResult<AccountHoldingsResponse> {
+    let account_name = path.0 .0;
+    let ledger = ledger.read().await;
+    let operations = ledger.operations();
+    let timezone = &ledger.options.timezone;
+    let datetime = match params.0.date {
+        Some(date) => match end_of_date(timezone, date) {
+            Some(datetime) => datetime,
+            None => return ResponseWrapper::bad_request(),
+        },
+        None => Utc::now().with_timezone(timezone),
+    };
+
+    let holdings = ledger.account_holdings(&account_name, datetime)?;
+    let datetime = datetime.naive_local();
+    let formatter = operations.amount_formatter();
+
+    let mut accounts: BTreeMap<String, BTreeMap<String, HoldingTotalResponse>> = BTreeMap::new();
+    let mut total: BTreeMap<String, HoldingTotalResponse> = BTreeMap::new();
+    for holding in holdings.iter() {
+        let market_value = holding.market_value.as_ref().unwrap_or(&holding.cost_basis);
+        let account_total = accounts
+            .entry(holding.account.name().to_owned())
+            .or_default()
+            .entry(holding.cost_basis.currency.clone())
+            .or_default();
+        let portfolio_total = total.entry(holding.cost_basis.currency.clone()).or_default();
+        for entry in [account_total, portfolio_total] {
+            entry.cost_basis += &holding.cost_basis.number;
+            entry.market_value += &market_value.number;
+            entry.unrealized_gain += &market_value.number - &holding.cost_basis.number;
+        }
+    }
+
+    let holdings = holdings
+        .into_iter()
+        .map(|holding| HoldingItemResponse {
+            account: holding.account.name().to_owned(),
+            commodity: holding.commodity,
+            amount: holding.amount,
+            cost: AmountResponse::new(holding.cost, &formatter),
+            acquisition_date: holding.acquisition_date,
+            cost_basis: AmountResponse::new(holding.cost_basis, &formatter),
+            price_datetime: holding.price.as_ref().map(|price| price.datetime),
+            price: holding
+                .price
+                .map(|price| AmountResponse::new(Amount::new(price.amount, price.target_commodity), &formatter)),
+            market_value: holding.market_value.map(|amount| AmountResponse::new(amount, &formatter)),
+            unrealized_gain: holding.unrealized_gain.map(|amount| AmountResponse::new(amount, &formatter)),
+        })
+        .collect_vec();
+
+    ResponseWrapper::json(AccountHoldingsResponse {
+        datetime,
+        holdings,
+        accounts,
+        total,
+    })
+}
+
 pub async fn create_account_balance(
     ledger: State<Arc<RwLock<Ledger>>>, reload_sender: State<Arc<ReloadSender>>, params: Path<(String,)>, Json(payload): Json<AccountBalanceRequest>,
 ) -> ApiResult<()> {
@@ -191,6 +289,7 @@ pub async fn create_account_balance(
                 number: amount.number,
                 currency: amount.commodity,
             },
+            tolerance: None,
             meta: Default::default(),
         }),
         AccountBalanceRequest::Pad { amount, pad, .. } => Directive::BalancePad(BalancePad {
@@ -224,6 +323,7 @@ pub async fn create_batch_account_balances(
                     number: amount.number,
                     currency: amount.commodity,
                 },
+                tolerance: None,
                 meta: Default::default(),
             }),
             AccountBalanceRequest::Pad { account_name, amount, pad } => Directive::BalancePad(BalancePad {
@@ -1,35 +1,42 @@
 use std::cmp::Reverse;
-use std::ops::Sub;
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
-use chrono::NaiveDate;
 use itertools::Itertools;
-use now::DateTimeNow;
 use tokio::sync::RwLock;
 use zhang_ast::amount::Amount;
 use zhang_core::ledger::Ledger;
 use zhang_core::store::BudgetIntervalDetail;
+use zhang_core::utils::date_range::{beginning_of_date, end_of_date};
 
 use crate::request::BudgetListRequest;
 use crate::response::{BudgetInfoResponse, BudgetIntervalEventResponse, BudgetListItemResponse, ResponseWrapper};
 use crate::ApiResult;
 
+/// budgets in the interval which the requested date falls in, intervals are located by the period of each budget
 pub async fn get_budget_list(ledger: State<Arc<RwLock<Ledger>>>, params: Query<BudgetListRequest>) -> ApiResult<Vec<BudgetListItemResponse>> {
-    let interval = params.as_interval();
+    let date = params.as_date();
 
     let ledger = ledger.read().await;
     let operations = ledger.operations();
 
     let mut ret = vec![];
     for budget in operations.all_budgets()? {
-        if let Some(interval_detail) = operations.budget_month_detail(&budget.name, interval)? {
+        let interval = budget.period.interval(date);
+        let Some((interval_start, interval_end)) = budget.period.interval_range(interval) else {
+            continue;
+        };
+        if let Some(interval_detail) = operations.budget_interval_detail(&budget.name, interval)? {
             ret.push(BudgetListItemResponse {
                 name: budget.name,
                 alias: budget.alias,
                 category: budget.category,
                 closed: budget.closed,
-                available_amount: interval_detail.assigned_amount.sub(interval_detail.activity_amount.number.clone()),
+                rollover: budget.rollover,
+                period: budget.period,
+                interval_start,
+                interval_end,
+                available_amount: interval_detail.available_amount,
                 assigned_amount: interval_detail.assigned_amount,
                 activity_amount: interval_detail.activity_amount,
             });
@@ -46,13 +53,18 @@ pub async fn get_budget_info(ledger: State<Arc<RwLock<Ledger>>>, paths: Path<(St
     let Some(budget) = operations.all_budgets()?.into_iter().find(|budget| budget.name.eq(&budget_name)) else {
         return ResponseWrapper::not_found();
     };
-    let interval = params.as_interval();
-    let interval_detail = operations.budget_month_detail(&budget.name, interval)?.unwrap_or(BudgetIntervalDetail {
+    let interval = budget.period.interval(params.as_date());
+    let Some((interval_start, interval_end)) = budget.period.interval_range(interval) else {
+        return ResponseWrapper::bad_request();
+    };
+    let interval_detail = operations.budget_interval_detail(&budget.name, interval)?.unwrap_or(BudgetIntervalDetail {
         date: interval,
         events: vec![],
         assigned_amount: Amount::zero(&budget.commodity),
         activity_amount: Amount::zero(&budget.commodity),
+        available_amount: Amount::zero(&budget.commodity),
     });
+    let targets = operations.budget_target_progress(&budget.name, interval)?;
     let store = operations.store.read().unwrap();
     let related_accounts = store
         .metas
@@ -67,28 +79,44 @@ pub async fn get_budget_info(ledger: State<Arc<RwLock<Ledger>>>, paths: Path<(St
         alias: budget.alias,
         category: budget.category,
         closed: budget.closed,
+        rollover: budget.rollover,
+        period: budget.period,
+        interval_start,
+        interval_end,
         related_accounts,
-        available_amount: interval_detail.assigned_amount.sub(interval_detail.activity_amount.number.clone()),
+        targets,
+        available_amount: interval_detail.available_amount,
         assigned_amount: interval_detail.assigned_amount,
         activity_amount: interval_detail.activity_amount,
     })
 }
 
+/// events and postings of the interval, `index` is the ISO week, the month or the quarter in the year according to the period of budget,
+/// and always `1` for yearly budgets
 pub async fn get_budget_interval_detail(ledger: State<Arc<RwLock<Ledger>>>, paths: Path<(String, u32, u32)>) -> ApiResult<Vec<BudgetIntervalEventResponse>> {
-    let (budget_name, year, month) = paths.0;
+    let (budget_name, year, index) = paths.0;
     let ledger = ledger.read().await;
     let operations = ledger.operations();
 
-    if !operations.all_budgets()?.into_iter().any(|budget| budget.name.eq(&budget_name)) {
+    let Some(budget) = operations.all_budgets()?.into_iter().find(|budget| budget.name.eq(&budget_name)) else {
         return ResponseWrapper::not_found();
     };
-    let date = NaiveDate::from_ymd_opt(year as i32, month, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
-    let datetime = date.and_local_timezone(ledger.options.timezone).unwrap();
-    let month_beginning = datetime.beginning_of_month();
-    let month_end = datetime.end_of_month();
-    let interval = year * 100 + month;
+    // the index is the last two digits of interval, like `12` of `202312`
+    if index >= 100 {
+        return ResponseWrapper::bad_request();
+    }
+    let Some(interval) = year.checked_mul(100).map(|year| year + index) else {
+        return ResponseWrapper::bad_request();
+    };
+    let Some((interval_start, interval_end)) = budget.period.interval_range(interval) else {
+        return ResponseWrapper::bad_request();
+    };
+    let timezone = ledger.options.timezone;
+    let (Some(interval_beginning), Some(interval_ending)) = (beginning_of_date(&timezone, interval_start), end_of_date(&timezone, interval_end)) else {
+        return ResponseWrapper::bad_request();
+    };
     let budget_events = operations
-        .budget_month_detail(&budget_name, interval)?
+        .budget_interval_detail(&budget_name, interval)?
         .map(|interval| interval.events)
         .unwrap_or_default()
         .into_iter()
@@ -105,7 +133,7 @@ pub async fn get_budget_interval_detail(ledger: State<Arc<RwLock<Ledger>>>, path
         .map(|meta| meta.type_identifier.clone())
         .collect_vec();
     let journals = operations
-        .accounts_dated_journals(&related_accounts, month_beginning, month_end)?
+        .accounts_dated_journals(&related_accounts, interval_beginning, interval_ending)?
         .into_iter()
         .map(BudgetIntervalEventResponse::Posting)
         .collect_vec();
@@ -1,13 +1,21 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use axum::extract::{Path, State};
+use bigdecimal::BigDecimal;
 use itertools::Itertools;
 use tokio::sync::RwLock;
+use zhang_ast::amount::Amount;
 use zhang_core::constants::COMMODITY_GROUP;
 use zhang_core::domains::schemas::{CommodityDomain, MetaType};
 use zhang_core::ledger::Ledger;
 
-use crate::response::{CommodityDetailResponse, CommodityListItemResponse, CommodityLotResponse, CommodityPrice, ResponseWrapper};
+use super::Query;
+use crate::request::RealizedGainsRequest;
+use crate::response::{
+    AmountResponse, CommodityDetailResponse, CommodityListItemResponse, CommodityLotResponse, CommodityPrice, RealizedGainItemResponse, RealizedGainsResponse,
+    ResponseWrapper,
+};
 use crate::ApiResult;
 
 pub async fn get_all_commodities(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<Vec<CommodityListItemResponse>> {
@@ -87,6 +95,7 @@ pub async fn get_single_commodity(ledger: State<Arc<RwLock<Ledger>>>, params: Pa
             datetime: price.datetime,
             amount: price.amount,
             target_commodity: Some(price.target_commodity),
+            source: price.source,
         })
         .collect_vec();
 
@@ -96,3 +105,40 @@ pub async fn get_single_commodity(ledger: State<Arc<RwLock<Ledger>>>, params: Pa
         prices,
     })
 }
+
+pub async fn get_realized_gains(ledger: State<Arc<RwLock<Ledger>>>, params: Query<RealizedGainsRequest>) -> ApiResult<RealizedGainsResponse> {
+    let ledger = ledger.read().await;
+    let operations = ledger.operations();
+
+    let gains = operations.realized_gains(params.0.from, params.0.to)?;
+    let formatter = operations.amount_formatter();
+
+    let mut total: BTreeMap<String, BigDecimal> = BTreeMap::new();
+    for gain in gains.iter() {
+        let entry = total.entry(gain.gain.currency.clone()).or_default();
+        *entry += &gain.gain.number;
+    }
+
+    let gains = gains
+        .into_iter()
+        .map(|gain| RealizedGainItemResponse {
+            trx_id: gain.trx_id,
+            datetime: gain.datetime.naive_local(),
+            account: gain.account.name().to_owned(),
+            commodity: gain.commodity,
+            amount: gain.amount,
+            cost: AmountResponse::new(gain.cost, &formatter),
+            price: AmountResponse::new(gain.price, &formatter),
+            acquisition_date: gain.acquisition_date,
+            gain: AmountResponse::new(gain.gain, &formatter),
+        })
+        .collect_vec();
+
+    ResponseWrapper::json(RealizedGainsResponse {
+        total: total
+            .into_iter()
+            .map(|(commodity, number)| AmountResponse::new(Amount::new(number, commodity), &formatter))
+            .collect_vec(),
+        gains,
+    })
+}
@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use itertools::Itertools;
+use tokio::sync::RwLock;
+use zhang_core::ledger::Ledger;
+
+use crate::response::{CustomResponse, ResponseWrapper};
+use crate::ApiResult;
+
+pub async fn get_customs(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(String,)>) -> ApiResult<Vec<CustomResponse>> {
+    let custom_type = path.0 .0;
+    let ledger = ledger.read().await;
+    let operations = ledger.operations();
+
+    let customs = operations.customs(custom_type)?.into_iter().map(CustomResponse::from).collect_vec();
+    ResponseWrapper::json(customs)
+}
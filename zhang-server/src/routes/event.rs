@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use itertools::Itertools;
+use tokio::sync::RwLock;
+use zhang_core::ledger::Ledger;
+use zhang_core::utils::date_range::end_of_date;
+
+use super::Query;
+use crate::request::EventRequest;
+use crate::response::{EventResponse, EventTimelineResponse, ResponseWrapper};
+use crate::ApiResult;
+
+/// the end of the requested date in ledger's timezone, or now if date is not given.
+/// `None` if the end of the requested date does not exist in ledger's timezone
+fn requested_datetime(ledger: &Ledger, params: &EventRequest) -> Option<DateTime<Tz>> {
+    let timezone = &ledger.options.timezone;
+    match params.date {
+        Some(date) => end_of_date(timezone, date),
+        None => Some(Utc::now().with_timezone(timezone)),
+    }
+}
+
+pub async fn get_events(ledger: State<Arc<RwLock<Ledger>>>, params: Query<EventRequest>) -> ApiResult<Vec<EventResponse>> {
+    let ledger = ledger.read().await;
+    let operations = ledger.operations();
+    let Some(datetime) = requested_datetime(&ledger, &params.0) else {
+        return ResponseWrapper::bad_request();
+    };
+
+    let events = operations.active_events(datetime)?.into_iter().map(EventResponse::from).collect_vec();
+    ResponseWrapper::json(events)
+}
+
+pub async fn get_event_timeline(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(String,)>, params: Query<EventRequest>) -> ApiResult<EventTimelineResponse> {
+    let event_type = path.0 .0;
+    let ledger = ledger.read().await;
+    let operations = ledger.operations();
+    let Some(datetime) = requested_datetime(&ledger, &params.0) else {
+        return ResponseWrapper::bad_request();
+    };
+
+    let timeline = operations.events(&event_type)?;
+    if timeline.is_empty() {
+        return ResponseWrapper::not_found();
+    }
+    let current = operations.event_value(&event_type, datetime)?.map(EventResponse::from);
+
+    ResponseWrapper::json(EventTimelineResponse {
+        event_type,
+        current,
+        timeline: timeline.into_iter().map(EventResponse::from).collect_vec(),
+    })
+}
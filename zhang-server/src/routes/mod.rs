@@ -2,8 +2,11 @@ pub mod account;
 pub mod budget;
 pub mod commodity;
 pub mod common;
+pub mod custom;
 pub mod document;
+pub mod event;
 pub mod file;
+pub mod query;
 pub mod statistics;
 pub mod transaction;
 
@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use tokio::sync::RwLock;
+use zhang_core::ledger::Ledger;
+use zhang_core::query::QueryResult;
+use zhang_core::ZhangError;
+
+use super::Query;
+use crate::request::QueryRequest;
+use crate::response::ResponseWrapper;
+use crate::ApiResult;
+
+pub async fn execute_query(ledger: State<Arc<RwLock<Ledger>>>, params: Query<QueryRequest>) -> ApiResult<QueryResult> {
+    let ledger = ledger.read().await;
+    match ledger.operations().query(&params.0.query) {
+        Ok(result) => ResponseWrapper::json(result),
+        Err(ZhangError::InvalidQuery(message)) => ResponseWrapper::bad_request_with_message(message),
+        Err(e) => Err(e.into()),
+    }
+}
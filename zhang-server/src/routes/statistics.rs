@@ -1,21 +1,27 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
-use chrono::Utc;
+use chrono::{NaiveTime, Utc};
 use itertools::Itertools;
 use tokio::sync::RwLock;
 use zhang_ast::amount::Amount;
-use zhang_ast::{Account, AccountType, Flag};
+use zhang_ast::{Account, AccountType, Directive, Flag, Transaction};
 use zhang_core::ledger::Ledger;
 use zhang_core::utils::calculable::Calculable;
-use zhang_core::utils::date_range::NaiveDateRange;
+use zhang_core::utils::date_range::{end_of_date, NaiveDateRange};
+use zhang_core::{ZhangError, ZhangResult};
 
-use crate::request::{StatisticGraphRequest, StatisticRequest};
-use crate::response::{ReportRankItemResponse, ResponseWrapper, StatisticGraphResponse, StatisticRankResponse, StatisticSummaryResponse};
+use crate::request::{ForecastRequest, PeriodClosingRequest, StatisticGraphRequest, StatisticRequest};
+use crate::response::{
+    AmountResponse, PeriodClosingResponse, ReportRankItemResponse, ResponseWrapper, StatisticGraphResponse, StatisticRankResponse, StatisticSummaryResponse,
+};
 use crate::ApiResult;
 
+const DEFAULT_FORECAST_MONTHS: u32 = 12;
+const MAX_FORECAST_MONTHS: u32 = 120;
+
 pub async fn get_statistic_summary(ledger: State<Arc<RwLock<Ledger>>>, params: Query<StatisticRequest>) -> ApiResult<StatisticSummaryResponse> {
     let ledger = ledger.read().await;
     let timezone = &ledger.options.timezone;
@@ -148,6 +154,55 @@ pub async fn get_statistic_graph(ledger: State<Arc<RwLock<Ledger>>>, params: Que
     })
 }
 
+/// projected balances of assets and liabilities at the end of each month, and the changes of projected transactions
+pub async fn get_statistic_forecast(ledger: State<Arc<RwLock<Ledger>>>, params: Query<ForecastRequest>) -> ApiResult<StatisticGraphResponse> {
+    let ledger = ledger.read().await;
+    let timezone = &ledger.options.timezone;
+    let mut operations = ledger.operations();
+    let months = params.months.unwrap_or(DEFAULT_FORECAST_MONTHS);
+    if months > MAX_FORECAST_MONTHS {
+        return ResponseWrapper::bad_request();
+    }
+    let forecast = ledger.forecast(months)?;
+
+    let mut dated_balance = HashMap::new();
+    for (date, account_balances) in forecast.balances.into_iter() {
+        let balances = account_balances
+            .into_iter()
+            .filter(|it| it.account.account_type == AccountType::Assets || it.account.account_type == AccountType::Liabilities)
+            .flat_map(|it| it.balances)
+            .collect_vec();
+        let datetime = end_of_date(timezone, date).ok_or(ZhangError::InvalidDate)?;
+        let balance = balances.calculate(datetime, &mut operations)?;
+        dated_balance.insert(date, balance);
+    }
+
+    let mut dated_change = HashMap::new();
+    for posting in forecast.postings {
+        let account_type_store = dated_change.entry(posting.date).or_insert_with(HashMap::new);
+        let currency_store = account_type_store.entry(posting.account.account_type).or_insert_with(Vec::new);
+        currency_store.push(posting.amount);
+    }
+
+    let mut dated_change_ret = HashMap::new();
+    for (date, account_type_store) in dated_change.into_iter() {
+        let datetime = end_of_date(timezone, date).ok_or(ZhangError::InvalidDate)?;
+        let mut r = HashMap::new();
+        for (account_type, currency_store) in account_type_store.into_iter() {
+            let amount = currency_store.calculate(datetime, &mut operations)?;
+            r.insert(account_type, amount);
+        }
+        dated_change_ret.insert(date, r);
+    }
+
+    ResponseWrapper::json(StatisticGraphResponse {
+        from: forecast.from.and_time(NaiveTime::default()),
+        to: forecast.to.and_hms_opt(23, 59, 59).unwrap(),
+        balances: dated_balance,
+        changes: dated_change_ret,
+    })
+}
+
 pub async fn get_statistic_rank_detail_by_account_type(
     ledger: State<Arc<RwLock<Ledger>>>, paths: Path<(String,)>, params: Query<StatisticRequest>,
 ) -> ApiResult<StatisticRankResponse> {
@@ -192,3 +247,39 @@ pub async fn get_statistic_rank_detail_by_account_type(
         top_transactions,
     })
 }
+
+/// the balance sheet at the end of period after clearing income and expenses into equity, with the closing and opening transactions
+pub async fn get_statistic_closing(ledger: State<Arc<RwLock<Ledger>>>, params: Query<PeriodClosingRequest>) -> ApiResult<PeriodClosingResponse> {
+    let ledger = ledger.read().await;
+    let closing = ledger.close_period(params.from, params.to)?;
+    let formatter = ledger.operations().amount_formatter();
+
+    let export_directive = |directive: Directive| -> ZhangResult<String> {
+        let content = ledger.data_source.export(formatter.pad_directive(directive))?;
+        Ok(String::from_utf8_lossy(&content).to_string())
+    };
+    let export = |trx: &Option<Transaction>| -> ZhangResult<Option<String>> {
+        trx.as_ref().map(|trx| export_directive(Directive::Transaction(trx.clone()))).transpose()
+    };
+    let balance_sheet = closing
+        .balance_sheet
+        .iter()
+        .map(|(account, amounts)| {
+            let amounts = amounts.iter().map(|amount| AmountResponse::new(amount.clone(), &formatter)).collect_vec();
+            (account.clone(), amounts)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    ResponseWrapper::json(PeriodClosingResponse {
+        from: closing.from,
+        to: closing.to,
+        balance_sheet,
+        opens: closing
+            .opens
+            .iter()
+            .map(|open| export_directive(Directive::Open(open.clone())))
+            .collect::<ZhangResult<Vec<_>>>()?,
+        closing: export(&closing.closing)?,
+        opening: export(&closing.opening)?,
+    })
+}
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use axum::extract::{Multipart, Path, State};
 use axum::Json;
 use indexmap::IndexSet;
-use itertools::Itertools;
+use itertools::{Either, Itertools};
 use log::info;
 use tokio::sync::RwLock;
 use uuid::Uuid;
@@ -14,14 +14,14 @@ use zhang_ast::{Account, Date, Directive, Flag, Meta, Posting, SpanInfo, Transac
 use zhang_core::constants::TXN_ID;
 use zhang_core::domains::schemas::MetaType;
 use zhang_core::ledger::Ledger;
-use zhang_core::store::TransactionDomain;
+use zhang_core::store::{NoteDomain, TransactionDomain};
 use zhang_core::utils::string_::{escape_with_quote, StringExt};
 
 use super::Query;
 use crate::request::{CreateTransactionRequest, JournalRequest};
 use crate::response::{
-    InfoForNewTransaction, JournalBalanceCheckItemResponse, JournalBalancePadItemResponse, JournalItemResponse, JournalTransactionItemResponse,
-    JournalTransactionPostingResponse, Pageable, ResponseWrapper,
+    InfoForNewTransaction, JournalBalanceCheckItemResponse, JournalBalancePadItemResponse, JournalItemResponse, JournalNoteItemResponse,
+    JournalTransactionItemResponse, JournalTransactionPostingResponse, Pageable, ResponseWrapper,
 };
 use crate::{ApiResult, ReloadSender};
 
@@ -46,25 +46,39 @@ pub async fn get_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Query<Jour
 
     let store = operations.read();
 
-    let total_count = store
+    let transactions = store
         .transactions
         .values()
         .filter(|it| it.match_keywords(params.keyword.as_ref(), &params.tags, &params.links))
-        .count();
-
-    let journals: Vec<TransactionDomain> = store
-        .transactions
-        .values()
+        .map(|it| ((it.datetime, false, it.sequence), Either::Left(it)));
+    let notes = store
+        .notes
+        .iter()
         .filter(|it| it.match_keywords(params.keyword.as_ref(), &params.tags, &params.links))
-        .sorted_by_key(|it| -it.sequence)
+        .map(|it| ((it.datetime, true, it.sequence), Either::Right(it)));
+    let journals = transactions.chain(notes).collect_vec();
+    let total_count = journals.len();
+
+    // notes are numbered apart from transactions, so journals are ordered by datetime first,
+    // and notes go after the transactions of the same datetime
+    let journals: Vec<Either<TransactionDomain, NoteDomain>> = journals
+        .into_iter()
+        .sorted_by(|(a_key, _), (b_key, _)| a_key.cmp(b_key).reverse())
         .skip(params.offset() as usize)
         .take(params.limit() as usize)
-        .cloned()
+        .map(|(_, it)| it.map_either(Clone::clone, Clone::clone))
         .collect_vec();
 
     drop(store);
     let mut ret = vec![];
     for journal_item in journals {
+        let journal_item = match journal_item {
+            Either::Left(trx) => trx,
+            Either::Right(note) => {
+                ret.push(JournalItemResponse::Note(JournalNoteItemResponse::from(note)));
+                continue;
+            }
+        };
         let item = match journal_item.flag {
             Flag::BalancePad => {
                 let postings = journal_item.postings.into_iter().map(JournalTransactionPostingResponse::from).collect_vec();
@@ -102,6 +116,11 @@ pub async fn get_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Query<Jour
                     .errors_by_meta(TXN_ID, &journal_item.id.to_string())?
                     .iter()
                     .any(|error| error.error_type == ErrorKind::UnbalancedTransaction);
+                let events = operations
+                    .active_events(journal_item.datetime)?
+                    .into_iter()
+                    .map(|event| (event.event_type, event.description))
+                    .collect();
 
                 JournalItemResponse::Transaction(JournalTransactionItemResponse {
                     id: journal_item.id,
@@ -115,13 +134,12 @@ pub async fn get_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Query<Jour
                     is_balanced: !has_unbalanced_error,
                     postings,
                     metas,
+                    events,
                 })
             }
         };
         ret.push(item);
     }
-    ret.sort_by_key(|item| item.sequence());
-    ret.reverse();
     ResponseWrapper::json(Pageable::new(total_count as u32, params.page(), params.limit(), ret))
 }
 
@@ -254,7 +272,7 @@ pub async fn update_single_transaction(
         postings,
         meta: metas,
     });
-    let txn_content = ledger.data_source.export(trx)?;
+    let txn_content = ledger.data_source.export(ledger.operations().amount_formatter().pad_directive(trx))?;
     let trx_content = String::from_utf8_lossy(&txn_content);
     let source_file_path = span_info.source_file.to_string_lossy().to_string();
 